@@ -1,5 +1,7 @@
 fn main() {
     linker_be_nice();
+    // embedded-test collects the test cases through its own linker script
+    println!("cargo:rustc-link-arg-tests=-Tembedded-test.x");
     println!("cargo:rustc-link-arg=-Tdefmt.x");
     // make sure linkall.x is the last linker script (otherwise might cause problems with flip-link)
     println!("cargo:rustc-link-arg=-Tlinkall.x");
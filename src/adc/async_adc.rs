@@ -0,0 +1,123 @@
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::spi::{SpiBus, SpiDevice};
+use crate::adc::frame::Framer;
+use crate::adc::register::{AdcModeRegister, DataRegister, InterfaceModeRegister, Register, WritableRegister};
+use crate::adc::{AdcError, RESET_DELAY_US};
+use crate::device_bus::DeviceBus;
+
+/// Async counterpart of [`ADC`](crate::adc::ADC), awaiting SPI transfers instead of blocking.
+///
+/// Register framing, checksums and interface configuration tracking are shared with the
+/// blocking driver, so both behave the same on the wire.
+#[derive(Debug)]
+pub struct AsyncADC<Bus: SpiBus> {
+    spi: Bus,
+    framer: Framer,
+}
+
+impl<Device: SpiDevice> AsyncADC<DeviceBus<Device>> {
+    /// Creates the driver on a shared bus, with chip select handled per transaction by `device`.
+    pub fn new_with_device(device: Device) -> Self {
+        Self::new(DeviceBus::new(device))
+    }
+}
+
+impl<Bus: SpiBus> AsyncADC<Bus> {
+    pub fn new(spi: Bus) -> Self {
+        Self {
+            spi,
+            framer: Framer::new(),
+        }
+    }
+
+    /// See [`ADC::interface_mode`](crate::adc::ADC::interface_mode).
+    pub fn interface_mode(&self) -> InterfaceModeRegister {
+        self.framer.interface_mode
+    }
+
+    /// See [`ADC::adc_mode`](crate::adc::ADC::adc_mode).
+    pub fn adc_mode(&self) -> AdcModeRegister {
+        self.framer.adc_mode
+    }
+
+    /// Reads a register, see [`ADC::read`](crate::adc::ADC::read).
+    pub async fn read<const N: usize, T: Register<N>>(&mut self) -> Result<T, AdcError<Bus::Error>> {
+        let len = self.framer.start_read::<_, N, T>()?;
+        self.spi.transfer_in_place(&mut self.framer.buf[..len]).await?;
+        self.framer.finish_read()
+    }
+
+    /// Reads a register along with its raw bytes, see
+    /// [`ADC::read_with_raw`](crate::adc::ADC::read_with_raw).
+    pub async fn read_with_raw<const N: usize, T: Register<N>>(&mut self) -> Result<(T, [u8; N]), AdcError<Bus::Error>> {
+        let register = self.read::<N, T>().await?;
+        Ok((register, self.framer.read_payload()))
+    }
+
+    /// Reads a register without decoding it, see [`ADC::read_raw`](crate::adc::ADC::read_raw).
+    pub async fn read_raw(&mut self, id: u8, len: usize) -> Result<&[u8], AdcError<Bus::Error>> {
+        let transfer_len = self.framer.start_read_raw(id, len)?;
+        self.spi.transfer_in_place(&mut self.framer.buf[..transfer_len]).await?;
+        self.framer.finish_read_raw(id, len)
+    }
+
+    /// Writes a register, see [`ADC::write`](crate::adc::ADC::write).
+    pub async fn write<const N: usize, T: WritableRegister<N>>(&mut self, register: &T) -> Result<(), AdcError<Bus::Error>> {
+        let len = self.framer.start_write(register)?;
+        self.spi.write(&self.framer.buf[..len]).await?;
+        self.framer.finish_write(T::get_id());
+
+        Ok(())
+    }
+
+    /// Resets the device, see [`ADC::reset`](crate::adc::ADC::reset).
+    pub async fn reset(&mut self, delay: &mut impl DelayNs) -> Result<(), AdcError<Bus::Error>> {
+        self.spi.write(&[0xff; 8]).await?;
+        self.spi.flush().await?;
+        self.framer.reset();
+
+        delay.delay_us(RESET_DELAY_US).await;
+
+        Ok(())
+    }
+
+    /// Reads the latest conversion, see [`ADC::read_data`](crate::adc::ADC::read_data).
+    pub async fn read_data(&mut self) -> Result<DataRegister, AdcError<Bus::Error>> {
+        let len = self.framer.start_read_data(false)?;
+        self.spi.transfer_in_place(&mut self.framer.buf[..len]).await?;
+        self.framer.finish_read_data()
+    }
+
+    /// Enables continuous read mode, see [`ADC::start_continuous_read`](crate::adc::ADC::start_continuous_read).
+    pub async fn start_continuous_read(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        let interface_mode = self.read::<2, InterfaceModeRegister>().await?.with_cont_read(true);
+        self.write(&interface_mode).await
+    }
+
+    /// Clocks out the next conversion, see [`ADC::read_next`](crate::adc::ADC::read_next).
+    pub async fn read_next(&mut self) -> Result<DataRegister, AdcError<Bus::Error>> {
+        let frame = self.framer.start_read_next()?;
+        self.spi.transfer_in_place(&mut self.framer.buf[frame]).await?;
+        self.framer.finish_read_next()
+    }
+
+    /// Waits for the next conversion to complete and clocks it out.
+    ///
+    /// `rdy` has to observe the DOUT/RDY line, which goes low when a conversion is ready. As
+    /// that line doubles as the SPI data output it is typically wired to a second GPIO as well.
+    pub async fn wait_and_read_next<Rdy: Wait>(&mut self, rdy: &mut Rdy) -> Result<DataRegister, AdcError<Bus::Error>> {
+        // GPIO inputs can't fail to wait, and the error type wouldn't fit AdcError anyway
+        let _ = rdy.wait_for_low().await;
+        self.read_next().await
+    }
+
+    /// Leaves continuous read mode, see [`ADC::stop_continuous_read`](crate::adc::ADC::stop_continuous_read).
+    pub async fn stop_continuous_read(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        let len = self.framer.start_stop_continuous_read()?;
+        self.spi.transfer_in_place(&mut self.framer.buf[..len]).await?;
+        self.framer.finish_stop_continuous_read();
+
+        Ok(())
+    }
+}
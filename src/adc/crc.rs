@@ -0,0 +1,29 @@
+/// CRC-8 polynomial x^8 + x^2 + x + 1, with the implicit x^8 term dropped.
+const POLYNOMIAL: u8 = 0x07;
+
+/// Computes the polynomial based checksum the AD7175-2 appends to register transactions.
+///
+/// The checksum covers the command byte followed by the register data, see the
+/// "CRC Calculation" section of the datasheet.
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ POLYNOMIAL
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Computes the XOR checksum the AD7175-2 appends to reads when CRC_EN selects XOR mode.
+///
+/// Cheaper than [`crc8`] but blind to errors that cancel out, e.g. the same bit flipped in two
+/// bytes. Like the CRC it covers the command byte followed by the register data.
+pub fn xor8(data: &[u8]) -> u8 {
+    data.iter().fold(0, |checksum, byte| checksum ^ byte)
+}
@@ -0,0 +1,29 @@
+use esp_hal::gpio::{Input, InputConfig, InputPin, Pull};
+
+/// Wakes the caller when the ADC signals a finished conversion on DOUT/RDY.
+///
+/// DOUT/RDY doubles as the SPI data output, so this needs a second GPIO wired to the same line
+/// next to the one used as MISO. The line is only driven while CS is low and floats otherwise,
+/// which is why the pin is pulled up; with a hardware CS that is only asserted during transfers
+/// RDY is never seen, so CS has to be held low between transfers (or tied low) for this to work.
+#[derive(Debug)]
+pub struct DataReady<'d> {
+    pin: Input<'d>,
+}
+
+impl<'d> DataReady<'d> {
+    pub fn new(pin: impl InputPin + 'd) -> Self {
+        Self {
+            pin: Input::new(pin, InputConfig::default().with_pull(Pull::Up)),
+        }
+    }
+
+    /// Waits for DOUT/RDY to fall, meaning a new conversion can be read.
+    ///
+    /// Call this after the previous conversion was read out, RDY returns high once that
+    /// happens. The line toggles with the data bits during transfers, so don't wait concurrently
+    /// with a transfer on the same bus.
+    pub async fn wait_for_data(&mut self) {
+        self.pin.wait_for_falling_edge().await;
+    }
+}
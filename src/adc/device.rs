@@ -0,0 +1,87 @@
+//! The parts of the AD7175 family the driver can talk to.
+//!
+//! The AD7175-2 and AD7175-8 share their register map, the -8 only has more of the per-channel
+//! and per-setup registers at the addresses after those of the -2. [`Channel`], [`Setup`] and
+//! [`Input`] name everything the larger part has, and the part selected with
+//! [`ADC::with_part`] decides which of them can be used.
+//!
+//! Besides the ID [`ADC::check_id`] expects, the driver checks every register it reads or
+//! writes against the part: a channel, setup or analog input past the part's own fails with
+//! [`AdcError::NotOnPart`] before anything reaches the bus, so channel 7 can't be configured on
+//! an AD7175-2 while the AD7175-8 gets all sixteen.
+//!
+//! [`ADC::with_part`]: crate::adc::ADC::with_part
+//! [`ADC::check_id`]: crate::adc::ADC::check_id
+//! [`AdcError::NotOnPart`]: crate::adc::AdcError::NotOnPart
+
+use crate::adc::{Channel, Input, Setup};
+
+/// Channel registers in the register map, CH0 to CH15.
+pub const MAX_CHANNELS: usize = 16;
+/// Setups in the register map, each with its own four registers.
+pub const MAX_SETUPS: usize = 8;
+/// Analog inputs the input multiplexer can select, AIN0 to AIN16.
+pub const MAX_ANALOG_INPUTS: usize = 17;
+
+/// What a part of the family has, selected with [`ADC::with_part`](crate::adc::ADC::with_part).
+pub trait Device {
+    /// Name used in logs and error messages.
+    const NAME: &'static str;
+    /// Contents of the ID register, the lowest four bits masked out as they vary between units.
+    const ID: u16;
+    /// Channel registers, CH0 upwards.
+    const CHANNELS: usize;
+    /// Setups, each with its own setup configuration, filter, offset and gain registers.
+    const SETUPS: usize;
+    /// Analog inputs, AIN0 upwards.
+    const ANALOG_INPUTS: usize;
+
+    /// Whether the part has `channel`.
+    fn has_channel(channel: Channel) -> bool {
+        (channel.into_bits() as usize) < Self::CHANNELS
+    }
+
+    /// Whether the part has `setup`.
+    fn has_setup(setup: Setup) -> bool {
+        (setup.into_bits() as usize) < Self::SETUPS
+    }
+
+    /// Whether the part has `input`. The temperature sensor, supply and reference inputs are on
+    /// every part.
+    fn has_input(input: Input) -> bool {
+        input.partner().is_some() || (input.into_bits() as usize) < Self::ANALOG_INPUTS
+    }
+}
+
+/// The AD7175-2: four channels, four setups and five analog inputs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ad7175_2;
+
+impl Device for Ad7175_2 {
+    const NAME: &'static str = "AD7175-2";
+    const ID: u16 = 0x0cd0;
+    const CHANNELS: usize = 4;
+    const SETUPS: usize = 4;
+    const ANALOG_INPUTS: usize = 5;
+}
+
+/// The AD7175-8: sixteen channels, eight setups and seventeen analog inputs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ad7175_8;
+
+impl Device for Ad7175_8 {
+    const NAME: &'static str = "AD7175-8";
+    const ID: u16 = 0x3cd0;
+    const CHANNELS: usize = 16;
+    const SETUPS: usize = 8;
+    const ANALOG_INPUTS: usize = 17;
+}
+
+/// Fails the build for a part whose counts don't fit the register map, which the driver's
+/// per-channel and per-setup state is sized for, or that lacks the channel 0 and setup 0 the
+/// single conversion helpers use.
+pub(crate) const fn assert_fits_register_map<Part: Device>() {
+    assert!(Part::CHANNELS >= 1 && Part::CHANNELS <= MAX_CHANNELS, "part needs between 1 and MAX_CHANNELS channels");
+    assert!(Part::SETUPS >= 1 && Part::SETUPS <= MAX_SETUPS, "part needs between 1 and MAX_SETUPS setups");
+    assert!(Part::ANALOG_INPUTS <= MAX_ANALOG_INPUTS, "part has more analog inputs than the multiplexer can select");
+}
@@ -0,0 +1,309 @@
+use defmt::debug;
+use crate::adc::{crc, AdcError, Crc, DataRegisterLength, OutputCoding, ReadConfiguration, Setup};
+use crate::adc::device::{MAX_CHANNELS, MAX_SETUPS};
+use crate::adc::register::{register_name, AdcModeRegister, Channel0Register, Channel15Register, DataAndStatusRegister, DataRegister, InterfaceModeRegister, Register, RegisterRW, SetupConfig0Register, SetupConfig7Register, WritableRegister};
+
+/// Size of the transfer buffer: a command byte, the widest register (data followed by status,
+/// 4 bytes) and a checksum byte.
+pub const FRAME_BUF_LEN: usize = 6;
+
+/// Fails the build when a register of `N` bytes plus the command and checksum bytes wouldn't
+/// fit the transfer buffer, instead of panicking on the first transfer.
+const fn assert_fits<const N: usize>() {
+    assert!(N + 2 <= FRAME_BUF_LEN, "register too wide for the ADC transfer buffer");
+}
+
+/// Transport independent half of the ADC drivers.
+///
+/// Lays out register transactions in a shared buffer, checks the bytes that come back and keeps
+/// track of the interface configuration the framing depends on. The blocking and async drivers
+/// only differ in how the buffer gets on and off the bus.
+///
+/// The last written [`InterfaceModeRegister`] and [`AdcModeRegister`] are cached, starting from
+/// their reset values, so the drivers can look at them without a readback. So are the setup
+/// each channel converts with and the output coding of each setup, for decoding samples.
+#[derive(Debug)]
+pub struct Framer {
+    pub buf: [u8; FRAME_BUF_LEN],
+    pub read_configuration: ReadConfiguration,
+    pub interface_mode: InterfaceModeRegister,
+    pub adc_mode: AdcModeRegister,
+    /// Indexed by channel.
+    pub channel_setups: [Setup; MAX_CHANNELS],
+    /// Indexed by setup.
+    pub output_codings: [OutputCoding; MAX_SETUPS],
+}
+
+impl Framer {
+    pub fn new() -> Self {
+        Self {
+            buf: [0; FRAME_BUF_LEN],
+            read_configuration: ReadConfiguration::default(),
+            interface_mode: InterfaceModeRegister::new(),
+            adc_mode: AdcModeRegister::new(),
+            channel_setups: [Channel0Register::new().setup_sel(); MAX_CHANNELS],
+            output_codings: [SetupConfig0Register::new().bi_unipolar(); MAX_SETUPS],
+        }
+    }
+
+    /// Forgets everything written so far, for after the device has been reset.
+    pub fn reset(&mut self) {
+        self.read_configuration = ReadConfiguration::default();
+        self.interface_mode = InterfaceModeRegister::new();
+        self.adc_mode = AdcModeRegister::new();
+        self.channel_setups = [Channel0Register::new().setup_sel(); MAX_CHANNELS];
+        self.output_codings = [SetupConfig0Register::new().bi_unipolar(); MAX_SETUPS];
+    }
+
+    fn check_not_continuous<E>(&self) -> Result<(), AdcError<E>> {
+        if self.read_configuration.data_read_configuration.continuous {
+            return Err(AdcError::ContinuousReadActive);
+        }
+        Ok(())
+    }
+
+    fn check_continuous<E>(&self) -> Result<(), AdcError<E>> {
+        if !self.read_configuration.data_read_configuration.continuous {
+            return Err(AdcError::ContinuousReadInactive);
+        }
+        Ok(())
+    }
+
+    /// Sets up a read of `T`, returning how many bytes of the buffer to transfer in place.
+    pub fn start_read<E, const N: usize, T: Register<N>>(&mut self) -> Result<usize, AdcError<E>> {
+        const { assert_fits::<N>() };
+        self.check_not_continuous()?;
+
+        let id = T::get_id();
+        self.buf[0] = id | RegisterRW::Read as u8;
+
+        debug!("Reading register: {:02x}", id);
+
+        let len = N + 1 + self.read_checksum_len();
+        // keep DIN low while the register is clocked out rather than replaying the last frame
+        self.buf[1..len].fill(0);
+
+        Ok(len)
+    }
+
+    /// Checks and decodes the bytes clocked in by a read set up with [`Framer::start_read`].
+    pub fn finish_read<E, const N: usize, T: Register<N>>(&mut self) -> Result<T, AdcError<E>> {
+        const { assert_fits::<N>() };
+        debug!("Read register: {:012x}", self.buf);
+
+        self.check_read_checksum(T::get_id(), N)?;
+
+        // can't fail, the slice is N bytes long and N fits the buffer
+        let register = T::from_buffer((&self.buf[1..N + 1]).try_into().unwrap());
+        register.validate().map_err(AdcError::InvalidValue)?;
+
+        Ok(register)
+    }
+
+    /// The register bytes of the last read, as clocked in, without the command byte and checksum.
+    pub fn read_payload<const N: usize>(&self) -> [u8; N] {
+        const { assert_fits::<N>() };
+        // can't fail, the slice is N bytes long and N fits the buffer
+        self.buf[1..N + 1].try_into().unwrap()
+    }
+
+    /// Sets up a read of the data register, sized for the configured word length and optionally
+    /// followed by the status byte, returning how many bytes of the buffer to transfer in place.
+    pub fn start_read_data<E>(&mut self, status: bool) -> Result<usize, AdcError<E>> {
+        self.check_not_continuous()?;
+
+        self.buf[0] = DataRegister::get_id() | RegisterRW::Read as u8;
+
+        debug!("Reading data register");
+
+        let len = 1 + self.data_len() + status as usize + self.read_checksum_len();
+        self.buf[1..len].fill(0);
+
+        Ok(len)
+    }
+
+    /// Checks and decodes a conversion read with [`Framer::start_read_data`] without the status
+    /// byte.
+    pub fn finish_read_data<E>(&mut self) -> Result<DataRegister, AdcError<E>> {
+        let data_len = self.data_len();
+        self.check_read_checksum(DataRegister::get_id(), data_len)?;
+
+        Ok(DataRegister::from_buffer(&self.data_word()))
+    }
+
+    /// Checks and decodes a conversion read with [`Framer::start_read_data`] with the status
+    /// byte.
+    pub fn finish_read_data_and_status<E>(&mut self) -> Result<DataAndStatusRegister, AdcError<E>> {
+        let data_len = self.data_len();
+        self.check_read_checksum(DataRegister::get_id(), data_len + 1)?;
+
+        let [high, mid, low] = self.data_word();
+        Ok(DataAndStatusRegister::from_buffer(&[high, mid, low, self.buf[1 + data_len]]))
+    }
+
+    /// Sets up a read of `len` bytes from the register at `id`, returning how many bytes of the
+    /// buffer to transfer in place.
+    pub fn start_read_raw<E>(&mut self, id: u8, len: usize) -> Result<usize, AdcError<E>> {
+        self.check_not_continuous()?;
+
+        let transfer_len = len + 1 + self.read_checksum_len();
+        if transfer_len > self.buf.len() {
+            return Err(AdcError::InvalidLength);
+        }
+
+        self.buf[0] = id | RegisterRW::Read as u8;
+        self.buf[1..transfer_len].fill(0);
+
+        debug!("Reading raw register: {:02x} ({})", id, register_name(id).unwrap_or("unknown"));
+
+        Ok(transfer_len)
+    }
+
+    /// Checks the bytes clocked in by a read set up with [`Framer::start_read_raw`] and returns
+    /// the register's bytes.
+    pub fn finish_read_raw<E>(&mut self, id: u8, len: usize) -> Result<&[u8], AdcError<E>> {
+        debug!("Read raw register: {:012x}", self.buf);
+
+        self.check_read_checksum(id, len)?;
+
+        Ok(&self.buf[1..len + 1])
+    }
+
+    /// Lays out a write of `register`, returning how many bytes of the buffer to send.
+    pub fn start_write<E, const N: usize, T: WritableRegister<N>>(&mut self, register: &T) -> Result<usize, AdcError<E>> {
+        self.start_write_at(T::get_id(), register)
+    }
+
+    /// Lays out a write of `register` to the register at `id`, for registers sharing the layout
+    /// of `T` at another address.
+    pub fn start_write_at<E, const N: usize, T: WritableRegister<N>>(&mut self, id: u8, register: &T) -> Result<usize, AdcError<E>> {
+        const { assert_fits::<N>() };
+        self.check_not_continuous()?;
+        register.check_reserved_bits().map_err(AdcError::ReservedBitsSet)?;
+
+        self.buf[0] = id | RegisterRW::Write as u8;
+        self.buf[1..N + 1].copy_from_slice(&register.to_buffer());
+
+        // register writes are always protected with the polynomial CRC, even in XOR mode
+        let mut len = N + 1;
+        if self.read_configuration.crc != Crc::Disabled {
+            self.buf[len] = crc::crc8(&self.buf[..len]);
+            len += 1;
+        }
+
+        debug!("Writing register: {:02x} {:012x}", id, self.buf);
+
+        Ok(len)
+    }
+
+    /// Records the effect of a write to the register at `id` that made it onto the bus.
+    ///
+    /// Writing the [`InterfaceModeRegister`] changes how later transactions are framed. It and
+    /// the [`AdcModeRegister`] are cached.
+    pub fn finish_write(&mut self, id: u8) {
+        if id == InterfaceModeRegister::get_id() {
+            self.interface_mode = InterfaceModeRegister::from_buffer(&[self.buf[1], self.buf[2]]);
+            self.read_configuration = ReadConfiguration::from(&self.interface_mode);
+        } else if id == AdcModeRegister::get_id() {
+            self.adc_mode = AdcModeRegister::from_buffer(&[self.buf[1], self.buf[2]]);
+        } else if (Channel0Register::get_id()..=Channel15Register::get_id()).contains(&id) {
+            // every channel register shares the layout of channel 0
+            let channel = Channel0Register::from_buffer(&[self.buf[1], self.buf[2]]);
+            self.channel_setups[(id - Channel0Register::get_id()) as usize] = channel.setup_sel();
+        } else if (SetupConfig0Register::get_id()..=SetupConfig7Register::get_id()).contains(&id) {
+            let setup_config = SetupConfig0Register::from_buffer(&[self.buf[1], self.buf[2]]);
+            self.output_codings[(id - SetupConfig0Register::get_id()) as usize] = setup_config.bi_unipolar();
+        }
+    }
+
+    /// Sets up reading the next conversion in continuous read mode, returning the part of the
+    /// buffer to transfer in place.
+    ///
+    /// The frame is placed after a placeholder for the implied data register read command so
+    /// the checksum can be computed over both.
+    pub fn start_read_next<E>(&mut self) -> Result<core::ops::Range<usize>, AdcError<E>> {
+        self.check_continuous()?;
+
+        let len = self.continuous_data_len() + self.read_checksum_len();
+
+        // DIN has to be held low while in continuous read mode
+        self.buf[1..len + 1].fill(0);
+
+        Ok(1..len + 1)
+    }
+
+    /// Checks and decodes a conversion read with [`Framer::start_read_next`].
+    pub fn finish_read_next<E>(&mut self) -> Result<DataRegister, AdcError<E>> {
+        // the checksum accounts for an implied data register read command
+        self.check_read_checksum(DataRegister::get_id(), self.continuous_data_len())?;
+
+        Ok(DataRegister::from_buffer(&self.data_word()))
+    }
+
+    /// Lays out the dummy data register read that ends continuous read mode, returning how many
+    /// bytes of the buffer to transfer in place.
+    ///
+    /// The command goes out in the first byte of a conversion and DIN stays low for the rest of
+    /// it, checksum included. The frame has to be exactly one conversion long: any further
+    /// bytes would reach the device after it has left continuous read and be taken as commands.
+    pub fn start_stop_continuous_read<E>(&mut self) -> Result<usize, AdcError<E>> {
+        self.check_continuous()?;
+
+        self.buf.fill(0);
+        self.buf[0] = DataRegister::get_id() | RegisterRW::Read as u8;
+
+        Ok(self.continuous_data_len() + self.read_checksum_len())
+    }
+
+    /// Records that continuous read mode has been left.
+    pub fn finish_stop_continuous_read(&mut self) {
+        self.read_configuration.data_read_configuration.continuous = false;
+        self.interface_mode = self.interface_mode.with_cont_read(false);
+    }
+
+    /// Length of a conversion in continuous read mode, without the checksum.
+    fn continuous_data_len(&self) -> usize {
+        self.data_len() + self.read_configuration.data_read_configuration.status_included as usize
+    }
+
+    /// Length of the data register for the configured word length.
+    fn data_len(&self) -> usize {
+        match self.read_configuration.data_read_configuration.data_register_length {
+            DataRegisterLength::TwentyFourBits => 3,
+            DataRegisterLength::SixteenBits => 2,
+        }
+    }
+
+    /// Number of checksum bytes the device appends to reads, CRC and XOR both take one.
+    fn read_checksum_len(&self) -> usize {
+        (self.read_configuration.crc != Crc::Disabled) as usize
+    }
+
+    /// Checks the checksum following `len` bytes read from the register at `id`, using the CRC
+    /// or XOR checksum as configured.
+    fn check_read_checksum<E>(&mut self, id: u8, len: usize) -> Result<(), AdcError<E>> {
+        let checksum: fn(&[u8]) -> u8 = match self.read_configuration.crc {
+            Crc::Disabled => return Ok(()),
+            Crc::EnableWithXorOnRead => crc::xor8,
+            Crc::Enable => crc::crc8,
+        };
+
+        // the checksum covers the command byte we sent, not what was clocked in alongside it
+        self.buf[0] = id | RegisterRW::Read as u8;
+        if checksum(&self.buf[..len + 1]) != self.buf[len + 1] {
+            return Err(AdcError::CrcMismatch);
+        }
+        Ok(())
+    }
+
+    /// The data word following the command byte, widened to 24 bits.
+    ///
+    /// A 16 bit word is the top of the 24 bit result, so it is padded with a zero LSB and
+    /// keeps the same scale, midscale and transfer function.
+    fn data_word(&self) -> [u8; 3] {
+        match self.data_len() {
+            2 => [self.buf[1], self.buf[2], 0],
+            _ => [self.buf[1], self.buf[2], self.buf[3]],
+        }
+    }
+}
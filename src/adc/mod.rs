@@ -1,47 +1,379 @@
-use defmt::{debug, Format};
-use embedded_hal::spi::SpiBus;
+use core::marker::PhantomData;
+use core::num::NonZeroU16;
+use defmt::Format;
+use embassy_time::Instant;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::SpiDevice;
 use esp_hal::Blocking;
-use esp_hal::dma::DmaChannelFor;
-use esp_hal::gpio::{InputPin, OutputPin};
+use esp_hal::delay::Delay as BusyDelay;
+use esp_hal::dma::{DmaBufError, DmaChannelFor};
+use esp_hal::gpio::{InputPin, Output, OutputPin};
 use esp_hal::spi::{AnySpi, BitOrder};
 use esp_hal::spi::master::{Config, Instance, Spi, SpiDmaBus};
 use esp_hal::time::Rate;
-use crate::adc::register::{Register, RegisterRW, WritableRegister};
-use crate::initialize_dma_buffers;
+use crate::adc::device::{assert_fits_register_map, Ad7175_2, Device, MAX_CHANNELS, MAX_SETUPS};
+use crate::adc::frame::{Framer, FRAME_BUF_LEN};
+use crate::adc::timestamp::SampleClock;
+use crate::adc::transport::RegisterTransport;
+use crate::device_bus::DeviceBus;
+use crate::adc::register::{AdcModeRegister, Channel0Register, Channel1Register, Channel2Register, Channel3Register, Channel15Register, DataAndStatusRegister, DataRegister, DefaultFilterConfig0Register, DefaultFilterConfig1Register, DefaultFilterConfig2Register, DefaultFilterConfig3Register, DirectSinc3MapFilterConfig0Register, DirectSinc3MapFilterConfig1Register, DirectSinc3MapFilterConfig2Register, DirectSinc3MapFilterConfig3Register, Gain0Register, Gain7Register, GPIOConfigRegister, IdRegister, InterfaceModeRegister, Offset0Register, Register, RegisterCheck, ReservedBitsSet, SetupConfig0Register, SetupConfig1Register, SetupConfig2Register, RegisterBatch, SetupConfig3Register, StatusRegister, WritableRegister, INTERNAL_MODULATOR_FREQUENCY_HZ};
+use crate::dma_bus_buffers;
 
+#[cfg(feature = "async")]
+pub mod async_adc;
+pub mod crc;
+pub mod data_ready;
+pub mod device;
+mod frame;
 pub mod register;
+pub mod scaling;
+pub mod setup_builder;
+pub mod timestamp;
+pub mod transport;
 
+/// Time the device needs after a reset before it can be addressed again.
+const RESET_DELAY_US: u32 = 500;
+
+/// Internal reference turn-on settling time with the recommended 100 nF on REFOUT (datasheet
+/// Table 1).
+const REFERENCE_SETTLE_US: u32 = 200;
+
+/// External crystal startup time (datasheet Table 1, typical).
+const CRYSTAL_STARTUP_US: u32 = 10;
+
+/// The low nibble of the ID register varies between units, only the upper bits identify the part.
+const ID_MASK: u16 = 0xfff0;
+
+/// DMA buffer sizes of the bus created by [`ADC::new_with_peripherals`]. Register transfers are
+/// a handful of bytes, the receive side is sized for streaming conversions.
+const DMA_RX_BUFFER_SIZE: usize = 4092;
+const DMA_TX_BUFFER_SIZE: usize = 64;
+
+/// SCLK rate used by [`ADC::get_spi_config`] and [`ADC::new_with_peripherals`].
+pub const DEFAULT_SCLK_RATE: Rate = Rate::from_mhz(10);
+
+/// Fastest SCLK the AD7175-2 accepts, from the 25 ns minimum high and low pulse widths
+/// (datasheet Table 2). With IOVDD below 4.75 V the data valid delay is up to 25 ns too, so
+/// reads at this rate leave no margin on long or loaded traces.
+pub const MAX_SCLK_RATE: Rate = Rate::from_mhz(20);
+
+/// External clock or crystal frequency assumed until [`ADC::with_external_clock_frequency`],
+/// the 16 MHz the datasheet specifies the timing at.
+const DEFAULT_EXTERNAL_CLOCK_HZ: f32 = 16_000_000.0;
+
+/// Number of status register polls before giving up on the device becoming ready.
+const MAX_READY_POLLS: u32 = 100_000;
+
+/// Longest wait for DOUT/RDY to fall during [`ADC::read_burst`], a little over one conversion at
+/// the slowest output data rate.
+const BURST_READY_TIMEOUT_US: u32 = 1_000_000;
+
+/// Conversions at or above this raw code count as railed when checking for open inputs.
+const OPEN_INPUT_THRESHOLD: u32 = 0xff_0000;
+
+/// Errors returned by the [`ADC`] driver.
+#[derive(Debug, Format)]
+pub enum AdcError<E> {
+    /// The underlying SPI bus failed.
+    Spi(E),
+    /// The checksum appended to a register read didn't match the received data, or the status
+    /// register flagged a register write that failed its CRC.
+    CrcMismatch,
+    /// The device reported an unexpected ID, usually a sign of a miswired or missing part.
+    IdMismatch { expected: u16, got: u16 },
+    /// The device didn't signal completion of an operation in time.
+    Timeout,
+    /// Register access was attempted while the interface is in continuous read mode.
+    ContinuousReadActive,
+    /// A continuous read operation was attempted while continuous read mode is off.
+    ContinuousReadInactive,
+    /// Data and status were requested together but DATA_STAT isn't enabled in the interface mode.
+    DataStatDisabled,
+    /// A raw read asked for more bytes than fit in the transfer buffer.
+    InvalidLength,
+    /// A register read back with a field value that doesn't decode, e.g. from a corrupted transfer.
+    InvalidValue(InvalidValue),
+    /// A GPIO other than GPIO0 or GPIO1 was addressed.
+    InvalidPin,
+    /// A register write was refused because reserved bits were set.
+    ReservedBitsSet(ReservedBitsSet),
+    /// The status register flagged an error in the ADC core, e.g. a modulator overrange or an
+    /// out of range reference. The conversion that came with it can't be trusted.
+    AdcFault,
+    /// The status register flagged a change in the register contents since they were written.
+    RegisterError,
+    /// A register read back differently from what was just written to it, both masked to the
+    /// bits that were compared.
+    Verify { id: u8, expected: u32, got: u32 },
+    /// A channel was set up with inputs that don't make a measurement together.
+    InvalidInputPair(InvalidInputPair),
+    /// The reference monitor on the SYNC/ERROR input flagged the external reference of the
+    /// conversion's setup, see [`ADC::enable_reference_monitor`].
+    ReferenceOutOfRange,
+    /// Registers were verified without a register check baseline, see
+    /// [`ADC::enable_register_check`], or after a register write dropped it.
+    RegisterCheckDisabled,
+    /// A channel, setup or analog input the part doesn't have was used, see [`ADC::with_part`].
+    /// Nothing was sent to the device.
+    NotOnPart(NotOnPart),
+}
+
+impl<E> From<E> for AdcError<E> {
+    fn from(error: E) -> Self {
+        AdcError::Spi(error)
+    }
+}
+
+/// What [`AdcError::NotOnPart`] found missing on the part.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotOnPart {
+    Channel(Channel),
+    Setup(Setup),
+    Input(Input),
+}
+
+/// Driver for the AD7175-2 24-bit sigma-delta ADC.
+///
+/// The driver is generic over any [`RegisterTransport`], which includes every
+/// [`SpiBus`](embedded_hal::spi::SpiBus); on the ESP32-S3 use [`ADC::new_with_peripherals`] to
+/// build one on top of a DMA backed SPI bus. Other parts of the family are selected with
+/// [`ADC::with_part`].
 #[derive(Debug)]
-pub struct ADC<Bus: SpiBus> {
+pub struct ADC<Bus: RegisterTransport, AdcDelay: DelayNs = BusyDelay, Part: Device = Ad7175_2> {
     spi: Bus,
-    buf: [u8; 6],
+    delay: AdcDelay,
+    part: PhantomData<Part>,
+    settle_times: SettleTimes,
+    framer: Framer,
+    power_state: PowerState,
+    cs_pin: Option<Output<'static>>,
+    cs_held: bool,
+    reference_monitor: bool,
+    /// REGCHECK as computed by the device when the register check was enabled.
+    register_check: Option<u32>,
+    modulator_hz: f32,
+    /// Frequency of the clock or crystal on XTAL/CLKIO, for [`ADC::set_clock_source`].
+    external_clock_hz: f32,
+}
+
+/// Waits the driver inserts after writing the [`AdcModeRegister`], see
+/// [`ADC::with_settle_times`].
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettleTimes {
+    /// After REF_EN turns the internal reference on, in µs.
+    pub reference_us: u32,
+    /// After MODE changes, in µs.
+    ///
+    /// The datasheet asks for nothing here, the device holds off RDY until the first conversion
+    /// in the new mode has settled, so this is zero unless something outside the ADC needs it.
+    pub mode_us: u32,
+    /// After CLOCKSEL switches to an external crystal, in µs.
+    ///
+    /// The datasheet figure is typical only. SCLK edges while the crystal is still starting up
+    /// can double clock it and corrupt conversions, so measure this on the final board and
+    /// raise it if needed.
+    pub clock_us: u32,
+}
+
+impl Default for SettleTimes {
+    fn default() -> Self {
+        Self {
+            reference_us: REFERENCE_SETTLE_US,
+            mode_us: 0,
+            clock_us: CRYSTAL_STARTUP_US,
+        }
+    }
+}
+
+/// Offset and gain coefficients of one setup, see [`ADC::read_calibration`].
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    pub offset: u32,
+    pub gain: u32,
+}
+
+/// A filter configuration register, decoded according to its SINC3_MAP bit.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterConfig<D, S> {
+    Default(D),
+    DirectSinc3Map(S),
 }
 
+/// The filter configuration register of any setup, see [`ADC::read_filter_config`].
+///
+/// Every setup's register shares the layout of setup 0, so the setup 0 types stand in for all
+/// of them.
+pub type FilterConfigKind = FilterConfig<DefaultFilterConfig0Register, DirectSinc3MapFilterConfig0Register>;
+
+/// The whole configuration of the device, see [`ADC::dump_config`].
+///
+/// Covers the channels and setups every part has. On an AD7175-8 the rest are read with
+/// [`ADC::read`], e.g. `adc.read::<2, Channel7Register>()`.
+#[derive(Format, Debug, Clone, Copy)]
+pub struct ConfigSnapshot {
+    pub adc_mode: AdcModeRegister,
+    pub interface_mode: InterfaceModeRegister,
+    pub channel0: Channel0Register,
+    pub channel1: Channel1Register,
+    pub channel2: Channel2Register,
+    pub channel3: Channel3Register,
+    pub setup_config0: SetupConfig0Register,
+    pub setup_config1: SetupConfig1Register,
+    pub setup_config2: SetupConfig2Register,
+    pub setup_config3: SetupConfig3Register,
+    pub filter_config0: FilterConfig<DefaultFilterConfig0Register, DirectSinc3MapFilterConfig0Register>,
+    pub filter_config1: FilterConfig<DefaultFilterConfig1Register, DirectSinc3MapFilterConfig1Register>,
+    pub filter_config2: FilterConfig<DefaultFilterConfig2Register, DirectSinc3MapFilterConfig2Register>,
+    pub filter_config3: FilterConfig<DefaultFilterConfig3Register, DirectSinc3MapFilterConfig3Register>,
+}
+
+/// The writable configuration of the device as plain data, see [`ADC::apply_config`].
+///
+/// Defaults to the reset value of every register. Calibration coefficients are left out, they
+/// are handled by [`ADC::read_calibration`] and [`ADC::write_calibration`]. Like
+/// [`ConfigSnapshot`] it covers the channels and setups every part has.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdcConfig {
+    pub adc_mode: AdcModeRegister,
+    pub interface_mode: InterfaceModeRegister,
+    pub gpio_config: GPIOConfigRegister,
+    pub channel0: Channel0Register,
+    pub channel1: Channel1Register,
+    pub channel2: Channel2Register,
+    pub channel3: Channel3Register,
+    pub setup_config0: SetupConfig0Register,
+    pub setup_config1: SetupConfig1Register,
+    pub setup_config2: SetupConfig2Register,
+    pub setup_config3: SetupConfig3Register,
+    pub filter_config0: FilterConfig<DefaultFilterConfig0Register, DirectSinc3MapFilterConfig0Register>,
+    pub filter_config1: FilterConfig<DefaultFilterConfig1Register, DirectSinc3MapFilterConfig1Register>,
+    pub filter_config2: FilterConfig<DefaultFilterConfig2Register, DirectSinc3MapFilterConfig2Register>,
+    pub filter_config3: FilterConfig<DefaultFilterConfig3Register, DirectSinc3MapFilterConfig3Register>,
+}
+
+impl Default for AdcConfig {
+    fn default() -> Self {
+        Self {
+            adc_mode: AdcModeRegister::new(),
+            interface_mode: InterfaceModeRegister::new(),
+            gpio_config: GPIOConfigRegister::new(),
+            channel0: Channel0Register::new(),
+            channel1: Channel1Register::new(),
+            channel2: Channel2Register::new(),
+            channel3: Channel3Register::new(),
+            setup_config0: SetupConfig0Register::new(),
+            setup_config1: SetupConfig1Register::new(),
+            setup_config2: SetupConfig2Register::new(),
+            setup_config3: SetupConfig3Register::new(),
+            filter_config0: FilterConfig::Default(DefaultFilterConfig0Register::new()),
+            filter_config1: FilterConfig::Default(DefaultFilterConfig1Register::new()),
+            filter_config2: FilterConfig::Default(DefaultFilterConfig2Register::new()),
+            filter_config3: FilterConfig::Default(DefaultFilterConfig3Register::new()),
+        }
+    }
+}
+
+/// Low power state the driver put the device in, with the ADC mode to return to on wake.
+#[derive(Debug, Clone, Copy)]
+enum PowerState {
+    Awake,
+    Standby(AdcModeRegister),
+    PowerDown(AdcModeRegister),
+}
+
+/// Interface settings the driver needs to know about to frame transactions correctly.
+///
+/// Kept in sync with the device whenever the [`InterfaceModeRegister`] is written.
+#[derive(Debug)]
 pub struct ReadConfiguration {
     crc: Crc,
     data_read_configuration: DataReadConfiguration
 }
 
+#[derive(Debug)]
 pub struct DataReadConfiguration {
     continuous: bool,
     status_included: bool,
     data_register_length: DataRegisterLength,
 }
 
+impl From<&InterfaceModeRegister> for ReadConfiguration {
+    fn from(interface_mode: &InterfaceModeRegister) -> Self {
+        Self {
+            crc: interface_mode.crc_en(),
+            data_read_configuration: DataReadConfiguration {
+                continuous: interface_mode.cont_read(),
+                status_included: interface_mode.data_stat(),
+                data_register_length: interface_mode.wl16(),
+            },
+        }
+    }
+}
+
+impl Default for ReadConfiguration {
+    fn default() -> Self {
+        Self::from(&InterfaceModeRegister::new())
+    }
+}
+
 impl <'d> ADC<SpiDmaBus<'d, Blocking>> {
 
+    /// SPI configuration used to talk to the AD7175-2, at [`DEFAULT_SCLK_RATE`].
+    ///
+    /// The part requires SPI mode 3 (CPOL = 1, CPHA = 1) with MSB first transfers.
     pub fn get_spi_config() -> Config {
+        Self::get_spi_config_with_frequency(DEFAULT_SCLK_RATE)
+    }
+
+    /// SPI configuration used to talk to the AD7175-2 with SCLK at `frequency`.
+    ///
+    /// The part requires SPI mode 3 (CPOL = 1, CPHA = 1) with MSB first transfers, which no
+    /// frequency changes.
+    ///
+    /// # Panics
+    ///
+    /// If `frequency` is above [`MAX_SCLK_RATE`].
+    pub fn get_spi_config_with_frequency(frequency: Rate) -> Config {
+        assert!(frequency.as_hz() <= MAX_SCLK_RATE.as_hz(), "ADC SCLK above the AD7175-2 maximum of 20 MHz");
+
         Config::default()
-            .with_frequency(Rate::from_mhz(10))
+            .with_frequency(frequency)
             .with_mode(esp_hal::spi::Mode::_3)
             .with_read_bit_order(BitOrder::MsbFirst)
             .with_write_bit_order(BitOrder::MsbFirst)
     }
 
-    pub fn new_with_peripherals<SpiInstance: Instance + 'static, CS: OutputPin + 'static, SCK: OutputPin + 'static, MOSI: OutputPin + 'static, MISO: InputPin + 'static, DmaChannel: DmaChannelFor<AnySpi<'d>>>(spi: SpiInstance, cs: CS, sck: SCK, mosi: MOSI, miso: MISO, dma_channel: DmaChannel) -> Self {
-        let (dma_rx_buf, dma_tx_buf) = initialize_dma_buffers();
+    /// Wires up a DMA backed SPI bus on the given peripherals and wraps it in an [`ADC`].
+    ///
+    /// Fails if the DMA buffers of the bus can't be set up.
+    ///
+    /// ```no_run
+    /// # use dc_load_control_loop_rs::adc::ADC;
+    /// let peripherals = esp_hal::init(esp_hal::Config::default());
+    /// let adc = ADC::new_with_peripherals(
+    ///     peripherals.SPI2,
+    ///     peripherals.GPIO8,  // CS
+    ///     peripherals.GPIO7,  // SCK
+    ///     peripherals.GPIO6,  // MOSI
+    ///     peripherals.GPIO5,  // MISO
+    ///     peripherals.DMA_CH0,
+    /// ).expect("ADC DMA init failed");
+    /// ```
+    pub fn new_with_peripherals<SpiInstance: Instance + 'static, CS: OutputPin + 'static, SCK: OutputPin + 'static, MOSI: OutputPin + 'static, MISO: InputPin + 'static, DmaChannel: DmaChannelFor<AnySpi<'d>>>(spi: SpiInstance, cs: CS, sck: SCK, mosi: MOSI, miso: MISO, dma_channel: DmaChannel) -> Result<Self, DmaBufError> {
+        Self::new_with_peripherals_and_frequency(spi, cs, sck, mosi, miso, dma_channel, DEFAULT_SCLK_RATE)
+    }
 
-        let adc_spi = Spi::new(spi, ADC::get_spi_config()).unwrap()
+    /// Like [`ADC::new_with_peripherals`], with SCLK at `frequency` instead of
+    /// [`DEFAULT_SCLK_RATE`], e.g. to get more conversions per second out in continuous read
+    /// mode.
+    ///
+    /// # Panics
+    ///
+    /// If `frequency` is above [`MAX_SCLK_RATE`].
+    pub fn new_with_peripherals_and_frequency<SpiInstance: Instance + 'static, CS: OutputPin + 'static, SCK: OutputPin + 'static, MOSI: OutputPin + 'static, MISO: InputPin + 'static, DmaChannel: DmaChannelFor<AnySpi<'d>>>(spi: SpiInstance, cs: CS, sck: SCK, mosi: MOSI, miso: MISO, dma_channel: DmaChannel, frequency: Rate) -> Result<Self, DmaBufError> {
+        let (dma_rx_buf, dma_tx_buf) = dma_bus_buffers!(DMA_RX_BUFFER_SIZE, DMA_TX_BUFFER_SIZE)?;
+
+        let adc_spi = Spi::new(spi, ADC::get_spi_config_with_frequency(frequency)).unwrap()
             .with_cs(cs)
             .with_sck(sck)
             .with_mosi(mosi)
@@ -49,42 +381,1348 @@ impl <'d> ADC<SpiDmaBus<'d, Blocking>> {
             .with_dma(dma_channel)
             .with_buffers(dma_rx_buf, dma_tx_buf);
 
-        Self::new(adc_spi)
+        Ok(Self::new(adc_spi))
+    }
+}
+
+impl <Device: SpiDevice> ADC<DeviceBus<Device>> {
+    /// Creates the driver on a shared bus, with chip select handled per transaction by `device`.
+    pub fn new_with_device(device: Device) -> Self {
+        Self::new(DeviceBus::new(device))
     }
 }
 
-impl <Bus: SpiBus> ADC<Bus> {
+impl <Bus: RegisterTransport> ADC<Bus> {
 
+    /// Creates the driver on `spi`, busy-waiting out the settle times, see [`ADC::with_delay`].
     pub fn new(spi: Bus) -> Self {
         Self {
             spi,
-            buf: [0; 6],
+            delay: BusyDelay::new(),
+            part: PhantomData,
+            settle_times: SettleTimes::default(),
+            framer: Framer::new(),
+            power_state: PowerState::Awake,
+            cs_pin: None,
+            cs_held: false,
+            reference_monitor: false,
+            register_check: None,
+            modulator_hz: INTERNAL_MODULATOR_FREQUENCY_HZ,
+            external_clock_hz: DEFAULT_EXTERNAL_CLOCK_HZ,
+        }
+    }
+}
+
+impl <Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device> ADC<Bus, AdcDelay, Part> {
+
+    /// Waits out the settle times with `delay` instead of busy-waiting, e.g. an
+    /// `embassy_time::Delay` so other tasks can run meanwhile.
+    pub fn with_delay<E: DelayNs>(self, delay: E) -> ADC<Bus, E, Part> {
+        ADC {
+            spi: self.spi,
+            delay,
+            part: self.part,
+            settle_times: self.settle_times,
+            framer: self.framer,
+            power_state: self.power_state,
+            cs_pin: self.cs_pin,
+            cs_held: self.cs_held,
+            reference_monitor: self.reference_monitor,
+            register_check: self.register_check,
+            modulator_hz: self.modulator_hz,
+            external_clock_hz: self.external_clock_hz,
+        }
+    }
+
+    /// Talks to `part` instead of the AD7175-2, e.g. [`Ad7175_8`](device::Ad7175_8).
+    ///
+    /// Besides the ID [`ADC::check_id`] expects, the part decides which channels, setups and
+    /// analog inputs can be used. Reading or writing a register of one it doesn't have fails
+    /// with [`AdcError::NotOnPart`], see [`device`].
+    pub fn with_part<P: Device>(self, _part: P) -> ADC<Bus, AdcDelay, P> {
+        const { assert_fits_register_map::<P>() };
+        ADC {
+            spi: self.spi,
+            delay: self.delay,
+            part: PhantomData,
+            settle_times: self.settle_times,
+            framer: self.framer,
+            power_state: self.power_state,
+            cs_pin: self.cs_pin,
+            cs_held: self.cs_held,
+            reference_monitor: self.reference_monitor,
+            register_check: self.register_check,
+            modulator_hz: self.modulator_hz,
+            external_clock_hz: self.external_clock_hz,
+        }
+    }
+
+    /// Changes how long the driver waits after ADC mode register writes, [`SettleTimes::default`]
+    /// being the datasheet figures.
+    ///
+    /// The wait follows any write of the [`AdcModeRegister`] through the driver, e.g. by
+    /// [`ADC::write`], [`ADC::standby`] or [`ADC::wake`], that turns the internal reference on,
+    /// changes MODE or selects an external crystal.
+    pub fn with_settle_times(mut self, settle_times: SettleTimes) -> Self {
+        self.settle_times = settle_times;
+        self
+    }
+
+    /// Sets the modulator frequency, half of MCLK, the timing helpers work from instead of the
+    /// nominal [`INTERNAL_MODULATOR_FREQUENCY_HZ`].
+    ///
+    /// The internal oscillator is only accurate to a few percent, so pass a measured value, or
+    /// half the frequency of the external clock or crystal selected with
+    /// [`ClockSource::External`] or [`ClockSource::ExternalCrystal`].
+    pub fn with_modulator_frequency(mut self, modulator_hz: f32) -> Self {
+        assert!(modulator_hz > 0.0, "modulator frequency must be positive");
+        self.modulator_hz = modulator_hz;
+        self
+    }
+
+    /// Sets the frequency of the external clock or crystal, 16 MHz by default, which
+    /// [`ADC::set_clock_source`] runs the timing helpers from when it selects one.
+    pub fn with_external_clock_frequency(mut self, clock_hz: f32) -> Self {
+        assert!(clock_hz > 0.0, "external clock frequency must be positive");
+        self.external_clock_hz = clock_hz;
+        self
+    }
+
+    /// Switches the master clock to `source`. The modulator then runs at half of MCLK, the
+    /// nominal [`INTERNAL_MODULATOR_FREQUENCY_HZ`] on the internal oscillator or half the
+    /// frequency set with [`ADC::with_external_clock_frequency`] on an external clock or crystal.
+    ///
+    /// Only CLOCKSEL changes in the ADC mode register. Switching to an external crystal waits
+    /// out its startup before returning, see [`SettleTimes::clock_us`], so no SCLK edges reach
+    /// it meanwhile. An external clock on CLKIO has to be running before it is selected.
+    /// [`ADC::sequence_cycle_time_us`] and [`ADC::settling_time_us`] follow the new frequency,
+    /// replacing one set with [`ADC::with_modulator_frequency`].
+    pub fn set_clock_source(&mut self, source: ClockSource) -> Result<(), AdcError<Bus::Error>> {
+        let modulator_hz = match source {
+            ClockSource::Internal | ClockSource::InternalWithOutput => INTERNAL_MODULATOR_FREQUENCY_HZ,
+            ClockSource::External | ClockSource::ExternalCrystal => self.external_clock_hz / 2.0,
+        };
+        let mode = self.read::<2, AdcModeRegister>()?;
+        self.write(&mode.with_clksel(source))?;
+        self.modulator_hz = modulator_hz;
+
+        Ok(())
+    }
+
+    /// The modulator frequency the timing helpers assume, see [`ADC::with_modulator_frequency`].
+    pub fn modulator_frequency(&self) -> f32 {
+        self.modulator_hz
+    }
+
+    /// [`sequence_cycle_time_us`] at the configured modulator frequency.
+    pub fn sequence_cycle_time_us(&self, channels: &[(FilterOrder, OutputDataRate)], single_cycle: bool) -> u32 {
+        sequence_cycle_time_us_at(channels, single_cycle, self.modulator_hz)
+    }
+
+    /// [`OutputDataRate::settling_time_us`] at the configured modulator frequency.
+    pub fn settling_time_us(&self, odr: OutputDataRate, order: FilterOrder) -> u32 {
+        odr.settling_time_us_at(order, self.modulator_hz)
+    }
+
+    /// Drives CS from `cs_pin` instead of leaving it to the bus, for a bus set up without one.
+    ///
+    /// CS is asserted around every transaction like before, and can additionally be held low
+    /// across several of them with [`ADC::hold_cs`]. DOUT/RDY only signals new conversions
+    /// while CS is low, which continuous read mode relies on.
+    pub fn with_manual_cs(mut self, mut cs_pin: Output<'static>) -> Self {
+        cs_pin.set_high();
+        self.cs_pin = Some(cs_pin);
+        self
+    }
+
+    /// Asserts CS and keeps it asserted between transactions until [`ADC::release_cs`].
+    ///
+    /// Does nothing unless CS is managed with [`ADC::with_manual_cs`].
+    pub fn hold_cs(&mut self) {
+        if let Some(cs_pin) = &mut self.cs_pin {
+            cs_pin.set_low();
+            self.cs_held = true;
+        }
+    }
+
+    /// Deasserts a CS held with [`ADC::hold_cs`], tristating DOUT/RDY.
+    ///
+    /// This only ends the frame, leaving continuous read mode still takes
+    /// [`ADC::stop_continuous_read`].
+    pub fn release_cs(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        if let Some(cs_pin) = &mut self.cs_pin {
+            self.spi.flush()?;
+            cs_pin.set_high();
+            self.cs_held = false;
         }
+        Ok(())
     }
 
-    pub fn read<const N: usize, T: Register<N>>(&mut self) -> Result<T, Bus::Error> {
-        let id = T::get_id();
-        self.buf[0] = id | RegisterRW::Read as u8;
+    /// Whether CS is being held low with [`ADC::hold_cs`].
+    pub fn is_cs_held(&self) -> bool {
+        self.cs_held
+    }
 
-        debug!("Writing register: {:02x} {:012x}", id, self.buf);
-        self.spi.transfer_in_place(&mut self.buf[..N + 1])?;
+    /// Runs `f` with CS held low throughout when it is managed manually, restoring it after
+    /// unless it was held already.
+    fn with_cs_held<R>(&mut self, f: impl FnOnce(&mut Self) -> Result<R, AdcError<Bus::Error>>) -> Result<R, AdcError<Bus::Error>> {
+        if self.cs_held {
+            return f(self);
+        }
 
-        let mut register_buf: [u8; N] = [0; N];
-        register_buf.copy_from_slice(&self.buf[1..N + 1]);
+        self.hold_cs();
+        let result = f(self);
+        self.release_cs()?;
+        result
+    }
 
-        debug!("Writing register: {:06x}", self.buf);
+    /// Runs `f` on the bus and the transfer buffer, with CS asserted around it when it is
+    /// managed manually and not held.
+    fn transaction(&mut self, f: impl FnOnce(&mut Bus, &mut [u8; FRAME_BUF_LEN]) -> Result<(), Bus::Error>) -> Result<(), Bus::Error> {
+        let Some(cs_pin) = &mut self.cs_pin else {
+            return f(&mut self.spi, &mut self.framer.buf);
+        };
 
-        Ok(T::from_buffer((&self.buf[1..N + 1]).try_into().unwrap()))
+        cs_pin.set_low();
+        // the last bits have to be clocked out before CS goes back up
+        let result = f(&mut self.spi, &mut self.framer.buf).and_then(|()| self.spi.flush());
+        if !self.cs_held {
+            cs_pin.set_high();
+        }
+        result
+    }
+
+    /// The interface mode last written through the driver, or its reset value.
+    pub fn interface_mode(&self) -> InterfaceModeRegister {
+        self.framer.interface_mode
     }
 
-    pub fn write<const N: usize, T: WritableRegister<N>>(&mut self, register: &T) -> Result<(), Bus::Error> {
-        let id = T::get_id();
-        self.buf[0] = id | RegisterRW::Write as u8;
-        self.buf[1..N + 1].copy_from_slice(&register.to_buffer());
+    /// The ADC mode last written through the driver, or its reset value.
+    ///
+    /// The device clears MODE back to standby after a single conversion or a calibration, which
+    /// this doesn't see.
+    pub fn adc_mode(&self) -> AdcModeRegister {
+        self.framer.adc_mode
+    }
 
-        debug!("Writing register: {:02x} {:012x}", id, self.buf);
+    /// The output coding of `setup` last written through the driver, or its reset value.
+    pub fn output_coding(&self, setup: Setup) -> OutputCoding {
+        self.framer.output_codings[setup.into_bits() as usize]
+    }
 
-        self.spi.write(&self.buf[..N + 1])
+    /// The underlying transport, e.g. for inspecting a mock in tests.
+    ///
+    /// Transfers made directly on the bus bypass the driver's view of the interface mode.
+    pub fn spi_mut(&mut self) -> &mut Bus {
+        &mut self.spi
+    }
+
+    /// Reads a register.
+    ///
+    /// When CRC or XOR checking is enabled in the interface mode the appended checksum is
+    /// clocked out as well and verified, returning [`AdcError::CrcMismatch`] if it doesn't match.
+    /// A channel or setup register the part doesn't have fails with [`AdcError::NotOnPart`].
+    pub fn read<const N: usize, T: Register<N>>(&mut self) -> Result<T, AdcError<Bus::Error>> {
+        Self::check_register(T::get_id())?;
+        let len = self.framer.start_read::<_, N, T>()?;
+        self.transaction(|spi, buf| spi.read_register(&mut buf[..len]))?;
+        self.framer.finish_read()
+    }
+
+    /// Reads a register like [`ADC::read`], also returning the bytes it was decoded from.
+    ///
+    /// Meant for logging the wire data, e.g. to tell a corrupted transfer from a decode bug. The
+    /// bytes are the register contents only, without the command byte and checksum.
+    pub fn read_with_raw<const N: usize, T: Register<N>>(&mut self) -> Result<(T, [u8; N]), AdcError<Bus::Error>> {
+        let register = self.read::<N, T>()?;
+        Ok((register, self.framer.read_payload()))
+    }
+
+    /// Reads `len` bytes from the register at `id` without decoding them.
+    ///
+    /// Meant for bring-up and debugging, e.g. dumping a register whose typed decode fails. The
+    /// checksum is verified like in [`ADC::read`] but not included in the returned bytes.
+    /// Returns [`AdcError::InvalidLength`] if the transfer wouldn't fit the internal buffer.
+    pub fn read_raw(&mut self, id: u8, len: usize) -> Result<&[u8], AdcError<Bus::Error>> {
+        Self::check_register(id)?;
+        let transfer_len = self.framer.start_read_raw(id, len)?;
+        self.transaction(|spi, buf| spi.read_register(&mut buf[..transfer_len]))?;
+        self.framer.finish_read_raw(id, len)
+    }
+
+    /// Writes a register, appending a checksum when CRC is enabled in the interface mode.
+    ///
+    /// Writing the [`InterfaceModeRegister`] updates the driver's view of the interface
+    /// configuration, the new settings apply from the next transaction on. Writing the
+    /// [`AdcModeRegister`] waits out its [`SettleTimes`] before returning.
+    ///
+    /// A channel or setup register the part doesn't have, or a channel register selecting a
+    /// setup or input it doesn't have, fails with [`AdcError::NotOnPart`].
+    pub fn write<const N: usize, T: WritableRegister<N>>(&mut self, register: &T) -> Result<(), AdcError<Bus::Error>> {
+        self.write_at(T::get_id(), register)
+    }
+
+    /// [`ADC::write`] to the register at `id`, for registers sharing the layout of `T` at another
+    /// address, e.g. channel 7 as a [`Channel0Register`].
+    fn write_at<const N: usize, T: WritableRegister<N>>(&mut self, id: u8, register: &T) -> Result<(), AdcError<Bus::Error>> {
+        Self::check_register(id)?;
+        if (Channel0Register::ID..=Channel15Register::ID).contains(&id) {
+            // every channel register shares the layout of channel 0
+            let buffer = register.to_buffer();
+            Self::check_channel_register(&Channel0Register::from_buffer(&[buffer[0], buffer[1]]))?;
+        }
+
+        let previous_mode = self.framer.adc_mode;
+        let len = self.framer.start_write_at(id, register)?;
+        self.transaction(|spi, buf| spi.write_register(&buf[..len]))?;
+        self.framer.finish_write(id);
+        // the device flags any write as a register error while the check is on
+        self.register_check = None;
+
+        if id == AdcModeRegister::ID {
+            self.settle(previous_mode);
+        }
+
+        Ok(())
+    }
+
+    /// [`ADC::read`] from the register at `id`, for registers sharing the layout of `T` at
+    /// another address.
+    fn read_at<const N: usize, T: Register<N>>(&mut self, id: u8) -> Result<T, AdcError<Bus::Error>> {
+        let buffer: [u8; N] = self.read_raw(id, N)?.try_into().unwrap();
+        let register = T::from_buffer(&buffer);
+        register.validate().map_err(AdcError::InvalidValue)?;
+        Ok(register)
+    }
+
+    /// Fails with [`AdcError::NotOnPart`] if `id` is a channel register or a setup's register
+    /// the part doesn't have.
+    fn check_register(id: u8) -> Result<(), AdcError<Bus::Error>> {
+        match id {
+            Channel0Register::ID..=Channel15Register::ID => {
+                let channel = Channel::from_bits(id - Channel0Register::ID);
+                if !Part::has_channel(channel) {
+                    return Err(AdcError::NotOnPart(NotOnPart::Channel(channel)));
+                }
+            }
+            // setup configuration, filter, offset and gain registers, in blocks of eight
+            SetupConfig0Register::ID..=Gain7Register::ID => {
+                let setup = Setup::from_bits((id - SetupConfig0Register::ID) % MAX_SETUPS as u8);
+                if !Part::has_setup(setup) {
+                    return Err(AdcError::NotOnPart(NotOnPart::Setup(setup)));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Fails with [`AdcError::NotOnPart`] if `channel` selects a setup or input the part doesn't
+    /// have.
+    fn check_channel_register(channel: &Channel0Register) -> Result<(), AdcError<Bus::Error>> {
+        channel.validate().map_err(AdcError::InvalidValue)?;
+        if !Part::has_setup(channel.setup_sel()) {
+            return Err(AdcError::NotOnPart(NotOnPart::Setup(channel.setup_sel())));
+        }
+        for input in [channel.ainpos(), channel.ainneg()] {
+            if !Part::has_input(input) {
+                return Err(AdcError::NotOnPart(NotOnPart::Input(input)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits out what the ADC mode just written needs after `previous_mode`, see [`SettleTimes`].
+    fn settle(&mut self, previous_mode: AdcModeRegister) {
+        let mode = self.framer.adc_mode;
+        let mut wait_us = 0;
+        if mode.ref_enable() && !previous_mode.ref_enable() {
+            wait_us = self.settle_times.reference_us;
+        }
+        if mode.mode() != previous_mode.mode() {
+            wait_us = wait_us.max(self.settle_times.mode_us);
+        }
+        if mode.clksel() == ClockSource::ExternalCrystal && previous_mode.clksel() != ClockSource::ExternalCrystal {
+            wait_us = wait_us.max(self.settle_times.clock_us);
+        }
+
+        if wait_us > 0 {
+            self.delay.delay_us(wait_us);
+        }
+    }
+
+    /// Writes several registers in order, e.g. a whole startup configuration in one call.
+    ///
+    /// Each register is still its own frame with its own command byte, the device doesn't
+    /// support multi-register writes. With [`ADC::with_manual_cs`] CS stays low across all of
+    /// them, which the device accepts as long as every frame is complete. Stops at the first
+    /// error, leaving the registers before it written.
+    ///
+    /// ```no_run
+    /// # use dc_load_control_loop_rs::adc::{Crc, ADC};
+    /// # use dc_load_control_loop_rs::adc::register::{AdcModeRegister, InterfaceModeRegister, SetupConfig0Register};
+    /// # use dc_load_control_loop_rs::adc::transport::RegisterTransport;
+    /// # fn configure<Bus: RegisterTransport>(adc: &mut ADC<Bus>) -> Result<(), dc_load_control_loop_rs::adc::AdcError<Bus::Error>> {
+    /// adc.write_all((
+    ///     InterfaceModeRegister::new().with_crc_en(Crc::Enable),
+    ///     SetupConfig0Register::for_low_impedance(),
+    ///     AdcModeRegister::new(),
+    /// ))
+    /// # }
+    /// ```
+    pub fn write_all(&mut self, registers: impl RegisterBatch) -> Result<(), AdcError<Bus::Error>> {
+        self.with_cs_held(|adc| registers.write_to(adc))
+    }
+
+    /// Writes a register and reads it back, failing with [`AdcError::Verify`] if the device
+    /// doesn't hold what was written.
+    ///
+    /// Catches wiring and timing problems during init that a write alone can't see. Registers
+    /// with fields that read back differently, like the self-clearing MODE of the
+    /// [`AdcModeRegister`], need [`ADC::write_verified_masked`] instead.
+    pub fn write_verified<const N: usize, T: WritableRegister<N>>(&mut self, register: &T) -> Result<(), AdcError<Bus::Error>> {
+        self.write_verified_masked(register, [0xff; N])
+    }
+
+    /// Like [`ADC::write_verified`], only comparing the bits set in `mask`, most significant
+    /// byte first like the register itself.
+    pub fn write_verified_masked<const N: usize, T: WritableRegister<N>>(&mut self, register: &T, mask: [u8; N]) -> Result<(), AdcError<Bus::Error>> {
+        self.write(register)?;
+        let read_back = self.read::<N, T>()?;
+
+        let masked = |buffer: [u8; N]| buffer.iter().zip(mask).fold(0u32, |value, (byte, mask)| (value << 8) | (byte & mask) as u32);
+        let expected = masked(register.to_buffer());
+        let got = masked(read_back.to_buffer());
+        if expected != got {
+            return Err(AdcError::Verify { id: T::get_id(), expected, got });
+        }
+
+        Ok(())
+    }
+
+    /// Resets the device by clocking 64 ones into DIN.
+    ///
+    /// This returns the serial interface and every register to its power-on state, so the caller
+    /// has to re-apply its configuration (including the interface mode) afterwards. Blocks on the
+    /// driver's delay for the 500 µs the datasheet requires before the device can be addressed
+    /// again.
+    pub fn reset(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        self.transaction(|spi, _| {
+            spi.write_register(&[0xff; 8])?;
+            spi.flush()
+        })?;
+        self.framer.reset();
+        self.power_state = PowerState::Awake;
+        self.register_check = None;
+
+        self.delay.delay_us(RESET_DELAY_US);
+
+        Ok(())
+    }
+
+    /// Resets the device like [`ADC::reset`] and then confirms it responds with its ID.
+    pub fn reset_and_verify(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        self.reset()?;
+        self.check_id()
+    }
+
+    /// Reads the ID register and checks it identifies the part, 0x0cdX for an AD7175-2 and
+    /// 0x3cdX for an AD7175-8.
+    ///
+    /// Fails with [`AdcError::IdMismatch`] otherwise, which is the quickest way to catch a
+    /// miswired or unpowered part before trusting any conversions.
+    pub fn check_id(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        let expected = Part::ID;
+        let got = self.read::<2, IdRegister>()?.id();
+        if got & ID_MASK != expected & ID_MASK {
+            return Err(AdcError::IdMismatch { expected, got });
+        }
+
+        Ok(())
+    }
+
+    /// Waits for the next conversion of `channel` and converts it to degrees Celsius.
+    ///
+    /// The channel must already be configured with [`Input::TemperatureSensorPos`] and
+    /// [`Input::TemperatureSensorNeg`], and its setup must use the internal reference with
+    /// bipolar coding (the reset default), otherwise the result is meaningless.
+    pub fn read_temperature(&mut self, channel: Channel) -> Result<f32, AdcError<Bus::Error>> {
+        Ok(self.read_channel(channel)?.to_celsius(OutputCoding::Bipolar))
+    }
+
+    /// Checks whether the inputs of `channel` are open, using the burnout currents of `setup`.
+    ///
+    /// Enables the 10 µA burnout current source and sink on the selected inputs and takes a
+    /// conversion; with nothing connected the inputs get pulled apart and the result rails
+    /// towards positive full scale. The setup configuration is restored afterwards, as the
+    /// burnout currents offset any real measurement.
+    ///
+    /// The currents have to charge whatever capacitance sits on the inputs before an open shows
+    /// up, at 10 µA / C volts per second. The first conversion after enabling them is discarded
+    /// for that reason; with heavy input filtering and a fast output data rate that may not be
+    /// enough, so lower the rate of `setup` for the check if needed. `channel` has to use
+    /// `setup` and be enabled.
+    pub fn check_open_input(&mut self, setup: Setup, channel: Channel) -> Result<bool, AdcError<Bus::Error>> {
+        let previous = self.read_setup_config(setup)?;
+        self.write_setup_config(setup, SetupConfig0Register::from_buffer(&previous).with_burnout_en(true).to_buffer())?;
+
+        let result = self.read_channel(channel).and_then(|_| self.read_channel(channel));
+
+        self.write_setup_config(setup, previous)?;
+
+        Ok(result?.data() >= OPEN_INPUT_THRESHOLD)
+    }
+
+    /// Runs an offset calibration and returns the resulting offset coefficient of `setup`.
+    ///
+    /// The calibration is performed on the enabled channel, which has to be the only enabled
+    /// channel and use `setup`. With `system` set a system zero-scale calibration is run, which
+    /// expects the zero-scale voltage to be applied to the channel's inputs, otherwise the
+    /// inputs are shorted internally. The device reverts to standby once the calibration is done.
+    pub fn calibrate_offset(&mut self, setup: Setup, system: bool) -> Result<u32, AdcError<Bus::Error>> {
+        let mode = if system {
+            Mode::SystemOffsetCalibration
+        } else {
+            Mode::InternalOffsetCalibration
+        };
+        self.calibrate(mode)?;
+
+        Ok(self.read_at::<3, Offset0Register>(Offset0Register::ID + setup.into_bits())?.offset())
+    }
+
+    /// Runs a system gain calibration and returns the resulting gain coefficient of `setup`.
+    ///
+    /// The full-scale voltage has to be applied externally to the inputs of the enabled channel
+    /// before calling this, and an offset calibration should have been run first. Like
+    /// [`ADC::calibrate_offset`] only the channel being calibrated may be enabled. The ADC mode
+    /// in effect before the calibration is restored afterwards.
+    pub fn calibrate_gain(&mut self, setup: Setup) -> Result<u32, AdcError<Bus::Error>> {
+        let previous_mode = self.calibrate(Mode::SystemGainCalibration)?;
+
+        let gain = self.read_at::<3, Gain0Register>(Gain0Register::ID + setup.into_bits())?.gain();
+
+        self.write(&previous_mode)?;
+
+        Ok(gain)
+    }
+
+    /// Reads the offset and gain coefficients of `setup`, e.g. to persist them after calibrating.
+    pub fn read_calibration(&mut self, setup: Setup) -> Result<Calibration, AdcError<Bus::Error>> {
+        Ok(Calibration {
+            offset: self.read_at::<3, Offset0Register>(Offset0Register::ID + setup.into_bits())?.offset(),
+            gain: self.read_at::<3, Gain0Register>(Gain0Register::ID + setup.into_bits())?.gain(),
+        })
+    }
+
+    /// Loads previously saved coefficients into `setup`, skipping a new calibration.
+    ///
+    /// The coefficients are only valid for the setup configuration, reference and gain path
+    /// they were calibrated with.
+    pub fn write_calibration(&mut self, setup: Setup, calibration: &Calibration) -> Result<(), AdcError<Bus::Error>> {
+        // every setup's registers share the layout of setup 0
+        self.write_at(Offset0Register::ID + setup.into_bits(), &Offset0Register::new().with_offset(calibration.offset))?;
+        self.write_at(Gain0Register::ID + setup.into_bits(), &Gain0Register::new().with_gain(calibration.gain))
+    }
+
+    /// Reads the filter configuration of `setup`, as the default or the direct sinc3 map layout
+    /// depending on its SINC3_MAP bit.
+    ///
+    /// The two layouts share the register, so the bit is the only way to tell which one was
+    /// written. Fails with [`AdcError::InvalidValue`] if a default layout holds an undefined
+    /// field value.
+    pub fn read_filter_config(&mut self, setup: Setup) -> Result<FilterConfigKind, AdcError<Bus::Error>> {
+        self.read_filter_config_at(DefaultFilterConfig0Register::ID + setup.into_bits())
+    }
+
+    /// Writes `config` to the filter configuration register of `setup`, in the layout it holds,
+    /// e.g. to restore what [`ADC::read_filter_config`] returned.
+    pub fn write_filter_config(&mut self, setup: Setup, config: &FilterConfigKind) -> Result<(), AdcError<Bus::Error>> {
+        self.write_filter_config_at(DefaultFilterConfig0Register::ID + setup.into_bits(), config)
+    }
+
+    /// Reads the mode, channel, setup and filter registers in one go, e.g. for logging the
+    /// device state in a bug report.
+    pub fn dump_config(&mut self) -> Result<ConfigSnapshot, AdcError<Bus::Error>> {
+        Ok(ConfigSnapshot {
+            adc_mode: self.read()?,
+            interface_mode: self.read()?,
+            channel0: self.read()?,
+            channel1: self.read()?,
+            channel2: self.read()?,
+            channel3: self.read()?,
+            setup_config0: self.read()?,
+            setup_config1: self.read()?,
+            setup_config2: self.read()?,
+            setup_config3: self.read()?,
+            filter_config0: self.read_filter_config_register()?,
+            filter_config1: self.read_filter_config_register()?,
+            filter_config2: self.read_filter_config_register()?,
+            filter_config3: self.read_filter_config_register()?,
+        })
+    }
+
+    /// Reads the writable configuration of the device, e.g. as the `current` side of
+    /// [`ADC::apply_config_diff`].
+    pub fn read_config(&mut self) -> Result<AdcConfig, AdcError<Bus::Error>> {
+        Ok(AdcConfig {
+            adc_mode: self.read()?,
+            interface_mode: self.read()?,
+            gpio_config: self.read()?,
+            channel0: self.read()?,
+            channel1: self.read()?,
+            channel2: self.read()?,
+            channel3: self.read()?,
+            setup_config0: self.read()?,
+            setup_config1: self.read()?,
+            setup_config2: self.read()?,
+            setup_config3: self.read()?,
+            filter_config0: self.read_filter_config_register()?,
+            filter_config1: self.read_filter_config_register()?,
+            filter_config2: self.read_filter_config_register()?,
+            filter_config3: self.read_filter_config_register()?,
+        })
+    }
+
+    /// Writes every register of `config`.
+    ///
+    /// The interface mode goes first so the rest is framed the way it asks for, and the ADC
+    /// mode goes last so conversions only start once the channels and setups are in place.
+    /// `config` must not enable continuous read, as register access stops working once it does.
+    pub fn apply_config(&mut self, config: &AdcConfig) -> Result<(), AdcError<Bus::Error>> {
+        self.apply(config, None)
+    }
+
+    /// Writes only the registers of `config` that differ from `current`, in the same order as
+    /// [`ADC::apply_config`].
+    ///
+    /// `current` has to match the device, e.g. from [`ADC::read_config`] or the config applied
+    /// last, otherwise registers that look unchanged are left as the device has them.
+    pub fn apply_config_diff(&mut self, config: &AdcConfig, current: &AdcConfig) -> Result<(), AdcError<Bus::Error>> {
+        self.apply(config, Some(current))
+    }
+
+    /// Takes a single conversion of `input_pos` against `input_neg` using `setup` and returns
+    /// the raw code.
+    ///
+    /// Channel 0 is reconfigured for the inputs. Other enabled channels are converted as part
+    /// of the same sequence but their results are skipped. The device is left in standby, as
+    /// it always is after a single conversion.
+    pub fn read_single(&mut self, input_pos: Input, input_neg: Input, setup: Setup) -> Result<u32, AdcError<Bus::Error>> {
+        self.configure_channel(Channel::Ch0, setup, input_pos, input_neg)?;
+        let mode = self.read::<2, AdcModeRegister>()?;
+        self.write(&mode.with_mode(Mode::SingleConversion))?;
+
+        Ok(self.read_channel(Channel::Ch0)?.data())
+    }
+
+    /// Takes `count` single conversions of `input_pos` against `input_neg` using `setup` and
+    /// returns their mean as a signed code.
+    ///
+    /// Bipolar codes are centred on zero first, so the mean keeps its sign, see
+    /// [`DataRegister::as_signed`]. The mean is truncated towards zero. Channel 0 is reconfigured
+    /// like in [`ADC::read_single`].
+    pub fn read_averaged(&mut self, input_pos: Input, input_neg: Input, setup: Setup, count: NonZeroU16) -> Result<i32, AdcError<Bus::Error>> {
+        // every setup register shares the layout of setup 0
+        let coding = SetupConfig0Register::from_buffer(&self.read_setup_config(setup)?).bi_unipolar();
+        self.configure_channel(Channel::Ch0, setup, input_pos, input_neg)?;
+        let mode = self.read::<2, AdcModeRegister>()?.with_mode(Mode::SingleConversion);
+
+        let mut sum = 0i64;
+        for _ in 0..count.get() {
+            self.write(&mode)?;
+            sum += self.read_channel(Channel::Ch0)?.as_signed(coding) as i64;
+        }
+
+        Ok((sum / count.get() as i64) as i32)
+    }
+
+    /// Polls the status register until the ready flag is set, giving up with
+    /// [`AdcError::Timeout`] after `max_polls` reads.
+    ///
+    /// Returns the status that showed ready, so the error flags that came with it can be
+    /// checked too. Ready is set both by a finished conversion and by the end of a calibration.
+    pub fn wait_ready(&mut self, max_polls: u32) -> Result<StatusRegister, AdcError<Bus::Error>> {
+        for _ in 0..max_polls {
+            let status = self.read::<1, StatusRegister>()?;
+            if status.ready() {
+                return Ok(status);
+            }
+        }
+
+        Err(AdcError::Timeout)
+    }
+
+    /// The channel the conversion currently in the data register was taken on.
+    ///
+    /// Costs a status register read, one SPI transaction. With DATA_STAT enabled the channel
+    /// comes with every sample for free, see [`Sequencer::next_sample`].
+    pub fn current_channel(&mut self) -> Result<Channel, AdcError<Bus::Error>> {
+        Ok(self.read::<1, StatusRegister>()?.channel())
+    }
+
+    /// Reads the latest conversion without waiting for it.
+    ///
+    /// Meant for when readiness is already known, e.g. after
+    /// [`DataReady::wait_for_data`](data_ready::DataReady::wait_for_data).
+    ///
+    /// With [`DataRegisterLength::SixteenBits`] selected only 2 bytes are clocked out, and the
+    /// result is shifted up to 24 bits so codes keep the same scale whatever the word length.
+    pub fn read_data(&mut self) -> Result<DataRegister, AdcError<Bus::Error>> {
+        let len = self.framer.start_read_data(false)?;
+        self.transaction(|spi, buf| spi.read_register(&mut buf[..len]))?;
+        self.framer.finish_read_data()
+    }
+
+    /// Reads the latest conversion together with the status byte in a single transaction.
+    ///
+    /// This is the only way to know for sure which channel a sample came from when several
+    /// channels are enabled. Requires DATA_STAT to be enabled in the [`InterfaceModeRegister`],
+    /// fails with [`AdcError::DataStatDisabled`] otherwise.
+    pub fn read_data_and_status(&mut self) -> Result<DataAndStatusRegister, AdcError<Bus::Error>> {
+        if !self.framer.read_configuration.data_read_configuration.status_included {
+            return Err(AdcError::DataStatDisabled);
+        }
+
+        let len = self.framer.start_read_data(true)?;
+        self.transaction(|spi, buf| spi.read_register(&mut buf[..len]))?;
+        self.framer.finish_read_data_and_status()
+    }
+
+    /// Reads the latest conversion as a signed code, decoded with the output coding of the setup
+    /// the channel it came from converts with, see [`DataRegister::as_signed`].
+    ///
+    /// The channel is taken from the status byte when DATA_STAT is enabled, otherwise the status
+    /// register is read straight after the data, which costs an extra transaction and can see the
+    /// next conversion's channel if one completes in between. The channel and setup
+    /// configuration are taken from what was last written through the driver, see
+    /// [`ADC::output_coding`].
+    pub fn read_signed(&mut self) -> Result<i32, AdcError<Bus::Error>> {
+        let (data, channel) = if self.framer.read_configuration.data_read_configuration.status_included {
+            let frame = self.read_data_and_status()?;
+            (DataRegister::new().with_data(frame.data()), StatusRegister::from_buffer(&[frame.status()]).channel())
+        } else {
+            let data = self.read_data()?;
+            (data, self.read::<1, StatusRegister>()?.channel())
+        };
+
+        let setup = self.framer.channel_setups[channel.into_bits() as usize];
+        Ok(data.as_signed(self.output_coding(setup)))
+    }
+
+    /// Reads the latest conversion and fails if the status register flags an error with it.
+    ///
+    /// With DATA_STAT enabled the status byte comes with the data in the same transaction,
+    /// otherwise the status register is read straight after the data, which costs an extra
+    /// transaction. Errors map to [`AdcError::AdcFault`], [`AdcError::RegisterError`] and
+    /// [`AdcError::CrcMismatch`].
+    ///
+    /// With [`ADC::enable_reference_monitor`] an ADC error on a setup using the external
+    /// reference is looked into further, and reported as [`AdcError::ReferenceOutOfRange`] if the
+    /// monitor is what raised it. That costs a few more transactions, on the error path only.
+    pub fn read_data_checked(&mut self) -> Result<DataRegister, AdcError<Bus::Error>> {
+        let (data, status) = if self.framer.read_configuration.data_read_configuration.status_included {
+            let frame = self.read_data_and_status()?;
+            (DataRegister::new().with_data(frame.data()), StatusRegister::from_buffer(&[frame.status()]))
+        } else {
+            let data = self.read_data()?;
+            (data, self.read::<1, StatusRegister>()?)
+        };
+
+        if status.adc_error() && self.reference_monitor && self.reference_flagged(status.channel())? {
+            return Err(AdcError::ReferenceOutOfRange);
+        }
+        check_status(&status)?;
+        Ok(data)
+    }
+
+    /// Whether the reference monitor is holding the ERROR input low while `channel` uses the
+    /// external reference.
+    fn reference_flagged(&mut self, channel: Channel) -> Result<bool, AdcError<Bus::Error>> {
+        let (_, setup) = self.channel_setup(channel)?;
+        // every setup register shares the layout of setup 0
+        let reference = SetupConfig0Register::from_buffer(&self.read_setup_config(setup)?).ref_sel();
+        if reference != ReferenceSource::External {
+            return Ok(false);
+        }
+
+        // ERR_DAT reads back the level of the active low ERROR input
+        Ok(!self.read::<2, GPIOConfigRegister>()?.err_dat())
+    }
+
+    /// Puts the interface into continuous read mode.
+    ///
+    /// The ADC has to be in [`Mode::ContinuousConversion`] already. While continuous read is
+    /// active only [`ADC::read_next`], [`ADC::stop_continuous_read`] and [`ADC::reset`] talk to
+    /// the device, register access fails with [`AdcError::ContinuousReadActive`].
+    pub fn start_continuous_read(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        let interface_mode = self.read::<2, InterfaceModeRegister>()?.with_cont_read(true);
+        self.write(&interface_mode)
+    }
+
+    /// Clocks out the next conversion while continuous read is active.
+    ///
+    /// No command byte is sent, only the data word followed by the status byte and checksum when
+    /// those are enabled. Call this once DOUT/RDY has gone low; each call reads exactly one
+    /// conversion and a conversion that isn't read before the next one completes is lost.
+    pub fn read_next(&mut self) -> Result<DataRegister, AdcError<Bus::Error>> {
+        let frame = self.framer.start_read_next()?;
+        self.transaction(|spi, buf| spi.read_register(&mut buf[frame]))?;
+        self.framer.finish_read_next()
+    }
+
+    /// Reads up to `out.len()` conversions in continuous read mode, returning how many were read.
+    ///
+    /// This is a polled loop, not a single DMA transfer: each conversion has to be clocked out
+    /// while DOUT/RDY is low, so `rdy` is polled and the frames are read one at a time with
+    /// [`ADC::read_next`]. Stops early if `rdy` doesn't fall within a second, e.g. because
+    /// conversions have stopped.
+    ///
+    /// CS has to stay low for the whole burst, DOUT/RDY is tristated while it is high and
+    /// `rdy` reads nothing meaningful. Manage CS with [`ADC::with_manual_cs`], which holds it
+    /// low for the burst. A bus that drives CS itself, such as the one
+    /// [`ADC::new_with_peripherals`] sets up, raises it after every frame and isn't suitable.
+    pub fn read_burst<Rdy: embedded_hal::digital::InputPin>(&mut self, rdy: &mut Rdy, out: &mut [u32]) -> Result<usize, AdcError<Bus::Error>> {
+        self.with_cs_held(|adc| adc.read_burst_held(rdy, out))
+    }
+
+    /// Like [`ADC::read_burst`], pairing each conversion with its time as assigned by `clock`.
+    ///
+    /// A conversion counts as seen when `rdy` is found low, before it is read. Keep `clock`
+    /// across bursts of the same stream so they share one time axis.
+    pub fn read_burst_timestamped<Rdy: embedded_hal::digital::InputPin>(&mut self, rdy: &mut Rdy, clock: &mut SampleClock, out: &mut [(Instant, u32)]) -> Result<usize, AdcError<Bus::Error>> {
+        self.with_cs_held(|adc| {
+            for (count, sample) in out.iter_mut().enumerate() {
+                if !wait_rdy_low(rdy, &mut adc.delay) {
+                    return Ok(count);
+                }
+
+                let time = clock.stamp(Instant::now());
+                *sample = (time, adc.read_next()?.data());
+            }
+
+            Ok(out.len())
+        })
+    }
+
+    fn read_burst_held<Rdy: embedded_hal::digital::InputPin>(&mut self, rdy: &mut Rdy, out: &mut [u32]) -> Result<usize, AdcError<Bus::Error>> {
+        for (count, sample) in out.iter_mut().enumerate() {
+            if !wait_rdy_low(rdy, &mut self.delay) {
+                return Ok(count);
+            }
+
+            *sample = self.read_next()?.data();
+        }
+
+        Ok(out.len())
+    }
+
+    /// The conversions of continuous read mode as an iterator, each read as `rdy` signals it
+    /// like in [`ADC::read_burst`].
+    ///
+    /// A failed read is yielded as an error and the stream carries on with the next
+    /// conversion. It ends when `rdy` doesn't fall within a second, e.g. because conversions
+    /// have stopped. CS has to stay low as for [`ADC::read_burst`], with [`ADC::with_manual_cs`]
+    /// it is held low until the iterator is dropped.
+    ///
+    /// ```no_run
+    /// # use dc_load_control_loop_rs::adc::ADC;
+    /// # use dc_load_control_loop_rs::adc::scaling::Scaling;
+    /// # use dc_load_control_loop_rs::adc::OutputCoding;
+    /// # fn capture<Bus: embedded_hal::spi::SpiBus>(adc: &mut ADC<Bus>, rdy: &mut impl embedded_hal::digital::InputPin, scaling: Scaling) {
+    /// let mut peak = 0.0f32;
+    /// for amps in adc.samples(rdy).take(1000).flatten().map(|code| scaling.code_to_amps(code, OutputCoding::Bipolar)) {
+    ///     peak = peak.max(amps);
+    /// }
+    /// # }
+    /// ```
+    pub fn samples<'a, Rdy: embedded_hal::digital::InputPin>(&'a mut self, rdy: &'a mut Rdy) -> Samples<'a, Bus, Rdy, AdcDelay, Part> {
+        let release_cs = !self.cs_held;
+        self.hold_cs();
+
+        Samples { adc: self, rdy, release_cs, done: false }
+    }
+
+    /// Leaves continuous read mode by issuing a dummy read of the data register, 0x44, and
+    /// clears CONTREAD in the cached interface mode.
+    ///
+    /// Like [`ADC::read_next`] this has to happen while DOUT/RDY is low, with CS held low, otherwise
+    /// the device doesn't recognise the command. The command takes the place of the first byte
+    /// of a conversion, which is clocked out in full, status and checksum included, and
+    /// discarded. A frame of any other length leaves the interface out of step, see
+    /// [`ADC::reset`] to recover from that.
+    pub fn stop_continuous_read(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        let len = self.framer.start_stop_continuous_read()?;
+        self.transaction(|spi, buf| spi.read_register(&mut buf[..len]))?;
+        self.framer.finish_stop_continuous_read();
+
+        Ok(())
+    }
+
+    /// Sets or clears SING_CYC in the ADC mode register, leaving the rest of the mode as is.
+    ///
+    /// With SING_CYC set a single enabled channel only outputs fully settled conversions, at the
+    /// cost of a lower rate. It has no effect on the sinc5 + sinc1 filter at 10 kSPS and below,
+    /// or on a sequence of several channels, which always settles fully on every channel. Use
+    /// [`sequence_cycle_time_us`] to see what it does to the loop timing.
+    pub fn set_single_cycle(&mut self, enabled: bool) -> Result<(), AdcError<Bus::Error>> {
+        let mode = self.read::<2, AdcModeRegister>()?;
+        self.write(&mode.with_sing_cyc(enabled))
+    }
+
+    /// Enables `channel`, converting `input_pos` against `input_neg` using `setup`.
+    ///
+    /// Fails with [`AdcError::InvalidInputPair`] without writing anything if the inputs can't be
+    /// paired, see [`Input::check_pair`], and with [`AdcError::NotOnPart`] if the part doesn't
+    /// have `channel`, `setup` or either input.
+    pub fn configure_channel(&mut self, channel: Channel, setup: Setup, input_pos: Input, input_neg: Input) -> Result<(), AdcError<Bus::Error>> {
+        Input::check_pair(input_pos, input_neg).map_err(AdcError::InvalidInputPair)?;
+
+        // every channel register shares the layout of channel 0
+        let register = Channel0Register::new().with_ch_en(true).with_setup_sel(setup).with_ainpos(input_pos).with_ainneg(input_neg);
+        self.write_at(Channel0Register::ID + channel.into_bits(), &register)
+    }
+
+    /// Takes `channel` out of the conversion sequence, leaving the rest of its configuration as is.
+    pub fn disable_channel(&mut self, channel: Channel) -> Result<(), AdcError<Bus::Error>> {
+        let id = Channel0Register::ID + channel.into_bits();
+        let register = self.read_at::<2, Channel0Register>(id)?.with_ch_en(false);
+        self.write_at(id, &register)
+    }
+
+    /// Puts the device in standby, keeping register contents, until [`ADC::wake`].
+    ///
+    /// The internal reference and an external crystal keep running unless disabled in the ADC
+    /// mode register beforehand.
+    pub fn standby(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        let mode = self.read::<2, AdcModeRegister>()?;
+        self.write(&mode.with_mode(Mode::Standby))?;
+        if let PowerState::Awake = self.power_state {
+            self.power_state = PowerState::Standby(mode);
+        }
+
+        Ok(())
+    }
+
+    /// Powers the device down completely until [`ADC::wake`].
+    ///
+    /// Every register loses its contents, so after waking only the ADC mode is restored and the
+    /// rest of the configuration has to be applied again.
+    pub fn power_down(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        // the device only enters power-down from standby
+        self.standby()?;
+        let mode = self.read::<2, AdcModeRegister>()?;
+        self.write(&mode.with_mode(Mode::PowerDown))?;
+        if let PowerState::Standby(previous_mode) = self.power_state {
+            self.power_state = PowerState::PowerDown(previous_mode);
+        }
+
+        Ok(())
+    }
+
+    /// Brings the device back from [`ADC::standby`] or [`ADC::power_down`] into the ADC mode
+    /// it was in before, or continuous conversion if it wasn't put to sleep by the driver.
+    ///
+    /// Leaving power-down takes a serial interface reset and the 500 µs it needs, spent on the
+    /// driver's delay. Either way the first conversion afterwards needs the filter's full
+    /// settling time (see [`OutputDataRate::settling_time_us`]), and if the internal reference
+    /// was off it has to settle too before conversions are accurate, see Figure 19 of the
+    /// datasheet.
+    pub fn wake(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        let mode = match self.power_state {
+            PowerState::Awake => self.read::<2, AdcModeRegister>()?.with_mode(Mode::ContinuousConversion),
+            PowerState::Standby(mode) => mode,
+            PowerState::PowerDown(mode) => {
+                self.reset()?;
+                // the reference was off while powered down and comes up with the reset
+                if mode.ref_enable() {
+                    self.delay.delay_us(self.settle_times.reference_us);
+                }
+                mode
+            }
+        };
+
+        self.write(&mode)?;
+        self.power_state = PowerState::Awake;
+
+        Ok(())
+    }
+
+    /// Turns SYNC/ERROR into an error input for an external reference monitor, e.g. a window
+    /// comparator on REF+ driving the pin low while the reference is missing or out of range.
+    ///
+    /// The part has no reference detection of its own, a bad external reference only shows up
+    /// as an ADC error alongside an input overrange. With the pin low ADC_ERROR is set and
+    /// [`ADC::read_data_checked`] tells the two apart, for setups using the external reference;
+    /// the internal reference isn't checked. SYNC is disabled, the pin can't do both.
+    pub fn enable_reference_monitor(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        let config = self.read::<2, GPIOConfigRegister>()?;
+        self.write(&config.with_sync_en(false).with_err_en(SyncErrorPinMode::ErrorInput))?;
+        self.reference_monitor = true;
+
+        Ok(())
+    }
+
+    /// Turns on the device's register integrity check and keeps the checksum it computes over
+    /// the configuration as written, for [`ADC::verify_registers`] to compare against.
+    ///
+    /// Write the whole configuration first. The baseline is taken from the device rather than
+    /// worked out from the registers written through the driver, which would miss anything
+    /// written around it. REG_CHECK is cleared and set again, the device only works REGCHECK
+    /// out when the bit goes on.
+    ///
+    /// Any register write through the driver sets REG_ERROR while the check is on, including
+    /// the mode writes of [`ADC::standby`], [`ADC::wake`], [`ADC::read_single`] and
+    /// [`ADC::set_clock_source`], so a write drops the baseline and [`ADC::verify_registers`]
+    /// fails until this is called again. So does [`ADC::reset`], which turns the check off.
+    pub fn enable_register_check(&mut self) -> Result<(), AdcError<Bus::Error>> {
+        let interface_mode = self.read::<2, InterfaceModeRegister>()?;
+        self.write(&interface_mode.with_reg_check(false))?;
+        self.write(&interface_mode.with_reg_check(true))?;
+        self.register_check = Some(self.read::<3, RegisterCheck>()?.reg_check());
+
+        Ok(())
+    }
+
+    /// Checks that the configuration hasn't changed since [`ADC::enable_register_check`],
+    /// e.g. through EMI on a long unattended run. Returns `false` if it has.
+    ///
+    /// Both the REG_ERROR flag of the status register and the current REGCHECK value are looked
+    /// at, two transactions. Fails with [`AdcError::RegisterCheckDisabled`] without a baseline,
+    /// e.g. after a register write.
+    pub fn verify_registers(&mut self) -> Result<bool, AdcError<Bus::Error>> {
+        let Some(expected) = self.register_check else {
+            return Err(AdcError::RegisterCheckDisabled);
+        };
+
+        if self.read::<1, StatusRegister>()?.register_error() {
+            return Ok(false);
+        }
+        Ok(self.read::<3, RegisterCheck>()?.reg_check() == expected)
+    }
+
+    /// Makes GPIO `pin` (0 or 1) an output, or an input when `output` is false.
+    ///
+    /// The configuration of the other GPIO is left as is.
+    pub fn configure_gpio(&mut self, pin: u8, output: bool) -> Result<(), AdcError<Bus::Error>> {
+        Self::check_gpio_pin(pin)?;
+
+        let config = self.read::<2, GPIOConfigRegister>()?;
+        let config = if pin == 0 {
+            config.with_gpio0_output_enable(output).with_gpio0_input_enable(!output)
+        } else {
+            config.with_gpio1_output_enable(output).with_gpio1_input_enable(!output)
+        };
+        self.write(&config)
+    }
+
+    /// Drives GPIO `pin` (0 or 1) high or low, leaving the other GPIO as is.
+    ///
+    /// The pin has to be configured as an output with [`ADC::configure_gpio`] for the level to
+    /// show up on it.
+    pub fn set_gpio(&mut self, pin: u8, level: bool) -> Result<(), AdcError<Bus::Error>> {
+        Self::check_gpio_pin(pin)?;
+
+        let config = self.read::<2, GPIOConfigRegister>()?;
+        let config = if pin == 0 { config.with_gpio0_data(level) } else { config.with_gpio1_data(level) };
+        self.write(&config)
+    }
+
+    fn check_gpio_pin(pin: u8) -> Result<(), AdcError<Bus::Error>> {
+        if pin > 1 {
+            return Err(AdcError::InvalidPin);
+        }
+        Ok(())
+    }
+
+    /// Waits for a conversion of `channel` and reads it, skipping conversions of other channels.
+    fn read_channel(&mut self, channel: Channel) -> Result<DataRegister, AdcError<Bus::Error>> {
+        for _ in 0..MAX_READY_POLLS {
+            let status = self.read::<1, StatusRegister>()?;
+            if status.ready() && status.channel() == channel {
+                return self.read_data();
+            }
+        }
+
+        Err(AdcError::Timeout)
+    }
+
+    /// Writes the registers of `config`, skipping those equal in `current` when given.
+    fn apply(&mut self, config: &AdcConfig, current: Option<&AdcConfig>) -> Result<(), AdcError<Bus::Error>> {
+        macro_rules! write_changed {
+            ($write:ident: $($field:ident),+) => {
+                $(
+                    if current.is_none_or(|current| current.$field != config.$field) {
+                        self.$write(&config.$field)?;
+                    }
+                )+
+            };
+        }
+
+        write_changed!(write: interface_mode, gpio_config);
+        write_changed!(write: setup_config0, setup_config1, setup_config2, setup_config3);
+        write_changed!(write_filter_config_register: filter_config0, filter_config1, filter_config2, filter_config3);
+        write_changed!(write: channel0, channel1, channel2, channel3, adc_mode);
+
+        Ok(())
+    }
+
+    /// Writes a filter configuration register in whichever layout `config` holds.
+    fn write_filter_config_register<D: WritableRegister<2>, S: WritableRegister<2>>(&mut self, config: &FilterConfig<D, S>) -> Result<(), AdcError<Bus::Error>> {
+        self.write_filter_config_at(D::get_id(), config)
+    }
+
+    /// [`ADC::write_filter_config_register`] for the register at `id`.
+    fn write_filter_config_at<D: WritableRegister<2>, S: WritableRegister<2>>(&mut self, id: u8, config: &FilterConfig<D, S>) -> Result<(), AdcError<Bus::Error>> {
+        match config {
+            FilterConfig::Default(register) => self.write_at(id, register),
+            FilterConfig::DirectSinc3Map(register) => self.write_at(id, register),
+        }
+    }
+
+    /// Reads a filter configuration register as whichever layout its SINC3_MAP bit selects.
+    fn read_filter_config_register<D: Register<2>, S: Register<2>>(&mut self) -> Result<FilterConfig<D, S>, AdcError<Bus::Error>> {
+        self.read_filter_config_at(D::get_id())
+    }
+
+    /// [`ADC::read_filter_config_register`] for the register at `id`.
+    fn read_filter_config_at<D: Register<2>, S: Register<2>>(&mut self, id: u8) -> Result<FilterConfig<D, S>, AdcError<Bus::Error>> {
+        let buffer: [u8; 2] = self.read_raw(id, 2)?.try_into().unwrap();
+        if buffer[0] & 0x80 != 0 {
+            return Ok(FilterConfig::DirectSinc3Map(S::from_buffer(&buffer)));
+        }
+
+        let register = D::from_buffer(&buffer);
+        register.validate().map_err(AdcError::InvalidValue)?;
+        Ok(FilterConfig::Default(register))
+    }
+
+    /// Whether `channel` is enabled, and the setup it converts with.
+    fn channel_setup(&mut self, channel: Channel) -> Result<(bool, Setup), AdcError<Bus::Error>> {
+        let register = self.read_at::<2, Channel0Register>(Channel0Register::ID + channel.into_bits())?;
+        Ok((register.ch_en(), register.setup_sel()))
+    }
+
+    /// Reads the raw setup configuration register of `setup`.
+    fn read_setup_config(&mut self, setup: Setup) -> Result<[u8; 2], AdcError<Bus::Error>> {
+        Ok(self.read_at::<2, SetupConfig0Register>(SetupConfig0Register::ID + setup.into_bits())?.to_buffer())
+    }
+
+    /// Writes the raw setup configuration register of `setup`.
+    fn write_setup_config(&mut self, setup: Setup, buffer: [u8; 2]) -> Result<(), AdcError<Bus::Error>> {
+        self.write_at(SetupConfig0Register::ID + setup.into_bits(), &SetupConfig0Register::from_buffer(&buffer))
+    }
+
+    /// Starts a calibration in `mode` and waits for the device to signal it has finished.
+    ///
+    /// Returns the ADC mode register as it was before the calibration was started.
+    fn calibrate(&mut self, mode: Mode) -> Result<AdcModeRegister, AdcError<Bus::Error>> {
+        let previous_mode = self.read::<2, AdcModeRegister>()?;
+        self.write(&previous_mode.with_mode(mode))?;
+        self.wait_ready(MAX_READY_POLLS)?;
+
+        Ok(previous_mode)
+    }
+}
+
+/// Waits for `rdy` to go low, giving up after [`BURST_READY_TIMEOUT_US`]. Returns whether it did.
+fn wait_rdy_low<Rdy: embedded_hal::digital::InputPin>(rdy: &mut Rdy, delay: &mut impl DelayNs) -> bool {
+    let mut waited_us = 0;
+    // a pin that can't be read counts as not ready
+    while !rdy.is_low().unwrap_or(false) {
+        if waited_us == BURST_READY_TIMEOUT_US {
+            return false;
+        }
+        delay.delay_us(1);
+        waited_us += 1;
+    }
+    true
+}
+
+/// Every channel `Part` has, in order.
+fn part_channels<Part: Device>() -> impl Iterator<Item = Channel> {
+    (0..Part::CHANNELS as u8).map(Channel::from_bits)
+}
+
+/// Turns the error flags of `status` into the matching [`AdcError`].
+fn check_status<E>(status: &StatusRegister) -> Result<(), AdcError<E>> {
+    if status.adc_error() {
+        return Err(AdcError::AdcFault);
+    }
+    if status.register_error() {
+        return Err(AdcError::RegisterError);
+    }
+    if status.crc_error() {
+        return Err(AdcError::CrcMismatch);
+    }
+    Ok(())
+}
+
+/// Iterator over the conversions of continuous read mode, see [`ADC::samples`].
+pub struct Samples<'a, Bus: RegisterTransport, Rdy, AdcDelay: DelayNs = BusyDelay, Part: Device = Ad7175_2> {
+    adc: &'a mut ADC<Bus, AdcDelay, Part>,
+    rdy: &'a mut Rdy,
+    /// Whether CS was held by the iterator rather than already by the caller.
+    release_cs: bool,
+    done: bool,
+}
+
+impl<Bus: RegisterTransport, Rdy: embedded_hal::digital::InputPin, AdcDelay: DelayNs, Part: Device> Iterator for Samples<'_, Bus, Rdy, AdcDelay, Part> {
+    type Item = Result<u32, AdcError<Bus::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || !wait_rdy_low(self.rdy, &mut self.adc.delay) {
+            self.done = true;
+            return None;
+        }
+
+        Some(self.adc.read_next().map(|data| data.data()))
+    }
+}
+
+impl<'a, Bus: RegisterTransport, Rdy: embedded_hal::digital::InputPin, AdcDelay: DelayNs, Part: Device> Samples<'a, Bus, Rdy, AdcDelay, Part> {
+    /// Pairs each conversion with its time as assigned by `clock`, see [`SampleClock`].
+    ///
+    /// A conversion counts as seen once it has been read, which shifts every stamp by about
+    /// the time a read takes.
+    pub fn timestamped(mut self, mut clock: SampleClock) -> impl Iterator<Item = Result<(Instant, u32), AdcError<Bus::Error>>> + 'a {
+        core::iter::from_fn(move || {
+            let sample = self.next()?;
+            Some(sample.map(|code| (clock.stamp(Instant::now()), code)))
+        })
+    }
+}
+
+impl<Bus: RegisterTransport, Rdy, AdcDelay: DelayNs, Part: Device> Drop for Samples<'_, Bus, Rdy, AdcDelay, Part> {
+    fn drop(&mut self) {
+        if self.release_cs {
+            // nothing to report a failed flush to, and CS goes up regardless
+            let _ = self.adc.release_cs();
+        }
+    }
+}
+
+/// How the [`Sequencer`] lets the channels on a setup settle after the multiplexer switches to
+/// them.
+///
+/// The ADC itself only outputs fully settled conversions while sequencing, so this is about
+/// whatever sits in front of the inputs, e.g. an RC filter or an external buffer.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Settling {
+    /// Every conversion is returned.
+    #[default]
+    Immediate,
+    /// The first `n` conversions of each channel on the setup are dropped once the sequence
+    /// starts, giving the front end `n` turns of the sequencer to charge up.
+    Discard(u8),
+    /// The ADC waits this long after every channel switch before it starts converting.
+    ///
+    /// The delay is set in the ADC mode register and applies to every channel, so the longest
+    /// delay asked for on any setup is the one programmed.
+    Delay(Delay),
+}
+
+/// Scans a set of channels and tags every sample with the channel that produced it.
+pub struct Sequencer<'a, Bus: RegisterTransport, AdcDelay: DelayNs = BusyDelay, Part: Device = Ad7175_2> {
+    adc: &'a mut ADC<Bus, AdcDelay, Part>,
+    /// Setup of each channel, indexed by channel, `None` if disabled or not known yet.
+    setups: [Option<Setup>; MAX_CHANNELS],
+    /// Conversions still to be dropped on each channel, indexed by channel.
+    discard: [u8; MAX_CHANNELS],
+}
+
+impl<'a, Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device> Sequencer<'a, Bus, AdcDelay, Part> {
+    /// Configures and enables each `(channel, setup, input_pos, input_neg)` entry, disables every
+    /// other channel so no stale channel shows up in the rotation, and enables DATA_STAT so each
+    /// sample carries its channel.
+    ///
+    /// Fails with [`AdcError::NotOnPart`] before configuring anything if an entry names a
+    /// channel the part doesn't have.
+    pub fn new(adc: &'a mut ADC<Bus, AdcDelay, Part>, channels: &[(Channel, Setup, Input, Input)]) -> Result<Self, AdcError<Bus::Error>> {
+        if let Some(&(channel, ..)) = channels.iter().find(|(channel, ..)| !Part::has_channel(*channel)) {
+            return Err(AdcError::NotOnPart(NotOnPart::Channel(channel)));
+        }
+
+        let mut setups = [None; MAX_CHANNELS];
+        for channel in part_channels::<Part>() {
+            match channels.iter().find(|(enabled, ..)| *enabled == channel) {
+                Some(&(_, setup, input_pos, input_neg)) => {
+                    adc.configure_channel(channel, setup, input_pos, input_neg)?;
+                    setups[channel.into_bits() as usize] = Some(setup);
+                }
+                None => adc.disable_channel(channel)?,
+            }
+        }
+
+        let interface_mode = adc.read::<2, InterfaceModeRegister>()?.with_data_stat(true);
+        adc.write(&interface_mode)?;
+
+        Ok(Self { adc, setups, discard: [0; MAX_CHANNELS] })
+    }
+
+    /// Scans whatever channels the ADC is already set up for, e.g. by an earlier
+    /// [`Sequencer::new`], without touching its configuration.
+    ///
+    /// DATA_STAT has to be enabled, [`Sequencer::next_sample`] fails with
+    /// [`AdcError::DataStatDisabled`] otherwise.
+    pub fn attach(adc: &'a mut ADC<Bus, AdcDelay, Part>) -> Self {
+        Self { adc, setups: [None; MAX_CHANNELS], discard: [0; MAX_CHANNELS] }
+    }
+
+    /// Applies `settling` to every enabled channel on `setup`.
+    ///
+    /// Call it once per setup that needs it, right after [`Sequencer::new`] or
+    /// [`Sequencer::attach`]. After an attach the channel registers are read to find out which
+    /// channels use `setup`.
+    pub fn with_settling(mut self, setup: Setup, settling: Settling) -> Result<Self, AdcError<Bus::Error>> {
+        match settling {
+            Settling::Immediate => {}
+            Settling::Discard(count) => {
+                for channel in part_channels::<Part>() {
+                    if self.setup_of(channel)? == Some(setup) {
+                        self.discard[channel.into_bits() as usize] = count;
+                    }
+                }
+            }
+            Settling::Delay(delay) => {
+                let mode = self.adc.read::<2, AdcModeRegister>()?;
+                if delay.into_bits() > mode.delay().into_bits() {
+                    self.adc.write(&mode.with_delay(delay))?;
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Setup `channel` converts with, or `None` if it's disabled.
+    fn setup_of(&mut self, channel: Channel) -> Result<Option<Setup>, AdcError<Bus::Error>> {
+        let index = channel.into_bits() as usize;
+        if self.setups[index].is_none() {
+            let (enabled, setup) = self.adc.channel_setup(channel)?;
+            self.setups[index] = enabled.then_some(setup);
+        }
+
+        Ok(self.setups[index])
+    }
+
+    /// Waits for the next conversion and returns it along with the channel it was taken on.
+    ///
+    /// Conversions dropped by [`Settling::Discard`] are read and skipped, so this can take
+    /// several conversions to return while the sequence settles.
+    pub fn next_sample(&mut self) -> Result<(Channel, u32), AdcError<Bus::Error>> {
+        loop {
+            self.adc.wait_ready(MAX_READY_POLLS)?;
+
+            let frame = self.adc.read_data_and_status()?;
+            let status = StatusRegister::from_buffer(&[frame.status()]);
+            let channel = status.channel();
+
+            let discard = &mut self.discard[channel.into_bits() as usize];
+            if *discard == 0 {
+                return Ok((channel, frame.data()));
+            }
+            *discard -= 1;
+        }
     }
 }
 
@@ -102,21 +1740,57 @@ macro_rules! bitfield_enum {
         }
         impl $name {
             pub const fn into_bits(self) -> $repr { self as $repr }
+            /// Decodes `value`, panicking if it doesn't name a variant.
+            ///
+            /// Used by the register accessors, which have to be const. Use
+            /// [`Self::try_from_bits`] on anything that came off the wire.
             pub const fn from_bits(value: $repr) -> Self {
                 match value {
                     $( $value => $name::$variant, )+
                     _ => panic!(concat!("Invalid value for ", stringify!($name))),
                 }
             }
+            /// Decodes `value`, reporting it as invalid if it doesn't name a variant.
+            pub const fn try_from_bits(value: $repr) -> Result<Self, InvalidValue> {
+                match value {
+                    $( $value => Ok($name::$variant), )+
+                    _ => Err(InvalidValue { type_name: stringify!($name), value: value as u32 }),
+                }
+            }
+        }
+        impl FieldValue for $name {
+            fn validate_bits(bits: u32) -> Result<(), InvalidValue> {
+                Self::try_from_bits(bits as $repr).map(|_| ())
+            }
         }
     };
 }
 
+/// A register field held a value that doesn't decode to any variant of its type.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidValue {
+    pub type_name: &'static str,
+    pub value: u32,
+}
+
+/// A type a register field can have, able to tell whether raw field bits decode to it.
+pub trait FieldValue {
+    fn validate_bits(_bits: u32) -> Result<(), InvalidValue> {
+        Ok(())
+    }
+}
+
+impl FieldValue for bool {}
+impl FieldValue for u8 {}
+impl FieldValue for u16 {}
+impl FieldValue for u32 {}
+
 bitfield_enum! {
     /// ADC channel selection.
     ///
     /// Used in the Status Register and Channel Registers to select or indicate the active channel.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    /// How many of them a part has is [`Device::CHANNELS`], the AD7175-2 stops at [`Channel::Ch3`].
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum Channel: u8 {
         /// Channel 0
         Ch0 = 0x00,
@@ -126,6 +1800,30 @@ bitfield_enum! {
         Ch2 = 0x02,
         /// Channel 3
         Ch3 = 0x03,
+        /// Channel 4
+        Ch4 = 0x04,
+        /// Channel 5
+        Ch5 = 0x05,
+        /// Channel 6
+        Ch6 = 0x06,
+        /// Channel 7
+        Ch7 = 0x07,
+        /// Channel 8
+        Ch8 = 0x08,
+        /// Channel 9
+        Ch9 = 0x09,
+        /// Channel 10
+        Ch10 = 0x0a,
+        /// Channel 11
+        Ch11 = 0x0b,
+        /// Channel 12
+        Ch12 = 0x0c,
+        /// Channel 13
+        Ch13 = 0x0d,
+        /// Channel 14
+        Ch14 = 0x0e,
+        /// Channel 15
+        Ch15 = 0x0f,
     }
 }
 
@@ -134,7 +1832,7 @@ bitfield_enum! {
     ///
     /// Used in the ADC Mode Register to select the delay between conversions.
     /// The delay can be used to allow external circuitry to settle before a conversion starts.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum Delay: u8 {
         /// No delay (0 µs)
         ZeroMicroseconds = 0x00,
@@ -159,7 +1857,7 @@ bitfield_enum! {
     /// ADC operating mode.
     ///
     /// Used in the ADC Mode Register to select the conversion mode or calibration operation.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum Mode: u8 {
         /// Continuous conversion mode. The ADC continuously converts and updates the data register.
         ContinuousConversion = 0x00,
@@ -182,7 +1880,7 @@ bitfield_enum! {
     /// Clock source selection for the ADC.
     ///
     /// Used in the ADC Mode Register to select the master clock source.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum ClockSource: u8 {
         /// Internal oscillator (default, 16 MHz).
         Internal = 0x00,
@@ -199,7 +1897,7 @@ bitfield_enum! {
     /// CRC mode for communication error checking.
     ///
     /// Used in the Interface Mode Register to select CRC or XOR error checking.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum Crc: u8 {
         /// CRC disabled.
         Disabled = 0x00,
@@ -214,7 +1912,7 @@ bitfield_enum! {
     /// Data register length selection.
     ///
     /// Used in the Interface Mode Register to select the number of bits in the data register.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum DataRegisterLength: u8 {
         /// 24-bit data register (default).
         TwentyFourBits = 0x00,
@@ -227,7 +1925,7 @@ bitfield_enum! {
     /// SYNC/ERROR pin mode selection.
     ///
     /// Used in the GPIO Configuration Register to select the function of the SYNC/ERROR pin.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum SyncErrorPinMode: u8 {
         /// Pin disabled.
         Disabled = 0x00,
@@ -244,7 +1942,8 @@ bitfield_enum! {
     /// Setup selection for channel configuration.
     ///
     /// Used in Channel Registers to select which setup configuration to use for a channel.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    /// How many of them a part has is [`Device::SETUPS`], the AD7175-2 stops at [`Setup::Setup3`].
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum Setup: u8 {
         /// Use Setup 0
         Setup0 = 0x00,
@@ -254,14 +1953,24 @@ bitfield_enum! {
         Setup2 = 0x02,
         /// Use Setup 3
         Setup3 = 0x03,
+        /// Use Setup 4
+        Setup4 = 0x04,
+        /// Use Setup 5
+        Setup5 = 0x05,
+        /// Use Setup 6
+        Setup6 = 0x06,
+        /// Use Setup 7
+        Setup7 = 0x07,
     }
 }
 
 bitfield_enum! {
     /// Input multiplexer selection.
     ///
-    /// Used in Channel Registers to select the positive or negative input for a channel.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    /// Used in Channel Registers to select the positive or negative input for a channel. How many
+    /// analog inputs a part has is [`Device::ANALOG_INPUTS`], the AD7175-2 stops at
+    /// [`Input::Analog4`]. The internal inputs are on every part.
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum Input: u8 {
         /// Analog input 0
         Analog0 = 0x00,
@@ -273,6 +1982,30 @@ bitfield_enum! {
         Analog3 = 0x03,
         /// Analog input 4
         Analog4 = 0x04,
+        /// Analog input 5
+        Analog5 = 0x05,
+        /// Analog input 6
+        Analog6 = 0x06,
+        /// Analog input 7
+        Analog7 = 0x07,
+        /// Analog input 8
+        Analog8 = 0x08,
+        /// Analog input 9
+        Analog9 = 0x09,
+        /// Analog input 10
+        Analog10 = 0x0a,
+        /// Analog input 11
+        Analog11 = 0x0b,
+        /// Analog input 12
+        Analog12 = 0x0c,
+        /// Analog input 13
+        Analog13 = 0x0d,
+        /// Analog input 14
+        Analog14 = 0x0e,
+        /// Analog input 15
+        Analog15 = 0x0f,
+        /// Analog input 16
+        Analog16 = 0x10,
         /// Temperature sensor positive
         TemperatureSensorPos = 0x11,
         /// Temperature sensor negative
@@ -288,11 +2021,56 @@ bitfield_enum! {
     }
 }
 
+/// A pair of inputs the crosspoint multiplexer can route but that don't measure anything, see
+/// [`Input::check_pair`].
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidInputPair {
+    pub pos: Input,
+    pub neg: Input,
+}
+
+impl Input {
+    /// The input an internal source is measured against, `None` for the analog inputs.
+    pub fn partner(&self) -> Option<Input> {
+        match self {
+            Input::Analog0 | Input::Analog1 | Input::Analog2 | Input::Analog3 | Input::Analog4
+            | Input::Analog5 | Input::Analog6 | Input::Analog7 | Input::Analog8 | Input::Analog9
+            | Input::Analog10 | Input::Analog11 | Input::Analog12 | Input::Analog13
+            | Input::Analog14 | Input::Analog15 | Input::Analog16 => None,
+            Input::TemperatureSensorPos => Some(Input::TemperatureSensorNeg),
+            Input::TemperatureSensorNeg => Some(Input::TemperatureSensorPos),
+            Input::Avdd1AvssDiffOver5Pos => Some(Input::Avdd1AvssDiffOver5Neg),
+            Input::Avdd1AvssDiffOver5Neg => Some(Input::Avdd1AvssDiffOver5Pos),
+            Input::PositiveReferenceVoltage => Some(Input::NegativeReferenceVoltage),
+            Input::NegativeReferenceVoltage => Some(Input::PositiveReferenceVoltage),
+        }
+    }
+
+    /// Checks that `pos` and `neg` make a meaningful measurement together.
+    ///
+    /// Any two analog inputs can be paired, including an input with itself for a shorted input
+    /// noise test. The temperature sensor, (AVDD1 − AVSS)/5 and reference nodes only mean
+    /// something against their own other half, either way round, so pairing one with an analog
+    /// input or another internal node is rejected.
+    pub fn check_pair(pos: Input, neg: Input) -> Result<(), InvalidInputPair> {
+        let valid = match (pos.partner(), neg.partner()) {
+            (None, None) => true,
+            (Some(partner), Some(_)) => partner == neg,
+            _ => false,
+        };
+
+        if !valid {
+            return Err(InvalidInputPair { pos, neg });
+        }
+        Ok(())
+    }
+}
+
 bitfield_enum! {
     /// Output coding mode for ADC data.
     ///
     /// Used in Setup Configuration Registers to select unipolar or bipolar output coding.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum OutputCoding: u8 {
         /// Unipolar output coding.
         Unipolar = 0x00,
@@ -305,14 +2083,14 @@ bitfield_enum! {
     /// Reference source selection for ADC conversions.
     ///
     /// Used in Setup Configuration Registers to select the reference source.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum ReferenceSource: u8 {
         /// External reference.
         External = 0x00,
-        /// Internal reference.
-        Internal = 0x01,
+        /// Internal 2.5 V reference, which also has to be enabled in the ADC mode register.
+        Internal = 0x02,
         /// AVDD1-AVSS differential reference.
-        Avdd1AvssDiff = 0x02,
+        Avdd1AvssDiff = 0x03,
     }
 }
 
@@ -320,7 +2098,7 @@ bitfield_enum! {
     /// Enhanced filter rate selection.
     ///
     /// Used in Filter Configuration Registers to select the enhanced filter rate.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum EnhancedFilterRate: u8 {
         /// 27 SPS
         Sps27 = 0x02,
@@ -337,7 +2115,7 @@ bitfield_enum! {
     /// Digital filter order selection.
     ///
     /// Used in Filter Configuration Registers to select the filter order.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum FilterOrder: u8 {
         /// Sinc5 + Sinc1 filter order.
         Sinc5Sinc1 = 0x00,
@@ -350,7 +2128,7 @@ bitfield_enum! {
     /// Output data rate selection for ADC conversions.
     ///
     /// Used in Filter Configuration Registers to select the output data rate.
-    #[derive(Format, Debug, Eq, PartialEq)]
+    #[derive(Format, Debug, Clone, Copy, Eq, PartialEq)]
     pub enum OutputDataRate: u8 {
         /// 250,000 samples per second
         Sps250000 = 0x00,
@@ -395,4 +2173,108 @@ bitfield_enum! {
         /// 5 samples per second
         Sps5 = 0x14,
     }
-}
\ No newline at end of file
+}
+
+/// Settling times in µs with the sinc5 + sinc1 filter, indexed by [`OutputDataRate`] (datasheet Table 19).
+const SINC5_SINC1_SETTLING_TIME_US: [u32; 21] = [
+    20, 24, 32, 36, 48, 56, 80, 100, 200, 400, 1_000, 2_000, 2_516, 5_000, 10_000, 16_670, 20_016,
+    50_000, 60_020, 100_000, 200_000,
+];
+
+/// Settling times in µs with the sinc3 filter, indexed by [`OutputDataRate`] (datasheet Table 21).
+const SINC3_SETTLING_TIME_US: [u32; 21] = [
+    12, 24, 48, 60, 96, 120, 192, 300, 600, 1_200, 3_000, 6_000, 7_500, 15_000, 30_000, 50_020,
+    60_000, 150_000, 180_000, 300_000, 600_000,
+];
+
+/// Nominal output data rates in SPS, indexed by [`OutputDataRate`].
+const OUTPUT_DATA_RATE_SPS: [f32; 21] = [
+    250_000.0, 125_000.0, 62_500.0, 50_000.0, 31_250.0, 25_000.0, 15_625.0, 10_000.0, 5_000.0,
+    2_500.0, 1_000.0, 500.0, 397.5, 200.0, 100.0, 59.92, 49.96, 20.0, 16.67, 10.0, 5.0,
+];
+
+impl OutputDataRate {
+    /// Nominal output data rate in samples per second with a single channel enabled.
+    pub fn sps(&self) -> f32 {
+        OUTPUT_DATA_RATE_SPS[self.into_bits() as usize]
+    }
+
+    /// Output data rate with the modulator running at `modulator_hz` rather than the nominal
+    /// [`INTERNAL_MODULATOR_FREQUENCY_HZ`], e.g. from an external clock or a measured internal
+    /// oscillator. Every rate scales with the clock.
+    pub fn sps_at(&self, modulator_hz: f32) -> f32 {
+        self.sps() * modulator_hz / INTERNAL_MODULATOR_FREQUENCY_HZ
+    }
+
+    /// Time in microseconds for a conversion to fully settle with the given filter order.
+    ///
+    /// This is the time per channel when several channels are enabled or SING_CYC is set, and
+    /// the latency of the first conversion after a channel switch. Values come from the
+    /// datasheet tables and are rounded to the nearest microsecond.
+    pub fn settling_time_us(&self, order: FilterOrder) -> u32 {
+        let index = self.into_bits() as usize;
+        match order {
+            FilterOrder::Sinc5Sinc1 => SINC5_SINC1_SETTLING_TIME_US[index],
+            FilterOrder::Sinc3 => SINC3_SETTLING_TIME_US[index],
+        }
+    }
+
+    /// [`OutputDataRate::settling_time_us`] with the modulator running at `modulator_hz`.
+    pub fn settling_time_us_at(&self, order: FilterOrder, modulator_hz: f32) -> u32 {
+        scale_to_clock(self.settling_time_us(order), modulator_hz)
+    }
+
+    /// Time in microseconds between conversions of a single enabled channel.
+    ///
+    /// Normally the output data rate. With SING_CYC set only fully settled results are output,
+    /// which slows the rate down to one per settling time where
+    /// [`OutputDataRate::single_cycle_has_effect`].
+    pub fn sample_period_us(&self, order: FilterOrder, single_cycle: bool) -> u32 {
+        self.sample_period_us_at(order, single_cycle, INTERNAL_MODULATOR_FREQUENCY_HZ)
+    }
+
+    /// [`OutputDataRate::sample_period_us`] with the modulator running at `modulator_hz`.
+    pub fn sample_period_us_at(&self, order: FilterOrder, single_cycle: bool, modulator_hz: f32) -> u32 {
+        if single_cycle && self.single_cycle_has_effect(order) {
+            return self.settling_time_us_at(order, modulator_hz);
+        }
+        (1_000_000.0 / self.sps_at(modulator_hz) + 0.5) as u32
+    }
+
+    /// Whether SING_CYC changes anything at this rate with the given filter order.
+    ///
+    /// The sinc5 + sinc1 filter already settles in a single cycle at 10 kSPS and below, so
+    /// SING_CYC has no effect there. Everywhere else it trades rate for settled data, it never
+    /// makes a scan faster.
+    pub fn single_cycle_has_effect(&self, order: FilterOrder) -> bool {
+        order == FilterOrder::Sinc3 || self.into_bits() < OutputDataRate::Sps10000.into_bits()
+    }
+}
+
+/// Rescales a time in microseconds quoted for the nominal modulator clock to `modulator_hz`.
+fn scale_to_clock(nominal_us: u32, modulator_hz: f32) -> u32 {
+    (nominal_us as f32 * INTERNAL_MODULATOR_FREQUENCY_HZ / modulator_hz + 0.5) as u32
+}
+
+/// Time in microseconds to cycle once through a sequence of enabled channels, given the filter
+/// order and output data rate of the setup each channel uses, and whether SING_CYC is set in
+/// the [`AdcModeRegister`].
+///
+/// With a single channel the ADC converts continuously, see [`OutputDataRate::sample_period_us`].
+/// With several, every channel switch restarts the filter, so each channel takes its full
+/// settling time, which for sinc5 + sinc1 includes the extra delay the datasheet quotes on a
+/// switch. SING_CYC changes nothing then, the sequencer only outputs settled data anyway. The
+/// per-channel rate is one sample per cycle.
+pub fn sequence_cycle_time_us(channels: &[(FilterOrder, OutputDataRate)], single_cycle: bool) -> u32 {
+    sequence_cycle_time_us_at(channels, single_cycle, INTERNAL_MODULATOR_FREQUENCY_HZ)
+}
+
+/// [`sequence_cycle_time_us`] with the modulator running at `modulator_hz`, see
+/// [`ADC::with_modulator_frequency`].
+pub fn sequence_cycle_time_us_at(channels: &[(FilterOrder, OutputDataRate)], single_cycle: bool, modulator_hz: f32) -> u32 {
+    match channels {
+        [] => 0,
+        [(order, odr)] => odr.sample_period_us_at(*order, single_cycle, modulator_hz),
+        _ => channels.iter().map(|(order, odr)| odr.settling_time_us_at(*order, modulator_hz)).sum(),
+    }
+}
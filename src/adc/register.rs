@@ -1,81 +1,296 @@
-use crate::adc::{Channel, ClockSource, Crc, DataRegisterLength, Delay, EnhancedFilterRate, FilterOrder, Input, Mode, OutputCoding, OutputDataRate, ReferenceSource, Setup, SyncErrorPinMode};
+use embedded_hal::delay::DelayNs;
+use crate::adc::{AdcError, Channel, ClockSource, Crc, FieldValue, InvalidValue, DataRegisterLength, Delay, EnhancedFilterRate, FilterOrder, Input, Mode, OutputCoding, OutputDataRate, ReferenceSource, Setup, SyncErrorPinMode, ADC};
+use crate::adc::device::Device;
+use crate::adc::transport::RegisterTransport;
+use crate::units::{Code, Millivolts};
 
 pub trait Register<const BUFF_LEN: usize> {
     fn get_id() -> u8;
     fn from_buffer(raw: &[u8; BUFF_LEN]) -> Self;
+    /// Checks that every enum field holds a value its accessor can decode without panicking.
+    fn validate(&self) -> Result<(), InvalidValue>;
 }
 
 pub trait WritableRegister<const BUFF_LEN: usize>: Register<BUFF_LEN> {
     fn to_buffer(&self) -> [u8; BUFF_LEN];
+    /// Checks that the reserved bits are all zero, as the device requires them to be written.
+    fn check_reserved_bits(&self) -> Result<(), ReservedBitsSet>;
 }
 
+/// Registers written one after the other by [`ADC::write_all`].
+///
+/// Implemented by every writable register, by references and slices of them and by tuples of
+/// up to eight batches, so registers of different widths can be mixed.
+pub trait RegisterBatch {
+    /// Writes the registers in order, stopping at the first error.
+    fn write_to<Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device>(&self, adc: &mut ADC<Bus, AdcDelay, Part>) -> Result<(), AdcError<Bus::Error>>;
+}
+
+impl<T: RegisterBatch + ?Sized> RegisterBatch for &T {
+    fn write_to<Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device>(&self, adc: &mut ADC<Bus, AdcDelay, Part>) -> Result<(), AdcError<Bus::Error>> {
+        (**self).write_to(adc)
+    }
+}
+
+impl<T: RegisterBatch> RegisterBatch for [T] {
+    fn write_to<Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device>(&self, adc: &mut ADC<Bus, AdcDelay, Part>) -> Result<(), AdcError<Bus::Error>> {
+        self.iter().try_for_each(|register| register.write_to(adc))
+    }
+}
+
+macro_rules! tuple_register_batch {
+    ($($batch:ident),+) => {
+        impl<$($batch: RegisterBatch),+> RegisterBatch for ($($batch,)+) {
+            fn write_to<Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device>(&self, adc: &mut ADC<Bus, AdcDelay, Part>) -> Result<(), AdcError<Bus::Error>> {
+                #[allow(non_snake_case)]
+                let ($($batch,)+) = self;
+                $($batch.write_to(adc)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+tuple_register_batch!(A);
+tuple_register_batch!(A, B);
+tuple_register_batch!(A, B, C);
+tuple_register_batch!(A, B, C, D);
+tuple_register_batch!(A, B, C, D, E);
+tuple_register_batch!(A, B, C, D, E, F);
+tuple_register_batch!(A, B, C, D, E, F, G);
+tuple_register_batch!(A, B, C, D, E, F, G, H);
+
+/// A register was about to be written with some of its reserved bits set.
+#[derive(defmt::Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReservedBitsSet {
+    /// The offending bits, positioned as in the register (24 bit registers sit in the upper
+    /// three bytes).
+    pub bits: u32,
+}
+
+/// Datasheet name of the register at `id`, for logging raw transactions.
+///
+/// The data register is named DATA even though it reads as data and status when DATA_STAT is
+/// set, and the filter configuration registers are named FILTCON whichever layout is in use.
+pub fn register_name(id: u8) -> Option<&'static str> {
+    Some(match id {
+        StatusRegister::ID => "STATUS",
+        AdcModeRegister::ID => "ADCMODE",
+        InterfaceModeRegister::ID => "IFMODE",
+        RegisterCheck::ID => "REGCHECK",
+        DataRegister::ID => "DATA",
+        GPIOConfigRegister::ID => "GPIOCON",
+        IdRegister::ID => "ID",
+        Channel0Register::ID => "CH0",
+        Channel1Register::ID => "CH1",
+        Channel2Register::ID => "CH2",
+        Channel3Register::ID => "CH3",
+        Channel4Register::ID => "CH4",
+        Channel5Register::ID => "CH5",
+        Channel6Register::ID => "CH6",
+        Channel7Register::ID => "CH7",
+        Channel8Register::ID => "CH8",
+        Channel9Register::ID => "CH9",
+        Channel10Register::ID => "CH10",
+        Channel11Register::ID => "CH11",
+        Channel12Register::ID => "CH12",
+        Channel13Register::ID => "CH13",
+        Channel14Register::ID => "CH14",
+        Channel15Register::ID => "CH15",
+        SetupConfig0Register::ID => "SETUPCON0",
+        SetupConfig1Register::ID => "SETUPCON1",
+        SetupConfig2Register::ID => "SETUPCON2",
+        SetupConfig3Register::ID => "SETUPCON3",
+        SetupConfig4Register::ID => "SETUPCON4",
+        SetupConfig5Register::ID => "SETUPCON5",
+        SetupConfig6Register::ID => "SETUPCON6",
+        SetupConfig7Register::ID => "SETUPCON7",
+        DefaultFilterConfig0Register::ID => "FILTCON0",
+        DefaultFilterConfig1Register::ID => "FILTCON1",
+        DefaultFilterConfig2Register::ID => "FILTCON2",
+        DefaultFilterConfig3Register::ID => "FILTCON3",
+        DefaultFilterConfig4Register::ID => "FILTCON4",
+        DefaultFilterConfig5Register::ID => "FILTCON5",
+        DefaultFilterConfig6Register::ID => "FILTCON6",
+        DefaultFilterConfig7Register::ID => "FILTCON7",
+        Offset0Register::ID => "OFFSET0",
+        Offset1Register::ID => "OFFSET1",
+        Offset2Register::ID => "OFFSET2",
+        Offset3Register::ID => "OFFSET3",
+        Offset4Register::ID => "OFFSET4",
+        Offset5Register::ID => "OFFSET5",
+        Offset6Register::ID => "OFFSET6",
+        Offset7Register::ID => "OFFSET7",
+        Gain0Register::ID => "GAIN0",
+        Gain1Register::ID => "GAIN1",
+        Gain2Register::ID => "GAIN2",
+        Gain3Register::ID => "GAIN3",
+        Gain4Register::ID => "GAIN4",
+        Gain5Register::ID => "GAIN5",
+        Gain6Register::ID => "GAIN6",
+        Gain7Register::ID => "GAIN7",
+        _ => return None,
+    })
+}
+
+/// Voltage of the internal reference.
+pub const INTERNAL_REFERENCE_MV: f32 = 2500.0;
+
+/// Modulator frequency when running from the internal 16 MHz oscillator (MCLK / 2).
+pub const INTERNAL_MODULATOR_FREQUENCY_HZ: f32 = 8_000_000.0;
+
+/// Sensitivity of the internal temperature sensor in the datasheet transfer function.
+const TEMPERATURE_SENSITIVITY_UV_PER_K: f32 = 477.0;
+
 pub enum RegisterRW {
     Read = 0x40,
     Write = 0x00,
 }
 
+// 24 bit registers are stored in the upper three bytes of a u32 (the low byte is padding),
+// and go over the wire MSB first like every other register.
 const fn from_u32(val: u32) -> [u8; 3] {
     [
+        (val >> 24) as u8,
         (val >> 16) as u8,
         (val >> 8) as u8,
-        val as u8,
     ]
 }
 
 const fn into_u32(slice: [u8; 3]) -> u32 {
-    ((slice[0] as u32) << 16) | ((slice[1] as u32) << 8) | (slice[2] as u32)
+    ((slice[0] as u32) << 24) | ((slice[1] as u32) << 16) | ((slice[2] as u32) << 8)
+}
+
+// RDY is active low on the wire, the status register exposes it as "new data available".
+const fn ready_from_rdy(rdy: u8) -> bool {
+    rdy == 0
+}
+
+const fn ready_into_rdy(ready: bool) -> u8 {
+    !ready as u8
+}
+
+// Walks a register's field list MSB first, checking the bits of each field against its type.
+macro_rules! validate_fields {
+    ($raw:expr, $remaining:expr; ) => {};
+    ($raw:expr, $remaining:expr; $(#[doc = $doc:literal])* #[bits($bits:literal $(, $($opt:tt)*)?)] $vis:vis $field:ident : $ty:ty $(, $($rest:tt)*)?) => {
+        <$ty as FieldValue>::validate_bits(($raw >> ($remaining - $bits)) & (u32::MAX >> (32 - $bits)))?;
+        validate_fields!($raw, $remaining - $bits; $($($rest)*)?);
+    };
+}
+
+// Walks a register's field list MSB first, OR-ing together the set bits of reserved fields.
+macro_rules! reserved_fields {
+    ($raw:expr, $remaining:expr; ) => { 0 };
+    ($raw:expr, $remaining:expr; $(#[doc = $doc:literal])* #[bits($bits:literal $(, $($opt:tt)*)?)] __ : $ty:ty $(, $($rest:tt)*)?) => {
+        ($raw & ((u32::MAX >> (32 - $bits)) << ($remaining - $bits)))
+            | reserved_fields!($raw, $remaining - $bits; $($($rest)*)?)
+    };
+    ($raw:expr, $remaining:expr; $(#[doc = $doc:literal])* #[bits($bits:literal $(, $($opt:tt)*)?)] $vis:vis $field:ident : $ty:ty $(, $($rest:tt)*)?) => {
+        reserved_fields!($raw, $remaining - $bits; $($($rest)*)?)
+    };
+}
+
+macro_rules! check_reserved_bits {
+    ($raw:expr, $total:expr; $($field:tt)*) => {{
+        let set: u32 = reserved_fields!($raw, $total; $($field)*);
+        if set != 0 {
+            return Err(ReservedBitsSet { bits: set });
+        }
+        Ok(())
+    }};
 }
 
 macro_rules! register {
     // Single struct with doc
     ($(#[$meta:meta])* $name:ident { $($field:tt)* }, 1, $id:expr) => {
         #[bitfield_struct::bitfield(u8, repr = [u8; 1], from = u8::to_ne_bytes, into = u8::from_ne_bytes, defmt = true, order = msb)]
+        #[derive(PartialEq, Eq)]
         $(#[$meta])*
         pub struct $name {
             $($field)*
         }
+        impl $name {
+            /// Address of the register, usable in patterns and constant expressions.
+            pub const ID: u8 = $id;
+        }
         impl Register<1> for $name {
-            fn get_id() -> u8 { $id }
+            fn get_id() -> u8 { Self::ID }
             fn from_buffer(raw: &[u8; 1]) -> Self { Self::from_bits(*raw) }
+            fn validate(&self) -> Result<(), InvalidValue> {
+                let raw = self.into_bits()[0] as u32;
+                validate_fields!(raw, 8; $($field)*);
+                Ok(())
+            }
         }
     };
     ($(#[$meta:meta])* $name:ident { $($field:tt)* }, 2, $id:expr) => {
-        #[bitfield_struct::bitfield(u16, repr = [u8; 2], from = u16::to_ne_bytes, into = u16::from_ne_bytes, defmt = true, order = msb)]
+        #[bitfield_struct::bitfield(u16, repr = [u8; 2], from = u16::to_be_bytes, into = u16::from_be_bytes, defmt = true, order = msb)]
+        #[derive(PartialEq, Eq)]
         $(#[$meta])*
         pub struct $name {
             $($field)*
         }
+        impl $name {
+            /// Address of the register, usable in patterns and constant expressions.
+            pub const ID: u8 = $id;
+        }
         impl Register<2> for $name {
-            fn get_id() -> u8 { $id }
+            fn get_id() -> u8 { Self::ID }
             fn from_buffer(raw: &[u8; 2]) -> Self { Self::from_bits(*raw) }
+            fn validate(&self) -> Result<(), InvalidValue> {
+                let raw = u16::from_be_bytes(self.into_bits()) as u32;
+                validate_fields!(raw, 16; $($field)*);
+                Ok(())
+            }
         }
     };
     ($(#[$meta:meta])* $name:ident { $($field:tt)* }, 3, $id:expr) => {
         #[bitfield_struct::bitfield(u32, repr = [u8; 3], from = from_u32, into = into_u32, defmt = true, order = msb)]
+        #[derive(PartialEq, Eq)]
         $(#[$meta])*
         pub struct $name {
             $($field)*
             #[bits(8)]
             ___: u8, // Padding to ensure 32 bits total
         }
+        impl $name {
+            /// Address of the register, usable in patterns and constant expressions.
+            pub const ID: u8 = $id;
+        }
         impl Register<3> for $name {
-            fn get_id() -> u8 { $id }
+            fn get_id() -> u8 { Self::ID }
             fn from_buffer(raw: &[u8; 3]) -> Self {
                 Self::from_bits(*raw)
             }
+            fn validate(&self) -> Result<(), InvalidValue> {
+                let raw = into_u32(self.into_bits());
+                validate_fields!(raw, 32; $($field)*);
+                Ok(())
+            }
         }
     };
     ($(#[$meta:meta])* $name:ident { $($field:tt)* }, 4, $id:expr) => {
-        #[bitfield_struct::bitfield(u32, repr = [u8; 4], from = u32::to_ne_bytes, into = u32::from_ne_bytes, defmt = true, order = msb)]
+        #[bitfield_struct::bitfield(u32, repr = [u8; 4], from = u32::to_be_bytes, into = u32::from_be_bytes, defmt = true, order = msb)]
+        #[derive(PartialEq, Eq)]
         $(#[$meta])*
         pub struct $name {
             $($field)*
         }
+        impl $name {
+            /// Address of the register, usable in patterns and constant expressions.
+            pub const ID: u8 = $id;
+        }
         impl Register<4> for $name {
-            fn get_id() -> u8 { $id }
+            fn get_id() -> u8 { Self::ID }
             fn from_buffer(raw: &[u8; 4]) -> Self {
                 Self::from_bits(*raw)
             }
+            fn validate(&self) -> Result<(), InvalidValue> {
+                let raw = u32::from_be_bytes(self.into_bits());
+                validate_fields!(raw, 32; $($field)*);
+                Ok(())
+            }
         }
     };
     // Multi-register: doc comment and field block applied to all
@@ -90,30 +305,54 @@ macro_rules! rw_register {
     // Single struct with doc
     ($(#[$meta:meta])* $name:ident { $($field:tt)* }, 1, $id:expr) => {
         register!($(#[$meta])* $name { $($field)* }, 1, $id);
+        impl RegisterBatch for $name {
+            fn write_to<Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device>(&self, adc: &mut ADC<Bus, AdcDelay, Part>) -> Result<(), AdcError<Bus::Error>> { adc.write(self) }
+        }
         impl WritableRegister<1> for $name   {
             fn to_buffer(&self) -> [u8; 1] { self.into_bits() }
+            fn check_reserved_bits(&self) -> Result<(), ReservedBitsSet> {
+                check_reserved_bits!(self.into_bits()[0] as u32, 8; $($field)*)
+            }
         }
     };
     ($(#[$meta:meta])* $name:ident { $($field:tt)* }, 2, $id:expr) => {
         register!($(#[$meta])* $name { $($field)* }, 2, $id);
+        impl RegisterBatch for $name {
+            fn write_to<Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device>(&self, adc: &mut ADC<Bus, AdcDelay, Part>) -> Result<(), AdcError<Bus::Error>> { adc.write(self) }
+        }
         impl WritableRegister<2> for $name   {
             fn to_buffer(&self) -> [u8; 2] { self.into_bits() }
+            fn check_reserved_bits(&self) -> Result<(), ReservedBitsSet> {
+                check_reserved_bits!(u16::from_be_bytes(self.into_bits()) as u32, 16; $($field)*)
+            }
         }
     };
     ($(#[$meta:meta])* $name:ident { $($field:tt)* }, 3, $id:expr) => {
         register!($(#[$meta])* $name { $($field)* }, 3, $id);
+        impl RegisterBatch for $name {
+            fn write_to<Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device>(&self, adc: &mut ADC<Bus, AdcDelay, Part>) -> Result<(), AdcError<Bus::Error>> { adc.write(self) }
+        }
         impl WritableRegister<3> for $name   {
             fn to_buffer(&self) -> [u8; 3] {
                 self.into_bits()
             }
+            fn check_reserved_bits(&self) -> Result<(), ReservedBitsSet> {
+                check_reserved_bits!(into_u32(self.into_bits()), 32; $($field)*)
+            }
         }
     };
     ($(#[$meta:meta])* $name:ident { $($field:tt)* }, 4, $id:expr) => {
         register!($(#[$meta])* $name { $($field)* }, 4, $id);
+        impl RegisterBatch for $name {
+            fn write_to<Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device>(&self, adc: &mut ADC<Bus, AdcDelay, Part>) -> Result<(), AdcError<Bus::Error>> { adc.write(self) }
+        }
         impl WritableRegister<4> for $name   {
             fn to_buffer(&self) -> [u8; 4] {
                 self.into_bits()
             }
+            fn check_reserved_bits(&self) -> Result<(), ReservedBitsSet> {
+                check_reserved_bits!(u32::from_be_bytes(self.into_bits()), 32; $($field)*)
+            }
         }
     };
     // Multi-register: doc comment and field block applied to all
@@ -140,9 +379,7 @@ macro_rules! multi_rw_register {
         1, 
         $id:expr
     ) => {
-        register!($(#[$meta])* $name { $($fields)* }, 1, $id);
-        // Optionally, you can add a const or impl block for $id here
-        // pub const $name _ID: u8 = $id;
+        rw_register!($(#[$meta])* $name { $($fields)* }, 1, $id);
     };
     ($docs_and_struct:tt, 2, $(($name:ident, $id:expr)),+ $(,)?) => {
         $(
@@ -158,9 +395,7 @@ macro_rules! multi_rw_register {
         2, 
         $id:expr
     ) => {
-        register!($(#[$meta])* $name { $($fields)* }, 2, $id);
-        // Optionally, you can add a const or impl block for $id here
-        // pub const $name _ID: u8 = $id;
+        rw_register!($(#[$meta])* $name { $($fields)* }, 2, $id);
     };
     ($docs_and_struct:tt, 3, $(($name:ident, $id:expr)),+ $(,)?) => {
         $(
@@ -176,9 +411,7 @@ macro_rules! multi_rw_register {
         3, 
         $id:expr
     ) => {
-        register!($(#[$meta])* $name { $($fields)* }, 3, $id);
-        // Optionally, you can add a const or impl block for $id here
-        // pub const $name _ID: u8 = $id;
+        rw_register!($(#[$meta])* $name { $($fields)* }, 3, $id);
     };
     ($docs_and_struct:tt, 4, $(($name:ident, $id:expr)),+ $(,)?) => {
         $(
@@ -194,7 +427,7 @@ macro_rules! multi_rw_register {
         4, 
         $id:expr
     ) => {
-        register!($(#[$meta])* $name { $($fields)* }, 4, $id);
+        rw_register!($(#[$meta])* $name { $($fields)* }, 4, $id);
     };
 }
 
@@ -204,26 +437,24 @@ register!(
     ///
     /// | Bit | Name            | Description                       |
     /// |-----|-----------------|-----------------------------------|
-    /// | 7   | READY           | Data ready flag. Set to true when new conversion data is available. |
+    /// | 7   | READY           | Data ready flag (RDY, active low). True when new conversion data is available. |
     /// | 6   | ADC_ERROR       | ADC error flag. Set to true if an error is detected in the ADC core. |
     /// | 5   | CRC_ERROR       | CRC error flag. Set to true if a CRC error is detected on a register read. |
     /// | 4   | REGISTER_ERROR  | Register error flag. Set to true if a register parity error is detected. |
-    /// | 3:2 | Reserved        | -                                 |
-    /// | 1:0 | CHANNEL         | Current channel (see [`Channel`]).|
+    /// | 3:0 | CHANNEL         | Current channel (see [`Channel`]), bits 3:2 are always zero on the AD7175-2. |
     ///
     /// Reset: 0x80, Access: Read-only
     StatusRegister {
-        /// Data ready flag. Set to true when new conversion data is available.
-        #[bits(1, default = true)] pub ready: bool,
+        /// Data ready flag. True when new conversion data (or a calibration result) is available.
+        #[bits(1, default = false, from = ready_from_rdy, into = ready_into_rdy)] pub ready: bool,
         /// ADC error flag. Set to true if an error is detected in the ADC core.
         #[bits(1)] pub adc_error: bool,
         /// CRC error flag. Set to true if a CRC error is detected on a register read.
         #[bits(1)] pub crc_error: bool,
         /// Register error flag. Set to true if a register parity error is detected.
         #[bits(1)] pub register_error: bool,
-        #[bits(2)] __: u8,
         /// Current channel. Indicates which channel's data is present (see [`Channel`]).
-        #[bits(2)] pub channel: Channel,
+        #[bits(4)] pub channel: Channel,
     }, 1, 0x00);
 
 rw_register!(
@@ -332,6 +563,85 @@ register!(
         #[bits(24)] pub data: u32,
     }, 3, 0x04);
 
+impl StatusRegister {
+    /// A compact log line listing only the flags that are set and the current channel, e.g.
+    /// `READY ADC_ERR CH1`.
+    pub fn summary(&self) -> StatusSummary {
+        StatusSummary(*self)
+    }
+}
+
+/// Formats a [`StatusRegister`] as a one-line summary, see [`StatusRegister::summary`].
+pub struct StatusSummary(StatusRegister);
+
+impl defmt::Format for StatusSummary {
+    fn format(&self, f: defmt::Formatter) {
+        let status = self.0;
+        if status.ready() {
+            defmt::write!(f, "READY ");
+        }
+        if status.adc_error() {
+            defmt::write!(f, "ADC_ERR ");
+        }
+        if status.crc_error() {
+            defmt::write!(f, "CRC_ERR ");
+        }
+        if status.register_error() {
+            defmt::write!(f, "REG_ERR ");
+        }
+        defmt::write!(f, "CH{=u8}", status.channel().into_bits());
+    }
+}
+
+impl DataRegister {
+    /// Converts the conversion result into a voltage using the AD7175-2 transfer function.
+    ///
+    /// `reference_mv` is the reference voltage of the setup that produced the sample and `coding`
+    /// the [`OutputCoding`] it is configured with. The result is in millivolts.
+    ///
+    /// - Unipolar: `code / 2^24 * Vref`
+    /// - Bipolar: `(code / 2^23 - 1) * Vref`, so midscale (0x800000) is exactly 0 V
+    pub fn to_voltage(&self, reference_mv: f32, coding: OutputCoding) -> f32 {
+        // every 24 bit code is exactly representable in an f32 mantissa
+        let code = self.data() as f32;
+        match coding {
+            OutputCoding::Unipolar => code / (1u32 << 24) as f32 * reference_mv,
+            OutputCoding::Bipolar => (code / (1u32 << 23) as f32 - 1.0) * reference_mv,
+        }
+    }
+
+    /// [`DataRegister::to_voltage`] with the units spelled out.
+    pub fn to_millivolts(&self, reference: Millivolts, coding: OutputCoding) -> Millivolts {
+        Millivolts(self.to_voltage(reference.0, coding))
+    }
+
+    /// The raw 24 bit conversion result.
+    pub fn code(&self) -> Code {
+        Code(self.data())
+    }
+
+    /// The conversion result as a signed code.
+    ///
+    /// Bipolar results are offset binary, so midscale (0x800000) maps to 0 and the range is
+    /// -2^23..2^23. Unipolar results are returned unchanged.
+    pub fn as_signed(&self, coding: OutputCoding) -> i32 {
+        match coding {
+            OutputCoding::Unipolar => self.data() as i32,
+            OutputCoding::Bipolar => self.data() as i32 - (1 << 23),
+        }
+    }
+
+    /// Converts a conversion of the internal temperature sensor into degrees Celsius.
+    ///
+    /// Assumes the setup uses the 2.5 V internal reference and applies the datasheet transfer
+    /// function `V / 477 µV/K - 273.15`. The sensor is only accurate to a few degrees unless
+    /// its slope has been calibrated at a known temperature.
+    pub fn to_celsius(&self, coding: OutputCoding) -> f32 {
+        let microvolts = self.to_voltage(INTERNAL_REFERENCE_MV, coding) * 1000.0;
+        microvolts / TEMPERATURE_SENSITIVITY_UV_PER_K - 273.15
+    }
+}
+
 register!(
     /// Data and Status Register (0x04)
     /// Holds the latest conversion result and status byte.
@@ -368,7 +678,7 @@ rw_register!(
     /// | 1     | GPIO1_DATA          | GPIO1 data value. Set to true for high.                            |
     /// | 0     | GPIO0_DATA          | GPIO0 data value. Set to true for high.                            |
     ///
-    /// Reset: 0x1800, Access: Read/Write
+    /// Reset: 0x0800, Access: Read/Write
     GPIOConfigRegister {
         #[bits(3)] __: u8,
         /// GPIO multiplexer enable. Set to true to enable mux.
@@ -404,20 +714,19 @@ register!(
     ///
     /// Reset: 0x0cd0, Access: Read-only
     IdRegister {
-        /// Device ID (fixed value for AD7175-2).
+        /// Device ID, 0x0cdX on the AD7175-2 and 0x3cdX on the AD7175-8.
         #[bits(16, default = 0x0cd0)] pub id: u16
     }, 2, 0x07);
 
 multi_rw_register! {
     {
-        /// Channel Registers (0x10..0x13)
+        /// Channel Registers (0x10..0x1F), the AD7175-2 only has the first four
         /// Configure channel enable, setup selection, and input mux for each channel.
         ///
         /// | Bit   | Name      | Description                                      |
         /// |-------|-----------|--------------------------------------------------|
         /// | 15    | CH_EN     | Channel enable. Set to true to enable channel.   |
-        /// | 14    | Reserved  | -                                                |
-        /// | 13:12 | SETUP_SEL | Setup selection (see [`Setup`]).                 |
+        /// | 14:12 | SETUP_SEL | Setup selection (see [`Setup`]), bit 14 is reserved on the AD7175-2. |
         /// | 11:10 | Reserved  | -                                                |
         /// | 9:5   | AINPOS    | Positive input selection (see [`Input`]).        |
         /// | 4:0   | AINNEG    | Negative input selection (see [`Input`]).        |
@@ -425,8 +734,7 @@ multi_rw_register! {
         /// Reset: 0x8000, Access: Read/Write
         pub struct ChannelRegister {
             #[bits(1, default = true)] pub ch_en: bool,
-            #[bits(1)] __: u8,
-            #[bits(2)] pub setup_sel: Setup,
+            #[bits(3)] pub setup_sel: Setup,
             #[bits(2)] __: u8,
             #[bits(5)] pub ainpos: Input,
             #[bits(5)] pub ainneg: Input,
@@ -436,12 +744,24 @@ multi_rw_register! {
     (Channel0Register, 0x10),
     (Channel1Register, 0x11),
     (Channel2Register, 0x12),
-    (Channel3Register, 0x13)
+    (Channel3Register, 0x13),
+    (Channel4Register, 0x14),
+    (Channel5Register, 0x15),
+    (Channel6Register, 0x16),
+    (Channel7Register, 0x17),
+    (Channel8Register, 0x18),
+    (Channel9Register, 0x19),
+    (Channel10Register, 0x1a),
+    (Channel11Register, 0x1b),
+    (Channel12Register, 0x1c),
+    (Channel13Register, 0x1d),
+    (Channel14Register, 0x1e),
+    (Channel15Register, 0x1f)
 }
 
 multi_rw_register! {
     {
-        /// Setup Configuration Registers (0x20..0x23)
+        /// Setup Configuration Registers (0x20..0x27), the AD7175-2 only has the first four
         /// Configure input/output coding, reference, and buffer settings for each setup.
         ///
         /// | Bit   | Name                | Description                                         |
@@ -457,7 +777,7 @@ multi_rw_register! {
         /// | 5:4   | REF_SEL             | Reference selection (see [`ReferenceSource`]).      |
         /// | 3:0   | Reserved            | -                                                   |
         /// |
-        /// Reset: 0x0580, Access: Read/Write
+        /// Reset: 0x1320, Access: Read/Write
         pub struct SetupConfigRegister {
             #[bits(3)] __: u16,
             /// Output coding (see [`OutputCoding`]).
@@ -482,13 +802,17 @@ multi_rw_register! {
     (SetupConfig0Register, 0x20),
     (SetupConfig1Register, 0x21),
     (SetupConfig2Register, 0x22),
-    (SetupConfig3Register, 0x23)
+    (SetupConfig3Register, 0x23),
+    (SetupConfig4Register, 0x24),
+    (SetupConfig5Register, 0x25),
+    (SetupConfig6Register, 0x26),
+    (SetupConfig7Register, 0x27)
 }
 
-// Filter Configuration Registers (0x28..0x2B)
+// Filter Configuration Registers (0x28..0x2F)
 multi_rw_register! {
     {
-        /// Filter Configuration Registers (0x28..0x2B)
+        /// Filter Configuration Registers (0x28..0x2F), the AD7175-2 only has the first four
         /// Configure digital filter type, enhanced filter, order, and output data rate for each setup.
         ///
         /// | Bit   | Name        | Description                                         |
@@ -516,13 +840,17 @@ multi_rw_register! {
     (DefaultFilterConfig0Register, 0x28),
     (DefaultFilterConfig1Register, 0x29),
     (DefaultFilterConfig2Register, 0x2a),
-    (DefaultFilterConfig3Register, 0x2b)
+    (DefaultFilterConfig3Register, 0x2b),
+    (DefaultFilterConfig4Register, 0x2c),
+    (DefaultFilterConfig5Register, 0x2d),
+    (DefaultFilterConfig6Register, 0x2e),
+    (DefaultFilterConfig7Register, 0x2f)
 }
 
-// Direct SINC3 Map Filter Configuration Registers (0x28..0x2B)
+// Direct SINC3 Map Filter Configuration Registers (0x28..0x2F)
 multi_rw_register! {
     {
-        /// Direct SINC3 Map Filter Configuration Registers (0x28..0x2B)
+        /// Direct SINC3 Map Filter Configuration Registers (0x28..0x2F), the AD7175-2 only has the first four
         /// Configure direct SINC3 decimation rate for each setup.
         ///
         /// | Bit   | Name            | Description                                         |
@@ -540,9 +868,97 @@ multi_rw_register! {
     (DirectSinc3MapFilterConfig0Register, 0x28),
     (DirectSinc3MapFilterConfig1Register, 0x29),
     (DirectSinc3MapFilterConfig2Register, 0x2a),
-    (DirectSinc3MapFilterConfig3Register, 0x2b)
+    (DirectSinc3MapFilterConfig3Register, 0x2b),
+    (DirectSinc3MapFilterConfig4Register, 0x2c),
+    (DirectSinc3MapFilterConfig5Register, 0x2d),
+    (DirectSinc3MapFilterConfig6Register, 0x2e),
+    (DirectSinc3MapFilterConfig7Register, 0x2f)
+}
+
+/// Largest decimation rate the direct SINC3 map filter can be programmed with.
+const MAX_SINC3_DECIMATION_RATE: u16 = 0x7fff;
+
+macro_rules! direct_sinc3_map_odr {
+    ($($name:ident),+ $(,)?) => {
+        $(
+            impl $name {
+                /// Output data rate with a single channel enabled, `f_mod / (32 × decimation rate)`.
+                ///
+                /// The modulator runs at MCLK / 2, see [`INTERNAL_MODULATOR_FREQUENCY_HZ`].
+                pub fn odr_hz(&self, f_mod_hz: f32) -> f32 {
+                    f_mod_hz / (32.0 * self.decimation_rate() as f32)
+                }
+
+                /// Programs the decimation rate that gives the output data rate nearest to
+                /// `target_hz`, clamped to what the register can hold.
+                pub fn from_target_odr(target_hz: f32, f_mod_hz: f32) -> Self {
+                    let decimation_rate = f_mod_hz / (32.0 * target_hz) + 0.5;
+                    // float to int casts saturate, which also covers a zero target
+                    Self::new().with_decimation_rate((decimation_rate as u16).clamp(1, MAX_SINC3_DECIMATION_RATE))
+                }
+            }
+        )+
+    };
+}
+
+direct_sinc3_map_odr!(
+    DirectSinc3MapFilterConfig0Register,
+    DirectSinc3MapFilterConfig1Register,
+    DirectSinc3MapFilterConfig2Register,
+    DirectSinc3MapFilterConfig3Register,
+    DirectSinc3MapFilterConfig4Register,
+    DirectSinc3MapFilterConfig5Register,
+    DirectSinc3MapFilterConfig6Register,
+    DirectSinc3MapFilterConfig7Register,
+);
+
+macro_rules! setup_config_buffer_presets {
+    ($($name:ident),+ $(,)?) => {
+        $(
+            impl $name {
+                /// Reset configuration with the analog input and reference buffers all enabled.
+                ///
+                /// Without buffers the sampling capacitors are charged straight from the source,
+                /// so source impedance above a few hundred ohms, or an RC filter in front of the
+                /// inputs or reference, shows up as gain error. The buffers isolate the source at
+                /// the cost of a little offset and noise, and the inputs need some headroom to
+                /// the supply rails, see the buffered input range in the datasheet.
+                pub fn for_high_impedance() -> Self {
+                    Self::new()
+                        .with_ainbuf_pos_enabled(true)
+                        .with_ainbuf_neg_enabled(true)
+                        .with_refbuf_pos_enabled(true)
+                        .with_refbuf_neg_enabled(true)
+                }
+
+                /// Reset configuration with every buffer disabled, for sources that can drive the
+                /// sampling capacitors directly, e.g. a shunt amplifier output.
+                ///
+                /// This gives the lowest offset and noise and lets the inputs swing rail to rail,
+                /// see [`Self::for_high_impedance`] for what it costs.
+                pub fn for_low_impedance() -> Self {
+                    Self::new()
+                        .with_ainbuf_pos_enabled(false)
+                        .with_ainbuf_neg_enabled(false)
+                        .with_refbuf_pos_enabled(false)
+                        .with_refbuf_neg_enabled(false)
+                }
+            }
+        )+
+    };
 }
 
+setup_config_buffer_presets!(
+    SetupConfig0Register,
+    SetupConfig1Register,
+    SetupConfig2Register,
+    SetupConfig3Register,
+    SetupConfig4Register,
+    SetupConfig5Register,
+    SetupConfig6Register,
+    SetupConfig7Register,
+);
+
 pub struct FilterConfig0Register;
 
 impl FilterConfig0Register {
@@ -591,10 +1007,58 @@ impl FilterConfig3Register {
     }
 }
 
-// Offset Registers (0x30..0x33)
+pub struct FilterConfig4Register;
+
+impl FilterConfig4Register {
+    pub fn new_default() -> DefaultFilterConfig4Register {
+        DefaultFilterConfig4Register::new()
+    }
+    
+    pub fn new_direct_sinc3_map() -> DirectSinc3MapFilterConfig4Register {
+        DirectSinc3MapFilterConfig4Register::new()
+    }
+}
+
+pub struct FilterConfig5Register;
+
+impl FilterConfig5Register {
+    pub fn new_default() -> DefaultFilterConfig5Register {
+        DefaultFilterConfig5Register::new()
+    }
+    
+    pub fn new_direct_sinc3_map() -> DirectSinc3MapFilterConfig5Register {
+        DirectSinc3MapFilterConfig5Register::new()
+    }
+}
+
+pub struct FilterConfig6Register;
+
+impl FilterConfig6Register {
+    pub fn new_default() -> DefaultFilterConfig6Register {
+        DefaultFilterConfig6Register::new()
+    }
+    
+    pub fn new_direct_sinc3_map() -> DirectSinc3MapFilterConfig6Register {
+        DirectSinc3MapFilterConfig6Register::new()
+    }
+}
+
+pub struct FilterConfig7Register;
+
+impl FilterConfig7Register {
+    pub fn new_default() -> DefaultFilterConfig7Register {
+        DefaultFilterConfig7Register::new()
+    }
+    
+    pub fn new_direct_sinc3_map() -> DirectSinc3MapFilterConfig7Register {
+        DirectSinc3MapFilterConfig7Register::new()
+    }
+}
+
+// Offset Registers (0x30..0x37)
 multi_rw_register! {
     {
-        /// Offset Registers (0x30..0x33)
+        /// Offset Registers (0x30..0x37), the AD7175-2 only has the first four
         /// Store offset calibration coefficients for each setup.
         ///
         /// | Bit   | Name   | Description                                 |
@@ -610,13 +1074,17 @@ multi_rw_register! {
     (Offset0Register, 0x30),
     (Offset1Register, 0x31),
     (Offset2Register, 0x32),
-    (Offset3Register, 0x33)
+    (Offset3Register, 0x33),
+    (Offset4Register, 0x34),
+    (Offset5Register, 0x35),
+    (Offset6Register, 0x36),
+    (Offset7Register, 0x37)
 }
 
-// Gain Registers (0x38..0x3B)
+// Gain Registers (0x38..0x3F)
 multi_rw_register! {
     {
-        /// Gain Registers (0x38..0x3B)
+        /// Gain Registers (0x38..0x3F), the AD7175-2 only has the first four
         /// Store gain calibration coefficients for each setup.
         ///
         /// | Bit   | Name | Description                               |
@@ -632,5 +1100,142 @@ multi_rw_register! {
     (Gain0Register, 0x38),
     (Gain1Register, 0x39),
     (Gain2Register, 0x3a),
-    (Gain3Register, 0x3b)
+    (Gain3Register, 0x3b),
+    (Gain4Register, 0x3c),
+    (Gain5Register, 0x3d),
+    (Gain6Register, 0x3e),
+    (Gain7Register, 0x3f)
+}
+
+/// Gain coefficient the datasheet transfer function divides by, a gain factor of 1.
+const UNITY_GAIN: u32 = 0x400000;
+
+/// Offset coefficient that leaves conversions unchanged.
+const ZERO_OFFSET: u32 = 0x800000;
+
+macro_rules! gain_factor {
+    ($($name:ident),+ $(,)?) => {
+        $(
+            impl $name {
+                /// The coefficient as the factor conversions are multiplied by, `gain / 0x400000`.
+                ///
+                /// The transfer function also scales the input by 0.75, so a device matching its
+                /// reference exactly reads around 0x555555, a factor of 4/3.
+                pub fn as_factor(&self) -> f32 {
+                    self.gain() as f32 / UNITY_GAIN as f32
+                }
+
+                /// The coefficient nearest to `factor`, clamped to what the register can hold.
+                pub fn from_factor(factor: f32) -> Self {
+                    // float to int casts saturate, which also covers negative factors
+                    let gain = (factor * UNITY_GAIN as f32 + 0.5) as u32;
+                    Self::new().with_gain(gain.min(0xffffff))
+                }
+            }
+        )+
+    };
 }
+
+gain_factor!(
+    Gain0Register,
+    Gain1Register,
+    Gain2Register,
+    Gain3Register,
+    Gain4Register,
+    Gain5Register,
+    Gain6Register,
+    Gain7Register,
+);
+
+macro_rules! offset_code {
+    ($($name:ident),+ $(,)?) => {
+        $(
+            impl $name {
+                /// The coefficient as the signed number of codes taken off the input before the
+                /// gain is applied, `offset - 0x800000`.
+                pub fn as_code(&self) -> i32 {
+                    self.offset() as i32 - ZERO_OFFSET as i32
+                }
+
+                /// The coefficient subtracting `code` from the input, clamped to what the
+                /// register can hold.
+                pub fn from_code(code: i32) -> Self {
+                    let code = code.clamp(-(ZERO_OFFSET as i32), ZERO_OFFSET as i32 - 1);
+                    Self::new().with_offset((code + ZERO_OFFSET as i32) as u32)
+                }
+            }
+        )+
+    };
+}
+
+offset_code!(
+    Offset0Register,
+    Offset1Register,
+    Offset2Register,
+    Offset3Register,
+    Offset4Register,
+    Offset5Register,
+    Offset6Register,
+    Offset7Register,
+);
+
+/// Ties a setup to the registers that configure it, so code generic over the setup can't mix in
+/// another setup's registers.
+///
+/// Implemented by the marker types [`Setup0Registers`] to [`Setup7Registers`]. The channel
+/// registers aren't part of it, any channel can select any setup.
+pub trait SetupRegisters {
+    /// The setup, as selected in the SETUP_SEL field of a channel.
+    const SETUP: Setup;
+
+    type SetupConfig: WritableRegister<2>;
+    type DefaultFilterConfig: WritableRegister<2>;
+    type DirectSinc3MapFilterConfig: WritableRegister<2>;
+    type Offset: WritableRegister<3>;
+    type Gain: WritableRegister<3>;
+
+    /// Reset setup configuration with the given reference and output coding.
+    fn setup_config(reference: ReferenceSource, coding: OutputCoding) -> Self::SetupConfig;
+
+    /// Reset filter configuration with the given filter order and output data rate.
+    fn default_filter_config(order: FilterOrder, odr: OutputDataRate) -> Self::DefaultFilterConfig;
+}
+
+macro_rules! setup_registers {
+    ($($marker:ident => $setup:ident, $setup_config:ident, $default_filter_config:ident, $direct_sinc3_map_filter_config:ident, $offset:ident, $gain:ident);+ $(;)?) => {
+        $(
+            #[doc = concat!("The registers of [`Setup::", stringify!($setup), "`], see [`SetupRegisters`].")]
+            #[derive(Debug, Clone, Copy)]
+            pub struct $marker;
+
+            impl SetupRegisters for $marker {
+                const SETUP: Setup = Setup::$setup;
+
+                type SetupConfig = $setup_config;
+                type DefaultFilterConfig = $default_filter_config;
+                type DirectSinc3MapFilterConfig = $direct_sinc3_map_filter_config;
+                type Offset = $offset;
+                type Gain = $gain;
+
+                fn setup_config(reference: ReferenceSource, coding: OutputCoding) -> Self::SetupConfig {
+                    $setup_config::new().with_ref_sel(reference).with_bi_unipolar(coding)
+                }
+
+                fn default_filter_config(order: FilterOrder, odr: OutputDataRate) -> Self::DefaultFilterConfig {
+                    $default_filter_config::new().with_order(order).with_odr(odr)
+                }
+            }
+        )+
+    };
+}
+
+setup_registers!(
+    Setup0Registers => Setup0, SetupConfig0Register, DefaultFilterConfig0Register, DirectSinc3MapFilterConfig0Register, Offset0Register, Gain0Register;
+    Setup1Registers => Setup1, SetupConfig1Register, DefaultFilterConfig1Register, DirectSinc3MapFilterConfig1Register, Offset1Register, Gain1Register;
+    Setup2Registers => Setup2, SetupConfig2Register, DefaultFilterConfig2Register, DirectSinc3MapFilterConfig2Register, Offset2Register, Gain2Register;
+    Setup3Registers => Setup3, SetupConfig3Register, DefaultFilterConfig3Register, DirectSinc3MapFilterConfig3Register, Offset3Register, Gain3Register;
+    Setup4Registers => Setup4, SetupConfig4Register, DefaultFilterConfig4Register, DirectSinc3MapFilterConfig4Register, Offset4Register, Gain4Register;
+    Setup5Registers => Setup5, SetupConfig5Register, DefaultFilterConfig5Register, DirectSinc3MapFilterConfig5Register, Offset5Register, Gain5Register;
+    Setup6Registers => Setup6, SetupConfig6Register, DefaultFilterConfig6Register, DirectSinc3MapFilterConfig6Register, Offset6Register, Gain6Register;
+    Setup7Registers => Setup7, SetupConfig7Register, DefaultFilterConfig7Register, DirectSinc3MapFilterConfig7Register, Offset7Register, Gain7Register;
+);
@@ -0,0 +1,62 @@
+use defmt::Format;
+use crate::adc::OutputCoding;
+use crate::adc::register::DataRegister;
+use crate::units::{Code, Milliamps};
+
+/// Linear correction applied after scaling, `(amps - offset) * gain`.
+///
+/// Meant for a system level trim against a reference meter, on top of whatever calibration the
+/// ADC applies internally.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct Correction {
+    /// Reading with no current flowing, in amps.
+    pub offset: f32,
+    /// Ratio of the true current to the offset corrected reading.
+    pub gain: f32,
+}
+
+/// Turns raw ADC codes into amps through a shunt and an amplifier stage.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct Scaling {
+    pub reference_mv: f32,
+    pub shunt_ohms: f32,
+    /// Gain of the amplifier between the shunt and the ADC inputs.
+    pub pga_gain: f32,
+    pub correction: Option<Correction>,
+}
+
+impl Scaling {
+    pub fn new(reference_mv: f32, shunt_ohms: f32, pga_gain: f32) -> Self {
+        Self {
+            reference_mv,
+            shunt_ohms,
+            pga_gain,
+            correction: None,
+        }
+    }
+
+    /// Applies `correction` to every conversion.
+    pub fn with_correction(mut self, correction: Correction) -> Self {
+        self.correction = Some(correction);
+        self
+    }
+
+    /// Current through the shunt for a 24 bit conversion result.
+    ///
+    /// The code goes through the same transfer function as [`DataRegister::to_voltage`], so
+    /// bipolar midscale is 0 A.
+    pub fn code_to_amps(&self, code: u32, coding: OutputCoding) -> f32 {
+        let millivolts = DataRegister::new().with_data(code).to_voltage(self.reference_mv, coding);
+        let amps = millivolts / 1000.0 / self.pga_gain / self.shunt_ohms;
+
+        match self.correction {
+            Some(correction) => (amps - correction.offset) * correction.gain,
+            None => amps,
+        }
+    }
+
+    /// [`Scaling::code_to_amps`] taking and returning typed units.
+    pub fn code_to_milliamps(&self, code: Code, coding: OutputCoding) -> Milliamps {
+        Milliamps::from_amps(self.code_to_amps(code.0, coding))
+    }
+}
@@ -0,0 +1,75 @@
+use core::marker::PhantomData;
+use embedded_hal::delay::DelayNs;
+use crate::adc::device::Device;
+use crate::adc::transport::RegisterTransport;
+use crate::adc::register::{DefaultFilterConfig0Register, SetupConfig0Register, SetupRegisters};
+use crate::adc::{AdcError, Channel, FilterOrder, Input, OutputCoding, OutputDataRate, ReferenceSource, ADC};
+
+/// Configures one measurement path: a channel, the setup it uses and that setup's filter.
+///
+/// The channel, setup configuration and filter configuration registers all have to agree on
+/// the setup index. The setup is picked by its [`SetupRegisters`] marker, e.g.
+/// [`Setup1Registers`](crate::adc::register::Setup1Registers), and all three writes are derived
+/// from it, so a channel can't end up pointing at a setup whose registers were never written,
+/// and configuring the wrong setup's filter doesn't compile.
+#[derive(Debug, Clone, Copy)]
+pub struct SetupBuilder<S: SetupRegisters> {
+    channel: Channel,
+    input_pos: Input,
+    input_neg: Input,
+    reference: ReferenceSource,
+    coding: OutputCoding,
+    order: FilterOrder,
+    odr: OutputDataRate,
+    setup: PhantomData<S>,
+}
+
+impl<S: SetupRegisters> SetupBuilder<S> {
+    /// Starts from the reset values of the setup and filter configuration.
+    pub fn new(channel: Channel, input_pos: Input, input_neg: Input) -> Self {
+        // every setup resets to the same values
+        let setup_config = SetupConfig0Register::new();
+        let filter_config = DefaultFilterConfig0Register::new();
+
+        Self {
+            channel,
+            input_pos,
+            input_neg,
+            reference: setup_config.ref_sel(),
+            coding: setup_config.bi_unipolar(),
+            order: filter_config.order(),
+            odr: filter_config.odr(),
+            setup: PhantomData,
+        }
+    }
+
+    pub fn with_reference(mut self, reference: ReferenceSource) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    pub fn with_output_coding(mut self, coding: OutputCoding) -> Self {
+        self.coding = coding;
+        self
+    }
+
+    pub fn with_filter_order(mut self, order: FilterOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_output_data_rate(mut self, odr: OutputDataRate) -> Self {
+        self.odr = odr;
+        self
+    }
+
+    /// Writes the setup and filter configuration, then enables the channel on them.
+    ///
+    /// The channel is written last so it never converts with a half applied setup.
+    pub fn apply<Bus: RegisterTransport, AdcDelay: DelayNs, Part: Device>(&self, adc: &mut ADC<Bus, AdcDelay, Part>) -> Result<(), AdcError<Bus::Error>> {
+        adc.write(&S::setup_config(self.reference, self.coding))?;
+        adc.write(&S::default_filter_config(self.order, self.odr))?;
+
+        adc.configure_channel(self.channel, S::SETUP, self.input_pos, self.input_neg)
+    }
+}
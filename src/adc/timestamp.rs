@@ -0,0 +1,68 @@
+use embassy_time::{Duration, Instant};
+
+/// Timestamps the conversions of continuous read mode, see [`ADC::read_burst_timestamped`] and
+/// [`Samples::timestamped`].
+///
+/// A conversion completes when DOUT/RDY falls, but it is only seen some time later, after RDY
+/// was polled and the frame read, and that lag varies from one conversion to the next. Rather
+/// than the times conversions were seen, the clock hands out times on the grid of the
+/// conversion period, which is how the ADC actually spaced them:
+///
+/// - The first conversion is stamped with the time it was seen, anchoring the grid.
+/// - Each following one is stamped one period after the previous stamp.
+/// - A conversion seen before its grid time, because the ADC runs a little faster than the
+///   nominal period, pulls the grid back to the time it was seen.
+/// - A conversion seen a whole period or more after its grid time means the reader fell behind
+///   and conversions were overwritten in between, the grid restarts at the time it was seen.
+///
+/// A stamp is therefore never later than the conversion was seen, and stamps of conversions
+/// seen one after the other always increase. They trail the actual end of each conversion by
+/// the lag of the anchoring one.
+///
+/// [`ADC::read_burst_timestamped`]: crate::adc::ADC::read_burst_timestamped
+/// [`Samples::timestamped`]: crate::adc::Samples::timestamped
+#[derive(Debug, Clone)]
+pub struct SampleClock {
+    period: Duration,
+    last: Option<Instant>,
+}
+
+impl SampleClock {
+    /// Creates a clock for conversions `period` apart, e.g. from
+    /// [`OutputDataRate::sample_period_us`](crate::adc::OutputDataRate::sample_period_us) for a
+    /// single channel. With several channels sequenced it is the time per conversion, not per
+    /// turn of the sequence.
+    pub fn new(period: Duration) -> Self {
+        assert!(period.as_ticks() > 0, "conversion period must not be zero");
+        Self { period, last: None }
+    }
+
+    /// Time of the last stamp handed out, if any.
+    pub fn last(&self) -> Option<Instant> {
+        self.last
+    }
+
+    /// Drops the grid, the next conversion anchors a new one. For when conversions were
+    /// stopped or reconfigured.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Stamps the next conversion, `seen` being when it was noticed.
+    pub fn stamp(&mut self, seen: Instant) -> Instant {
+        let stamp = match self.last {
+            None => seen,
+            Some(last) => {
+                let due = last + self.period;
+                if seen < due || seen.saturating_duration_since(due) >= self.period {
+                    seen
+                } else {
+                    due
+                }
+            }
+        };
+
+        self.last = Some(stamp);
+        stamp
+    }
+}
@@ -0,0 +1,42 @@
+use embedded_hal::spi::{ErrorType, SpiBus};
+
+/// Moves register frames between the [`ADC`](crate::adc::ADC) driver and the device.
+///
+/// A frame is laid out by the driver: the communications byte, then the register contents and
+/// the checksum when one is enabled. Everything about the framing, including decoding what comes
+/// back, stays in the driver, so a transport only has to get the bytes across. Any [`SpiBus`] is
+/// a transport, others can stand in for it, e.g. a fake in tests or a simulated device.
+///
+/// Besides register frames the driver sends the reset sequence and reads conversions in
+/// continuous read mode through the same calls, which have no communications byte.
+pub trait RegisterTransport {
+    type Error;
+
+    /// Sends `frame` and replaces it with the bytes clocked back in at the same time.
+    fn read_register(&mut self, frame: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Sends `frame`, discarding whatever comes back.
+    fn write_register(&mut self, frame: &[u8]) -> Result<(), Self::Error>;
+
+    /// Waits until everything sent so far is out, before the driver deasserts a manually
+    /// managed CS.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<Bus: SpiBus> RegisterTransport for Bus {
+    type Error = <Bus as ErrorType>::Error;
+
+    fn read_register(&mut self, frame: &mut [u8]) -> Result<(), <Bus as ErrorType>::Error> {
+        self.transfer_in_place(frame)
+    }
+
+    fn write_register(&mut self, frame: &[u8]) -> Result<(), <Bus as ErrorType>::Error> {
+        self.write(frame)
+    }
+
+    fn flush(&mut self) -> Result<(), <Bus as ErrorType>::Error> {
+        SpiBus::flush(self)
+    }
+}
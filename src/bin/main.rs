@@ -38,7 +38,7 @@
     holding buffers for the duration of a data transfer."
 )]
 
-use defmt::info;
+use defmt::{info, panic};
 use embassy_executor::Spawner;
 use embassy_time::{Duration, Timer};
 use esp_hal::clock::CpuClock;
@@ -71,7 +71,8 @@ async fn main(spawner: Spawner) {
         peripherals.GPIO6,  // D3
         peripherals.GPIO5,  // D2
         peripherals.DMA_CH0,
-    );
+    )
+    .unwrap_or_else(|error| panic!("ADC DMA init failed: {}", error));
 
     info!("ADC initialized!");
 
@@ -82,7 +83,8 @@ async fn main(spawner: Spawner) {
         peripherals.GPIO10, // D7
         peripherals.GPIO9,  // D6
         peripherals.DMA_CH1,
-    );
+    )
+    .unwrap_or_else(|error| panic!("DAC DMA init failed: {}", error));
 
     info!("DAC initialized!");
 
@@ -109,7 +111,7 @@ async fn dac_task(mut dac: DAC<'static, SpiDmaBus<'static, Blocking>>) {
     loop {
         esp_println::println!("Sending value to DAC: {}", value);
         dac.write(value).unwrap();
-        value += 16;
+        value = value.checked_add(16).filter(|&next| next <= dac.max_code()).unwrap_or(0);
         Timer::after(Duration::from_millis(5_00)).await;
     }
 }
@@ -0,0 +1,172 @@
+use core::f32::consts::PI;
+use defmt::Format;
+use embassy_time::Duration;
+use crate::adc::AdcError;
+use crate::control::pid::PidGains;
+use crate::control::protection::FaultKind;
+use crate::dac::DacError;
+use crate::filter::sqrt;
+
+/// Oscillation cycles averaged by default before the gains are worked out.
+const DEFAULT_CYCLES: u32 = 4;
+
+/// Ticks after which a relay experiment gives up by default.
+const DEFAULT_MAX_TICKS: u32 = 10_000;
+
+/// Errors ending a [`LoadController::autotune`](super::LoadController::autotune) run. The load
+/// is switched off whichever one it is.
+#[derive(Debug, Format)]
+pub enum AutotuneError<AdcE, DacE> {
+    /// Measuring failed.
+    Adc(AdcError<AdcE>),
+    /// Writing a relay code failed.
+    Dac(DacError<DacE>),
+    /// A protection limit tripped while the relay was swinging.
+    Fault(FaultKind),
+    /// The loop didn't settle into an oscillation within the tick budget.
+    NoOscillation,
+}
+
+/// What a [`RelayTuner`] wants done after an update.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub enum RelayStep {
+    /// Still measuring, write this code to the DAC.
+    Output(u32),
+    /// Enough cycles have been measured, these are the tuned gains.
+    Done(PidGains),
+    /// The tick budget ran out, e.g. because the relay codes don't straddle the setpoint.
+    TimedOut,
+}
+
+/// Relay feedback (Åström–Hägglund) experiment for tuning the PID gains of a load.
+///
+/// The output is switched between two DAC codes each time the error changes sign, which makes
+/// the loop oscillate at its ultimate period `Tu`. With the relay swinging `d` codes either
+/// side of its midpoint and the error swinging `a` either side of zero, the ultimate gain is
+/// `Ku = 4d / (π a)`. The classic Ziegler–Nichols rules then give `kp = 0.6 Ku`,
+/// `ki = 1.2 Ku / Tu` and `kd = 0.075 Ku Tu`, in codes per unit of error like the gains of a
+/// [`LoadController`](super::LoadController).
+///
+/// The first cycle starts from wherever the loop happened to be, so it is skipped.
+#[derive(Debug, Clone)]
+pub struct RelayTuner {
+    low_code: u32,
+    high_code: u32,
+    period: Duration,
+    hysteresis: f32,
+    cycles: u32,
+    max_ticks: u32,
+    high: Option<bool>,
+    tick: u32,
+    last_rise: Option<u32>,
+    cycle_min: f32,
+    cycle_max: f32,
+    completed: u32,
+    period_ticks: u32,
+    amplitude_sum: f32,
+}
+
+impl RelayTuner {
+    /// Creates an experiment switching between `low_code` and `high_code`, updated every
+    /// `period`.
+    pub fn new(low_code: u32, high_code: u32, period: Duration) -> Self {
+        assert!(low_code < high_code, "relay low code must be below the high code");
+        Self {
+            low_code,
+            high_code,
+            period,
+            hysteresis: 0.0,
+            cycles: DEFAULT_CYCLES,
+            max_ticks: DEFAULT_MAX_TICKS,
+            high: None,
+            tick: 0,
+            last_rise: None,
+            cycle_min: 0.0,
+            cycle_max: 0.0,
+            completed: 0,
+            period_ticks: 0,
+            amplitude_sum: 0.0,
+        }
+    }
+
+    /// Only switches once the error is more than `hysteresis` past zero, so noise can't chatter
+    /// the relay. Taken out of the measured amplitude again when working out the gains.
+    pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Sets how many oscillation cycles are averaged, 4 by default.
+    pub fn with_cycles(mut self, cycles: u32) -> Self {
+        assert!(cycles > 0, "relay experiment needs at least one cycle");
+        self.cycles = cycles;
+        self
+    }
+
+    /// Sets how many ticks to wait for the measurement to finish, 10 000 by default.
+    pub fn with_max_ticks(mut self, max_ticks: u32) -> Self {
+        self.max_ticks = max_ticks;
+        self
+    }
+
+    /// The time between updates.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Feeds in the latest error, `setpoint - measured` oriented so more output reduces it, and
+    /// returns what to do next.
+    pub fn update(&mut self, error: f32) -> RelayStep {
+        self.tick += 1;
+        let high = *self.high.get_or_insert(error >= 0.0);
+        self.cycle_min = self.cycle_min.min(error);
+        self.cycle_max = self.cycle_max.max(error);
+
+        if !high && error > self.hysteresis {
+            self.high = Some(true);
+            // a rising switch ends one cycle and starts the next
+            if let Some(last_rise) = self.last_rise {
+                if self.completed > 0 {
+                    self.period_ticks += self.tick - last_rise;
+                    self.amplitude_sum += (self.cycle_max - self.cycle_min) / 2.0;
+                }
+                self.completed += 1;
+            }
+            self.last_rise = Some(self.tick);
+            self.cycle_min = error;
+            self.cycle_max = error;
+        } else if high && error < -self.hysteresis {
+            self.high = Some(false);
+        }
+
+        if self.completed > self.cycles {
+            return RelayStep::Done(self.gains());
+        }
+        if self.tick >= self.max_ticks {
+            return RelayStep::TimedOut;
+        }
+
+        RelayStep::Output(if self.high == Some(true) { self.high_code } else { self.low_code })
+    }
+
+    /// Ziegler–Nichols gains from the averaged cycles.
+    fn gains(&self) -> PidGains {
+        let cycles = (self.completed - 1) as f32;
+        let amplitude = self.amplitude_sum / cycles;
+        let ultimate_period = self.period_ticks as f32 / cycles * self.period.as_micros() as f32 / 1_000_000.0;
+
+        // with hysteresis the relay switches late, which the describing function corrects for
+        let amplitude = match amplitude * amplitude - self.hysteresis * self.hysteresis {
+            squared if squared > 0.0 => sqrt(squared),
+            _ => amplitude,
+        };
+        let half_swing = (self.high_code - self.low_code) as f32 / 2.0;
+        let ultimate_gain = 4.0 * half_swing / (PI * amplitude);
+
+        PidGains {
+            kp: 0.6 * ultimate_gain,
+            ki: 1.2 * ultimate_gain / ultimate_period,
+            kd: 0.075 * ultimate_gain * ultimate_period,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use defmt::Format;
+use crate::control::protection::FaultKind;
+use crate::control::LoadMode;
+
+/// Something worth keeping a record of during an unattended run.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    /// A protection limit tripped.
+    Fault(FaultKind),
+    /// A transfer with the ADC failed its checksum.
+    CrcError,
+    /// The load switched to a new mode or setpoint.
+    ModeChange(LoadMode),
+}
+
+/// An [`EventKind`] stamped with when it happened, e.g. `Instant::now().as_ticks()`.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct Event {
+    pub timestamp_ticks: u64,
+    pub kind: EventKind,
+}
+
+/// Fixed capacity record of the last `N` events, filled by the control loop and drained by
+/// whatever reports them.
+///
+/// Once full the oldest event is overwritten, so the latest ones are always kept.
+/// [`EventLog::overwritten`] counts how many were lost that way.
+#[derive(Debug, Clone)]
+pub struct EventLog<const N: usize> {
+    events: [Option<Event>; N],
+    next: usize,
+    len: usize,
+    overwritten: u32,
+}
+
+impl<const N: usize> EventLog<N> {
+    pub const fn new() -> Self {
+        assert!(N > 0, "event log needs room for at least one event");
+        Self {
+            events: [None; N],
+            next: 0,
+            len: 0,
+            overwritten: 0,
+        }
+    }
+
+    /// Records `kind` as happening at `timestamp_ticks`, overwriting the oldest event when full.
+    pub fn push(&mut self, timestamp_ticks: u64, kind: EventKind) {
+        if self.len == N {
+            self.overwritten = self.overwritten.saturating_add(1);
+        } else {
+            self.len += 1;
+        }
+
+        self.events[self.next] = Some(Event { timestamp_ticks, kind });
+        self.next = (self.next + 1) % N;
+    }
+
+    /// Takes the oldest event out of the log.
+    pub fn pop(&mut self) -> Option<Event> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let oldest = (self.next + N - self.len) % N;
+        self.len -= 1;
+        self.events[oldest].take()
+    }
+
+    /// Number of events waiting to be drained.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of events dropped to make room for newer ones, saturating at `u32::MAX`.
+    pub fn overwritten(&self) -> u32 {
+        self.overwritten
+    }
+
+    /// Drops every event and resets the overwritten count.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<const N: usize> Default for EventLog<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
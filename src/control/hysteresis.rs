@@ -0,0 +1,47 @@
+/// Schmitt trigger on a measurement, e.g. to cut the load off when a battery runs down.
+///
+/// The output turns on once the value reaches `high` and only turns off again when it drops
+/// below `low`, so noise around a single threshold can't make it chatter. With `low == high`
+/// it is a plain comparator.
+#[derive(Debug, Clone)]
+pub struct Hysteresis {
+    low: f32,
+    high: f32,
+    state: bool,
+}
+
+impl Hysteresis {
+    /// Creates the trigger with its output off, so a value between the thresholds keeps it off
+    /// until it has reached `high` once.
+    pub fn new(low: f32, high: f32) -> Self {
+        assert!(low <= high, "hysteresis low threshold must not be above the high one");
+        Self {
+            low,
+            high,
+            state: false,
+        }
+    }
+
+    /// Starts with the output on instead, e.g. to keep a load running that was already on.
+    pub fn with_state(mut self, state: bool) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// The output as of the last update.
+    pub fn state(&self) -> bool {
+        self.state
+    }
+
+    /// Feeds in the latest `value` and returns the output.
+    ///
+    /// Turns on at or above `high`, off below `low`, and holds in between.
+    pub fn update(&mut self, value: f32) -> bool {
+        if value >= self.high {
+            self.state = true;
+        } else if value < self.low {
+            self.state = false;
+        }
+        self.state
+    }
+}
@@ -0,0 +1,293 @@
+use defmt::Format;
+use embassy_time::Duration;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::SpiBus;
+use crate::adc::transport::RegisterTransport;
+use crate::adc::ADC;
+use crate::control::autotune::{AutotuneError, RelayStep, RelayTuner};
+use crate::control::pid::{ControllerStatus, Pid, PidGains};
+use crate::control::protection::Protection;
+use crate::control::task::Sensing;
+use crate::dac::DAC;
+
+pub mod autotune;
+pub mod events;
+pub mod hysteresis;
+pub mod pid;
+pub mod power;
+pub mod profile;
+pub mod protection;
+pub mod range;
+pub mod selftest;
+pub mod slew;
+pub mod task;
+pub mod throttle;
+pub mod waveform;
+
+/// What the load regulates, along with its setpoint.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub enum LoadMode {
+    /// Sinks a fixed current, in amps.
+    ConstantCurrent(f32),
+    /// Sinks whatever current holds the input at a fixed voltage, in volts.
+    ConstantVoltage(f32),
+    /// Dissipates a fixed power, in watts.
+    ConstantPower(f32),
+    /// Behaves like a fixed resistor, in ohms.
+    ConstantResistance(f32),
+}
+
+/// Estimate of the DAC code that sinks a given current, for
+/// [`LoadController::set_feedforward`].
+///
+/// A straight line `code = codes_per_amp * amps + offset_code`, which is what a DAC driving a
+/// MOSFET through a current sense loop comes close to.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct FeedForward {
+    pub codes_per_amp: f32,
+    /// Code at which current starts to flow.
+    pub offset_code: f32,
+}
+
+impl FeedForward {
+    /// Solves the line through two `(amps, code)` points measured on the load, e.g. the current
+    /// flowing with the DAC held at a low and a high code.
+    pub fn from_two_points(a: (f32, u32), b: (f32, u32)) -> Self {
+        assert!(a.0 != b.0, "feed-forward points need different currents");
+
+        let codes_per_amp = (b.1 as f32 - a.1 as f32) / (b.0 - a.0);
+        Self {
+            codes_per_amp,
+            offset_code: a.1 as f32 - codes_per_amp * a.0,
+        }
+    }
+
+    /// The estimated code for `amps`, not limited to the DAC's range.
+    pub fn code(&self, amps: f32) -> f32 {
+        self.codes_per_amp * amps + self.offset_code
+    }
+}
+
+/// Turns measured voltage and current into a DAC code for the active [`LoadMode`].
+///
+/// The DAC is assumed to set the load current, so a higher code always sinks more current.
+#[derive(Debug, Clone)]
+pub struct LoadController {
+    pid: Pid,
+    mode: LoadMode,
+    max_code: u32,
+    output: u32,
+    enabled: bool,
+    soft_start_secs: f32,
+    /// Time since the load was enabled while the soft-start ramp is still running.
+    ramp_secs: Option<f32>,
+    feedforward: Option<FeedForward>,
+    /// Feed-forward part of the last output, the rest came from the PID.
+    feedforward_code: f32,
+}
+
+impl LoadController {
+    /// Creates a controller driving a DAC whose codes go up to `max_code`.
+    ///
+    /// The gains of `pid` are in DAC codes per unit of error of the active mode. Its output
+    /// limits are replaced by the DAC's range.
+    pub fn new(pid: Pid, max_code: u32, mode: LoadMode) -> Self {
+        Self {
+            pid: pid.with_output_limits(0.0, max_code as f32),
+            mode,
+            max_code,
+            output: 0,
+            enabled: false,
+            soft_start_secs: 0.0,
+            ramp_secs: None,
+            feedforward: None,
+            feedforward_code: 0.0,
+        }
+    }
+
+    pub fn mode(&self) -> LoadMode {
+        self.mode
+    }
+
+    /// The gains of the underlying PID, e.g. to store them after [`LoadController::autotune`].
+    pub fn gains(&self) -> PidGains {
+        self.pid.gains()
+    }
+
+    /// The DAC code returned by the last update.
+    pub fn output(&self) -> u32 {
+        self.output
+    }
+
+    /// Whether the load is on, i.e. [`LoadController::update`] has run since creation or the
+    /// last [`LoadController::disable`].
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Ramps the output ceiling from zero to full scale over `duration` each time the load is
+    /// enabled, so the device under test doesn't see an inrush. Zero, the default, turns the
+    /// soft start off.
+    ///
+    /// Unlike a [`SlewLimiter`](slew::SlewLimiter) this only acts on enable. The controller
+    /// reports [`Saturation::High`](pid::Saturation::High) while the ramp holds it back.
+    pub fn set_soft_start(&mut self, duration: Duration) {
+        self.soft_start_secs = duration.as_micros() as f32 / 1_000_000.0;
+    }
+
+    /// Holds the output still while the error stays within `amount`, see [`Pid::set_deadband`].
+    ///
+    /// The error is in the units of the mode: amps, volts, watts, or amps again for constant
+    /// resistance, so a mode switch may call for a new deadband.
+    pub fn set_deadband(&mut self, amount: f32) {
+        self.pid.set_deadband(amount);
+    }
+
+    /// Adds the code `feedforward` estimates for the load current the mode asks for to the PID
+    /// output, so the load jumps close to a new setpoint and the PID only trims what's left.
+    /// `None`, the default, leaves the PID on its own.
+    ///
+    /// The current asked for is the setpoint in constant current, and follows from the setpoint
+    /// and the measured voltage in constant power and constant resistance. Constant voltage
+    /// has no current to aim for and gets no feed-forward.
+    pub fn set_feedforward(&mut self, feedforward: Option<FeedForward>) {
+        self.feedforward = feedforward;
+    }
+
+    /// Turns the load off, e.g. on a fault. The output drops to zero and the controller starts
+    /// afresh, with the soft start, on the next update.
+    pub fn disable(&mut self) {
+        self.enabled = false;
+        self.output = 0;
+        self.feedforward_code = 0.0;
+        self.pid.reset();
+    }
+
+    /// Switches to `mode`, carrying on from the current output so the load doesn't jump.
+    ///
+    /// With [`LoadController::set_feedforward`] only the PID's trim carries on, and the output
+    /// moves straight to the estimate for the new setpoint on the next update.
+    pub fn set_mode(&mut self, mode: LoadMode) {
+        self.mode = mode;
+        self.pid.seed(self.output as f32 - self.feedforward_code);
+    }
+
+    /// Runs one control cycle on the latest measurements and returns the DAC code to write,
+    /// along with whether the output is pinned at either end of the DAC's range.
+    pub fn update(&mut self, voltage: f32, current: f32, dt_secs: f32) -> (u32, ControllerStatus) {
+        if !self.enabled {
+            self.enabled = true;
+            // without a soft start the ramp finishes straight away, restoring full scale
+            self.ramp_secs = Some(0.0);
+        }
+        let ceiling = self.advance_soft_start(dt_secs);
+
+        let feedforward = match (self.feedforward, self.target_current(voltage)) {
+            (Some(feedforward), Some(amps)) => feedforward.code(amps).clamp(0.0, ceiling),
+            _ => 0.0,
+        };
+        // the limits apply to the sum, so anti-windup sees the real headroom
+        self.pid.set_output_limits(-feedforward, ceiling - feedforward);
+        self.feedforward_code = feedforward;
+
+        let (setpoint, measured) = self.error_terms(voltage, current);
+        let (correction, status) = self.pid.update(setpoint, measured, dt_secs);
+        let output = feedforward + correction;
+
+        // float to int casts saturate, the PID limits keep it in range otherwise
+        self.output = ((output + 0.5) as u32).min(self.max_code);
+        (self.output, status)
+    }
+
+    /// Tunes the gains for the active mode with a relay feedback experiment around its
+    /// setpoint, see [`RelayTuner`], and switches the controller over to them.
+    ///
+    /// Blocks for the whole experiment, measuring with `sensing` and writing the relay codes
+    /// straight to `dac` once every period of `tuner`. Every reading is checked against
+    /// `protection` first and a trip ends the experiment with [`AutotuneError::Fault`]. The
+    /// relay codes have to straddle the setpoint, and should be close enough to it that the
+    /// swing stays well inside the limits.
+    ///
+    /// The load is switched off afterwards, successful or not, and the controller disabled so it
+    /// comes back through its soft start.
+    pub fn autotune<AdcBus: RegisterTransport, AdcDelay: DelayNs, DacBus: SpiBus>(
+        &mut self,
+        mut tuner: RelayTuner,
+        adc: &mut ADC<AdcBus, AdcDelay>,
+        dac: &mut DAC<'_, DacBus>,
+        sensing: &Sensing,
+        protection: &mut Protection,
+        delay: &mut impl DelayNs,
+    ) -> Result<PidGains, AutotuneError<AdcBus::Error, DacBus::Error>> {
+        let tick_us = tuner.period().as_micros() as u32;
+        let result = loop {
+            let measurement = match sensing.measure(adc) {
+                Ok(measurement) => measurement,
+                Err(error) => break Err(AutotuneError::Adc(error)),
+            };
+            if let Some(fault) = protection.check(measurement.voltage, measurement.current, measurement.temperature) {
+                break Err(AutotuneError::Fault(fault));
+            }
+
+            let (setpoint, measured) = self.error_terms(measurement.voltage, measurement.current);
+            match tuner.update(setpoint - measured) {
+                RelayStep::Output(code) => {
+                    if let Err(error) = dac.write(code) {
+                        break Err(AutotuneError::Dac(error));
+                    }
+                }
+                RelayStep::Done(gains) => break Ok(gains),
+                RelayStep::TimedOut => break Err(AutotuneError::NoOscillation),
+            }
+
+            delay.delay_us(tick_us);
+        };
+
+        self.disable();
+        let off = dac.write(0);
+        let gains = result?;
+        off.map_err(AutotuneError::Dac)?;
+
+        self.pid.set_gains(gains);
+        Ok(gains)
+    }
+
+    /// The setpoint and measurement of the active mode, oriented so a positive difference
+    /// calls for a higher code.
+    fn error_terms(&self, voltage: f32, current: f32) -> (f32, f32) {
+        match self.mode {
+            LoadMode::ConstantCurrent(amps) => (amps, current),
+            // sinking more current pulls the voltage down, so the error is inverted
+            LoadMode::ConstantVoltage(volts) => (voltage, volts),
+            LoadMode::ConstantPower(watts) => (watts, voltage * current),
+            // regulating I against V / R keeps the error defined when no current flows yet
+            LoadMode::ConstantResistance(ohms) => (voltage / ohms, current),
+        }
+    }
+
+    /// The load current the active mode asks for at `voltage`, if it asks for one.
+    fn target_current(&self, voltage: f32) -> Option<f32> {
+        match self.mode {
+            LoadMode::ConstantCurrent(amps) => Some(amps),
+            LoadMode::ConstantVoltage(_) => None,
+            LoadMode::ConstantPower(watts) => (voltage > 0.0).then(|| watts / voltage),
+            LoadMode::ConstantResistance(ohms) => Some(voltage / ohms),
+        }
+    }
+
+    /// Moves the soft-start ramp on by `dt_secs` and returns the output ceiling it has got to.
+    fn advance_soft_start(&mut self, dt_secs: f32) -> f32 {
+        let Some(ramp_secs) = self.ramp_secs else {
+            return self.max_code as f32;
+        };
+
+        let ramp_secs = ramp_secs + dt_secs;
+        if ramp_secs >= self.soft_start_secs {
+            self.ramp_secs = None;
+            self.max_code as f32
+        } else {
+            self.ramp_secs = Some(ramp_secs);
+            self.max_code as f32 * ramp_secs / self.soft_start_secs
+        }
+    }
+}
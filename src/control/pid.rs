@@ -0,0 +1,166 @@
+use defmt::Format;
+
+/// Whether the controller output ended up at one of its limits.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Saturation {
+    InRange,
+    /// Clamped to the minimum, e.g. the load is off and still too much current flows.
+    Low,
+    /// Clamped to the maximum, e.g. the load can't sink enough to reach the setpoint.
+    High,
+}
+
+/// What happened inside the controller on an update, for showing a regulation limit indicator.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControllerStatus {
+    pub saturation: Saturation,
+    /// The integral was held back this update to keep it from winding up past the limit.
+    pub integral_held: bool,
+}
+
+impl ControllerStatus {
+    /// Whether the setpoint is out of reach, the output can't go any further towards it.
+    pub fn at_limit(&self) -> bool {
+        self.saturation != Saturation::InRange
+    }
+}
+
+/// Proportional, integral and derivative gains of a [`Pid`].
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct PidGains {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+}
+
+/// A PID controller with output clamping and integral anti-windup.
+///
+/// The integral is stored already scaled by `ki`, so it is in output units and can be compared
+/// against the output limits directly.
+#[derive(Debug, Clone)]
+pub struct Pid {
+    kp: f32,
+    ki: f32,
+    kd: f32,
+    integral: f32,
+    previous_error: Option<f32>,
+    output_min: f32,
+    output_max: f32,
+    deadband: f32,
+}
+
+impl Pid {
+    /// Creates a controller with the given gains and an unbounded output.
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            integral: 0.0,
+            previous_error: None,
+            output_min: f32::NEG_INFINITY,
+            output_max: f32::INFINITY,
+            deadband: 0.0,
+        }
+    }
+
+    /// Clamps the output to `min..=max`, which is also the range the integral term is held in.
+    pub fn with_output_limits(mut self, min: f32, max: f32) -> Self {
+        self.set_output_limits(min, max);
+        self
+    }
+
+    /// The current gains.
+    pub fn gains(&self) -> PidGains {
+        PidGains { kp: self.kp, ki: self.ki, kd: self.kd }
+    }
+
+    /// Swaps in new gains, e.g. from [`LoadController::autotune`](super::LoadController::autotune).
+    ///
+    /// The integral is kept, as it is stored in output units it stays valid with a new `ki`.
+    pub fn set_gains(&mut self, gains: PidGains) {
+        self.kp = gains.kp;
+        self.ki = gains.ki;
+        self.kd = gains.kd;
+    }
+
+    /// Changes the output limits on a running controller, the integral is pulled into the new
+    /// range on the next update.
+    pub fn set_output_limits(&mut self, min: f32, max: f32) {
+        assert!(min <= max, "PID output minimum must not exceed the maximum");
+        self.output_min = min;
+        self.output_max = max;
+    }
+
+    /// Treats errors of at most `amount` either way as no error at all, so a measurement
+    /// hovering around the setpoint doesn't dither the output. Zero, the default, turns the
+    /// deadband off.
+    ///
+    /// Inside the band the error counts as zero, so the proportional term drops out and the
+    /// integral doesn't accumulate. The derivative is taken on that zeroed error, so entering
+    /// or leaving the band is a step in the error like any other and kicks it once; only
+    /// while the measurement stays inside does the output hold still. Outside the band the
+    /// error is used as is.
+    pub fn set_deadband(&mut self, amount: f32) {
+        assert!(amount >= 0.0, "PID deadband must not be negative");
+        self.deadband = amount;
+    }
+
+    /// Runs one step of the controller and returns the clamped output, along with whether it
+    /// was clamped.
+    ///
+    /// The derivative is taken on the error and skipped on the first update after creation or
+    /// [`Pid::reset`], so there's no kick from the jump from nothing to the first error. While the
+    /// output is saturated the integral stops accumulating in the direction that would push it
+    /// further past the limit.
+    pub fn update(&mut self, setpoint: f32, measured: f32, dt_secs: f32) -> (f32, ControllerStatus) {
+        let error = setpoint - measured;
+        let error = if error.abs() <= self.deadband { 0.0 } else { error };
+
+        let derivative = match self.previous_error {
+            Some(previous_error) if dt_secs > 0.0 => self.kd * (error - previous_error) / dt_secs,
+            _ => 0.0,
+        };
+        self.previous_error = Some(error);
+
+        let proportional = self.kp * error;
+        let unclamped_integral = self.integral + self.ki * error * dt_secs;
+        let integral = unclamped_integral.clamp(self.output_min, self.output_max);
+
+        let output = proportional + integral + derivative;
+        let saturated_high = output > self.output_max && error > 0.0;
+        let saturated_low = output < self.output_min && error < 0.0;
+        if !(saturated_high || saturated_low) {
+            self.integral = integral;
+        }
+        let integral_held = self.integral != unclamped_integral;
+
+        // at a limit and the error still pushing past it, however the output got there
+        let output = proportional + self.integral + derivative;
+        let saturation = if output >= self.output_max && error > 0.0 {
+            Saturation::High
+        } else if output <= self.output_min && error < 0.0 {
+            Saturation::Low
+        } else {
+            Saturation::InRange
+        };
+
+        (output.clamp(self.output_min, self.output_max), ControllerStatus { saturation, integral_held })
+    }
+
+    /// Preloads the integral so the next update starts out around `output` instead of zero.
+    ///
+    /// Used for bumpless transfer, e.g. when the quantity being regulated changes but the
+    /// actuator should stay where it is. The derivative history is cleared as the old error
+    /// doesn't relate to the new one.
+    pub fn seed(&mut self, output: f32) {
+        self.integral = output.clamp(self.output_min, self.output_max);
+        self.previous_error = None;
+    }
+
+    /// Clears the integral and derivative history, as if the controller was just created.
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.previous_error = None;
+    }
+}
@@ -0,0 +1,128 @@
+use embassy_time::Duration;
+use crate::adc::Channel;
+
+/// Matches up voltage and current readings tagged with their channel, e.g. by
+/// [`Sequencer::next_sample`](crate::adc::Sequencer::next_sample), into pairs taken in the same
+/// turn of the sequencer.
+///
+/// The ADC converts the enabled channels in ascending order, once each per turn, so a pair
+/// starts with whichever of the two channels is lower. A reading of the higher channel without
+/// one of the lower channel before it belongs to a turn that was joined halfway and is dropped,
+/// as is a lower channel reading replaced by the next one before its partner came.
+#[derive(Debug, Clone)]
+pub struct SamplePairer {
+    voltage_channel: Channel,
+    current_channel: Channel,
+    pending: Option<f32>,
+}
+
+impl SamplePairer {
+    pub fn new(voltage_channel: Channel, current_channel: Channel) -> Self {
+        assert!(voltage_channel != current_channel, "voltage and current need their own channels");
+        Self {
+            voltage_channel,
+            current_channel,
+            pending: None,
+        }
+    }
+
+    /// Takes a reading of `channel`, in volts or amps, and returns `(voltage, current)` once
+    /// both halves of a turn are in. Readings of any other channel are ignored.
+    pub fn push(&mut self, channel: Channel, value: f32) -> Option<(f32, f32)> {
+        let voltage_first = self.voltage_channel.into_bits() < self.current_channel.into_bits();
+        let (first, second) = if voltage_first {
+            (self.voltage_channel, self.current_channel)
+        } else {
+            (self.current_channel, self.voltage_channel)
+        };
+
+        if channel == first {
+            self.pending = Some(value);
+            return None;
+        }
+        if channel != second {
+            return None;
+        }
+
+        let earlier = self.pending.take()?;
+        Some(if voltage_first { (earlier, value) } else { (value, earlier) })
+    }
+}
+
+/// Power and energy taken in by the load, from paired voltage and current samples.
+///
+/// Energy is integrated from the first sample on. The average power is over a fixed window,
+/// updated each time a window completes, so a display reading it doesn't flicker with every
+/// sample.
+#[derive(Debug, Clone)]
+pub struct PowerMeter {
+    window_secs: f32,
+    instantaneous_w: f32,
+    average_w: Option<f32>,
+    // f64 so small increments still register after hours of running
+    energy_j: f64,
+    window_energy_j: f32,
+    window_elapsed_secs: f32,
+}
+
+impl PowerMeter {
+    /// Creates a meter averaging power over `window`.
+    pub fn new(window: Duration) -> Self {
+        assert!(window.as_ticks() > 0, "power averaging window must not be empty");
+        Self {
+            window_secs: window.as_micros() as f32 / 1_000_000.0,
+            instantaneous_w: 0.0,
+            average_w: None,
+            energy_j: 0.0,
+            window_energy_j: 0.0,
+            window_elapsed_secs: 0.0,
+        }
+    }
+
+    /// Adds a `voltage` and `current` pair from the same conversion cycle, see
+    /// [`SamplePairer`], taken `dt_secs` after the previous one.
+    pub fn add(&mut self, voltage: f32, current: f32, dt_secs: f32) {
+        self.instantaneous_w = voltage * current;
+
+        let energy_j = self.instantaneous_w * dt_secs;
+        self.energy_j += energy_j as f64;
+        self.window_energy_j += energy_j;
+        self.window_elapsed_secs += dt_secs;
+
+        if self.window_elapsed_secs >= self.window_secs {
+            self.average_w = Some(self.window_energy_j / self.window_elapsed_secs);
+            self.window_energy_j = 0.0;
+            self.window_elapsed_secs = 0.0;
+        }
+    }
+
+    /// Power of the last pair, in watts.
+    pub fn instantaneous_w(&self) -> f32 {
+        self.instantaneous_w
+    }
+
+    /// Average power over the last complete window, in watts.
+    ///
+    /// Until the first window completes this is the average so far, and zero before any sample.
+    pub fn power_w(&self) -> f32 {
+        match self.average_w {
+            Some(average_w) => average_w,
+            None if self.window_elapsed_secs > 0.0 => self.window_energy_j / self.window_elapsed_secs,
+            None => 0.0,
+        }
+    }
+
+    /// Energy taken in since creation or the last [`PowerMeter::reset`], in joules.
+    pub fn energy_j(&self) -> f32 {
+        self.energy_j as f32
+    }
+
+    /// Starts over from zero energy and no power reading.
+    pub fn reset(&mut self) {
+        self.instantaneous_w = 0.0;
+        self.average_w = None;
+        self.energy_j = 0.0;
+        self.window_energy_j = 0.0;
+        self.window_elapsed_secs = 0.0;
+    }
+}
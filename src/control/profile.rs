@@ -0,0 +1,103 @@
+use defmt::Format;
+use embassy_time::Duration;
+
+/// How a [`Segment`] gets from the previous setpoint to its target.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ramp {
+    /// Jumps to the target at the start of the segment and holds it.
+    Step,
+    /// Moves linearly from the previous setpoint, reaching the target at the end of the segment.
+    Linear,
+}
+
+/// One piece of a [`Profile`].
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub duration: Duration,
+    pub target: f32,
+    pub ramp: Ramp,
+}
+
+impl Segment {
+    /// Jumps to `target` and holds it for `duration`.
+    pub const fn hold(target: f32, duration: Duration) -> Self {
+        Self { duration, target, ramp: Ramp::Step }
+    }
+
+    /// Ramps linearly to `target` over `duration`.
+    pub const fn ramp(target: f32, duration: Duration) -> Self {
+        Self { duration, target, ramp: Ramp::Linear }
+    }
+}
+
+/// A scripted setpoint sequence, e.g. hold 1 A for 100 ms then ramp to 5 A over 200 ms.
+///
+/// Segments are borrowed so profiles can live in a `static`. The setpoint starts at 0, or the
+/// value given to [`Profile::with_start`], and every segment starts from where the previous one
+/// ended. Zero length segments are stepped over, though their target still becomes the starting
+/// point of the next segment.
+#[derive(Debug, Clone)]
+pub struct Profile<'a> {
+    segments: &'a [Segment],
+    start: f32,
+    looping: bool,
+}
+
+impl<'a> Profile<'a> {
+    /// Creates a profile that plays `segments` once and then holds the last target.
+    pub const fn new(segments: &'a [Segment]) -> Self {
+        Self {
+            segments,
+            start: 0.0,
+            looping: false,
+        }
+    }
+
+    /// Sets the setpoint the first segment starts from.
+    pub const fn with_start(mut self, start: f32) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Makes the profile start over from the beginning, and the start value, once it ends.
+    pub const fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Total length of one pass through the segments.
+    pub fn duration(&self) -> Duration {
+        self.segments.iter().fold(Duration::from_ticks(0), |total, segment| total + segment.duration)
+    }
+
+    /// Whether a non-looping profile has played out at `elapsed`. Looping profiles never finish.
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        !self.looping && elapsed >= self.duration()
+    }
+
+    /// The setpoint `elapsed` after the profile was started.
+    pub fn sample(&self, elapsed: Duration) -> f32 {
+        let total = self.duration().as_ticks();
+        let mut ticks = elapsed.as_ticks();
+        if self.looping && total > 0 {
+            ticks %= total;
+        }
+
+        let mut from = self.start;
+        for segment in self.segments {
+            let length = segment.duration.as_ticks();
+            if ticks < length {
+                return match segment.ramp {
+                    Ramp::Step => segment.target,
+                    Ramp::Linear => from + (segment.target - from) * (ticks as f32 / length as f32),
+                };
+            }
+
+            ticks -= length;
+            from = segment.target;
+        }
+
+        // past the end, or no segments at all
+        from
+    }
+}
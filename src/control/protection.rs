@@ -0,0 +1,97 @@
+use defmt::Format;
+use embedded_hal::spi::SpiBus;
+use crate::dac::{DacError, DAC};
+
+/// Which limit tripped the [`Protection`].
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultKind {
+    OverCurrent,
+    OverVoltage,
+    OverTemperature,
+}
+
+/// Trip limits checked every control cycle, latching a fault that holds the load off.
+///
+/// A reading that isn't a number counts as over its limit. The fault only forces zero load
+/// where the DAC is written through [`Protection::write_guarded`] or
+/// [`Protection::write_latched`], as [`control_loop`](crate::control::task::control_loop) does.
+#[derive(Debug, Clone)]
+pub struct Protection {
+    max_current: f32,
+    max_voltage: f32,
+    max_temperature: f32,
+    fault: Option<FaultKind>,
+}
+
+impl Protection {
+    /// Creates the limits, in amps, volts and degrees Celsius.
+    pub fn new(max_current: f32, max_voltage: f32, max_temperature: f32) -> Self {
+        Self {
+            max_current,
+            max_voltage,
+            max_temperature,
+            fault: None,
+        }
+    }
+
+    /// The latched fault, if any limit has been exceeded since it was last cleared.
+    pub fn fault(&self) -> Option<FaultKind> {
+        self.fault
+    }
+
+    /// Checks the latest readings, latching a fault if any of them is over its limit.
+    ///
+    /// The first fault is kept until [`Protection::clear_fault`], later ones don't replace it.
+    pub fn check(&mut self, voltage: f32, current: f32, temperature: f32) -> Option<FaultKind> {
+        if self.fault.is_none() {
+            self.fault = self.exceeded(voltage, current, temperature);
+        }
+        self.fault
+    }
+
+    /// Clears the latched fault, as long as the readings are back within every limit.
+    ///
+    /// Returns the limit that is still exceeded otherwise. The load controller should be reset
+    /// before the load is re-enabled, its integral is stale after the trip.
+    pub fn clear_fault(&mut self, voltage: f32, current: f32, temperature: f32) -> Result<(), FaultKind> {
+        if let Some(fault) = self.exceeded(voltage, current, temperature) {
+            return Err(fault);
+        }
+        self.fault = None;
+        Ok(())
+    }
+
+    /// Checks the readings and writes `code` to the DAC, or zero load while faulted.
+    ///
+    /// Returns the code that was actually written.
+    pub fn write_guarded<Bus: SpiBus>(&mut self, dac: &mut DAC<'_, Bus>, code: u32, voltage: f32, current: f32, temperature: f32) -> Result<u32, DacError<Bus::Error>> {
+        self.check(voltage, current, temperature);
+        self.write_latched(dac, code)
+    }
+
+    /// Writes `code` to the DAC, or zero load while a fault is latched, for callers that
+    /// checked the readings already.
+    ///
+    /// Returns the code that was actually written.
+    pub fn write_latched<Bus: SpiBus>(&self, dac: &mut DAC<'_, Bus>, code: u32) -> Result<u32, DacError<Bus::Error>> {
+        let code = match self.fault {
+            Some(_) => 0,
+            None => code,
+        };
+        dac.write(code)?;
+        Ok(code)
+    }
+
+    #[allow(clippy::neg_cmp_op_on_partial_ord, reason = "a NaN reading has to trip the limit")]
+    fn exceeded(&self, voltage: f32, current: f32, temperature: f32) -> Option<FaultKind> {
+        if !(current <= self.max_current) {
+            Some(FaultKind::OverCurrent)
+        } else if !(voltage <= self.max_voltage) {
+            Some(FaultKind::OverVoltage)
+        } else if !(temperature <= self.max_temperature) {
+            Some(FaultKind::OverTemperature)
+        } else {
+            None
+        }
+    }
+}
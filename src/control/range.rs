@@ -0,0 +1,79 @@
+use defmt::Format;
+use embedded_hal::delay::DelayNs;
+use esp_hal::gpio::{Level, Output};
+use crate::adc::scaling::Scaling;
+use crate::adc::transport::RegisterTransport;
+use crate::adc::{AdcError, Input, Setup, ADC};
+use crate::control::task::Sensing;
+
+/// One current range of the load: the shunt or amplifier path the current is measured through,
+/// and how codes on that path turn into amps.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    /// Setup the current channel converts with, configured beforehand with whatever reference,
+    /// buffers and filter the path needs, e.g. with a
+    /// [`SetupBuilder`](crate::adc::setup_builder::SetupBuilder).
+    pub setup: Setup,
+    pub input_pos: Input,
+    pub input_neg: Input,
+    /// Level of the range select pin, ignored without one.
+    pub select_high: bool,
+    pub scaling: Scaling,
+}
+
+/// Moves the load between [`Range`]s, keeping the current channel, the range select pin and the
+/// scaling in [`Sensing`] in step.
+///
+/// The shunt or gain switched by the select pin should only change under no load, disable the
+/// controller first and let it come back through its soft start.
+pub struct RangeSwitch {
+    select_pin: Option<Output<'static>>,
+    range: Option<Range>,
+}
+
+impl RangeSwitch {
+    /// Creates a switch for ranges that differ only in their ADC setup and inputs.
+    pub fn new() -> Self {
+        Self {
+            select_pin: None,
+            range: None,
+        }
+    }
+
+    /// Also drives `select_pin`, e.g. the gate of a shunt bypass or a gain select input, to the
+    /// level of each range.
+    pub fn with_select_pin(mut self, select_pin: Output<'static>) -> Self {
+        self.select_pin = Some(select_pin);
+        self
+    }
+
+    /// The range in use, or `None` before the first switch or after a failed one.
+    pub fn range(&self) -> Option<Range> {
+        self.range
+    }
+
+    /// Switches the current channel of `sensing` to `range`.
+    ///
+    /// The channel is reconfigured first, and the select pin and the scaling only follow once
+    /// that succeeded, so a measurement never pairs the codes of one range with the scaling of
+    /// another. On error the channel may have been written regardless, [`RangeSwitch::range`]
+    /// goes back to `None` and the switch has to be repeated before measuring again.
+    pub fn set_range<Bus: RegisterTransport, AdcDelay: DelayNs>(&mut self, adc: &mut ADC<Bus, AdcDelay>, sensing: &mut Sensing, range: Range) -> Result<(), AdcError<Bus::Error>> {
+        self.range = None;
+        adc.configure_channel(sensing.current_channel, range.setup, range.input_pos, range.input_neg)?;
+
+        if let Some(select_pin) = &mut self.select_pin {
+            select_pin.set_level(Level::from(range.select_high));
+        }
+        sensing.current_scaling = range.scaling;
+        self.range = Some(range);
+
+        Ok(())
+    }
+}
+
+impl Default for RangeSwitch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,84 @@
+use defmt::Format;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::SpiBus;
+use crate::adc::register::DataRegister;
+use crate::adc::transport::RegisterTransport;
+use crate::adc::{AdcError, Input, OutputCoding, Setup, ADC};
+use crate::dac::{DacError, DAC};
+
+/// Levels the self-test drives the DAC to, as fractions of its reference. The rails are left
+/// out, a DAC stuck at either one would pass there.
+const SELFTEST_LEVELS: [f32; 5] = [0.1, 0.3, 0.5, 0.7, 0.9];
+
+/// Errors failing a [`selftest`]. The DAC is set back to zero whichever one it is.
+#[derive(Debug, Format)]
+pub enum SelfTestError<AdcE, DacE> {
+    /// Converting the monitored output failed.
+    Adc(AdcError<AdcE>),
+    /// Setting a level failed.
+    Dac(DacError<DacE>),
+    /// The reading at `expected` volts didn't rise above the one of the level below, e.g.
+    /// because the DAC output or the monitor input is open or shorted.
+    NotMonotonic { expected: f32, measured: f32 },
+    /// The reading at `expected` volts was further from it than the tolerance.
+    OutOfTolerance { expected: f32, measured: f32 },
+}
+
+/// The ADC input wired to the DAC output for [`selftest`], and how closely it has to follow.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct DacMonitor {
+    pub input_pos: Input,
+    pub input_neg: Input,
+    /// Setup the monitor input converts with, its reference matching `adc_reference_mv`.
+    pub setup: Setup,
+    pub adc_reference_mv: f32,
+    pub coding: OutputCoding,
+    pub dac_reference_mv: f32,
+    /// Volts at the ADC input per volt of DAC output, e.g. the ratio of a divider between them.
+    pub ratio: f32,
+    /// Largest difference allowed between the commanded and the measured DAC output, in volts.
+    pub tolerance: f32,
+    /// Time the DAC output and the monitor path get to settle after each level is set, in µs.
+    pub settle_us: u32,
+}
+
+/// Checks the DAC and the ADC against each other at power-on, catching open joints and
+/// miswired channels before the load is trusted with any current.
+///
+/// The DAC is stepped through a few levels across its range and each one is converted through
+/// `monitor`. The readings have to rise with every step and land within the tolerance of the
+/// commanded voltage. Channel 0 of the ADC is reconfigured for the monitor input like in
+/// [`ADC::read_single`], and the ADC is left in standby. The DAC is set back to zero load
+/// afterwards, passed or not.
+pub fn selftest<AdcBus: RegisterTransport, AdcDelay: DelayNs, DacBus: SpiBus>(
+    adc: &mut ADC<AdcBus, AdcDelay>,
+    dac: &mut DAC<'_, DacBus>,
+    monitor: &DacMonitor,
+    delay: &mut impl DelayNs,
+) -> Result<(), SelfTestError<AdcBus::Error, DacBus::Error>> {
+    let mut sweep = || {
+        let mut previous = f32::NEG_INFINITY;
+        for level in SELFTEST_LEVELS {
+            let expected = level * monitor.dac_reference_mv / 1000.0;
+            dac.write_voltage(expected, monitor.dac_reference_mv).map_err(SelfTestError::Dac)?;
+            delay.delay_us(monitor.settle_us);
+
+            let code = adc.read_single(monitor.input_pos, monitor.input_neg, monitor.setup).map_err(SelfTestError::Adc)?;
+            let measured = DataRegister::new().with_data(code).to_voltage(monitor.adc_reference_mv, monitor.coding) / 1000.0 / monitor.ratio;
+
+            if measured <= previous {
+                return Err(SelfTestError::NotMonotonic { expected, measured });
+            }
+            if (measured - expected).abs() > monitor.tolerance {
+                return Err(SelfTestError::OutOfTolerance { expected, measured });
+            }
+            previous = measured;
+        }
+        Ok(())
+    };
+    let result = sweep();
+
+    let off = dac.write(0);
+    result?;
+    off.map_err(SelfTestError::Dac)
+}
@@ -0,0 +1,59 @@
+use embedded_hal::spi::SpiBus;
+use crate::dac::{DacError, DAC};
+
+/// Ramps DAC setpoint changes at a bounded rate instead of stepping.
+///
+/// The rate is given in codes per write, so with a fixed control tick a rate in amps per
+/// second converts as `amps_per_sec * tick_secs * max_code / full_scale_amps`.
+#[derive(Debug, Clone)]
+pub struct SlewLimiter {
+    last: u32,
+    max_step: Option<u32>,
+}
+
+impl SlewLimiter {
+    /// Creates a limiter starting from code 0, moving at most `max_step` codes per write.
+    ///
+    /// `max_step` must not be 0, see [`SlewLimiter::set_slew_rate`].
+    pub fn new(max_step: u32) -> Self {
+        let mut limiter = Self {
+            last: 0,
+            max_step: None,
+        };
+        limiter.set_slew_rate(Some(max_step));
+        limiter
+    }
+
+    /// Sets the largest change per write, or `None` to let setpoints step straight through.
+    pub fn set_slew_rate(&mut self, max_step: Option<u32>) {
+        assert!(max_step != Some(0), "slew rate must allow some movement, use None to disable");
+        self.max_step = max_step;
+    }
+
+    /// The code most recently commanded.
+    pub fn last(&self) -> u32 {
+        self.last
+    }
+
+    /// Makes the limiter ramp from `code` next, for when the DAC was written behind its back.
+    pub fn reset(&mut self, code: u32) {
+        self.last = code;
+    }
+
+    /// Moves one step towards `target` and returns the code to command.
+    pub fn next(&mut self, target: u32) -> u32 {
+        self.last = match self.max_step {
+            Some(max_step) if target > self.last => target.min(self.last.saturating_add(max_step)),
+            Some(max_step) => target.max(self.last.saturating_sub(max_step)),
+            None => target,
+        };
+        self.last
+    }
+
+    /// Writes the next step towards `target` to the DAC and returns the code that was written.
+    pub fn write<Bus: SpiBus>(&mut self, dac: &mut DAC<'_, Bus>, target: u32) -> Result<u32, DacError<Bus::Error>> {
+        let code = self.next(target);
+        dac.write(code)?;
+        Ok(code)
+    }
+}
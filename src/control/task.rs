@@ -0,0 +1,191 @@
+use core::cell::RefCell;
+use defmt::{warn, Format};
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, RawMutex};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Ticker};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::SpiBus;
+use esp_hal::Blocking;
+use esp_hal::spi::master::SpiDmaBus;
+use crate::adc::register::DataRegister;
+use crate::adc::scaling::Scaling;
+use crate::adc::transport::RegisterTransport;
+use crate::adc::{AdcError, Channel, OutputCoding, Sequencer, ADC};
+use crate::control::events::{EventKind, EventLog};
+use crate::control::protection::Protection;
+use crate::control::{LoadController, LoadMode};
+use crate::dac::DAC;
+
+/// Conversions read per cycle before giving up on seeing every sensed channel, two full turns
+/// of the sequencer.
+const MAX_SAMPLES_PER_CYCLE: usize = 8;
+
+/// Events [`control_loop_task`] keeps before overwriting the oldest.
+pub const EVENT_LOG_LEN: usize = 32;
+
+/// Where the control loop finds its measurements and how it turns codes into units.
+///
+/// The channels have to be enabled in continuous conversion mode with DATA_STAT set, e.g.
+/// with [`Sequencer::new`], before the loop starts.
+#[derive(Format, Debug, Clone, Copy)]
+pub struct Sensing {
+    pub current_channel: Channel,
+    pub current_scaling: Scaling,
+    pub voltage_channel: Channel,
+    /// Input volts per volt at the ADC, the inverse of the divider ratio.
+    pub voltage_ratio: f32,
+    /// Channel set up for the internal temperature sensor, or `None` to skip the over
+    /// temperature check. The sensor needs a setup with bipolar coding, its conversions are
+    /// always read as bipolar whatever `coding` says.
+    pub temperature_channel: Option<Channel>,
+    pub reference_mv: f32,
+    /// Coding of the current and voltage channels' setups.
+    pub coding: OutputCoding,
+}
+
+/// One cycle's worth of readings, in volts, amps and degrees Celsius.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub voltage: f32,
+    pub current: f32,
+    /// Negative infinity when no temperature channel is sensed.
+    pub temperature: f32,
+}
+
+impl Sensing {
+    /// Reads conversions until every sensed channel has come round once.
+    ///
+    /// Fails with [`AdcError::Timeout`] if a sensed channel doesn't show up within two turns
+    /// of the sequencer, e.g. because it isn't enabled.
+    pub fn measure<Bus: RegisterTransport, AdcDelay: DelayNs>(&self, adc: &mut ADC<Bus, AdcDelay>) -> Result<Measurement, AdcError<Bus::Error>> {
+        let mut sequencer = Sequencer::attach(adc);
+        let mut current = None;
+        let mut voltage = None;
+        // no sensor means nothing to wait for, and a reading that never trips the limit
+        let mut temperature = self.temperature_channel.map_or(Some(f32::NEG_INFINITY), |_| None);
+
+        for _ in 0..MAX_SAMPLES_PER_CYCLE {
+            let (channel, code) = sequencer.next_sample()?;
+            let data = DataRegister::new().with_data(code);
+
+            if channel == self.current_channel {
+                current = Some(self.current_scaling.code_to_amps(code, self.coding));
+            } else if channel == self.voltage_channel {
+                voltage = Some(data.to_voltage(self.reference_mv, self.coding) / 1000.0 * self.voltage_ratio);
+            } else if Some(channel) == self.temperature_channel {
+                temperature = Some(data.to_celsius(OutputCoding::Bipolar));
+            }
+
+            if let (Some(voltage), Some(current), Some(temperature)) = (voltage, current, temperature) {
+                return Ok(Measurement { voltage, current, temperature });
+            }
+        }
+
+        Err(AdcError::Timeout)
+    }
+}
+
+/// Runs the load every `period`: measures, checks the protection limits, updates the controller
+/// and writes the DAC.
+///
+/// A new [`LoadMode`] signalled on `setpoints` is picked up at the start of the next cycle.
+/// While a fault is latched, or when the ADC can't be read, the DAC is held at zero load and
+/// the controller disabled, so it comes back through its soft start, see
+/// [`LoadController::set_soft_start`]. The loop never clears a fault itself, so a trip holds
+/// the load off until the device is reset.
+///
+/// Mode changes, protection trips and ADC reads failing their checksum are pushed to `events`
+/// for whatever reports them to drain, see [`control_cycle`].
+///
+/// Embassy tasks can't be generic, spawn [`control_loop_task`] or wrap this in a task of your
+/// own for other buses.
+#[allow(clippy::too_many_arguments, reason = "the drivers, settings and shared handles the loop runs on")]
+pub async fn control_loop<AdcBus: RegisterTransport, AdcDelay: DelayNs, DacBus: SpiBus, M: RawMutex, const N: usize>(
+    mut adc: ADC<AdcBus, AdcDelay>,
+    mut dac: DAC<'_, DacBus>,
+    mut controller: LoadController,
+    mut protection: Protection,
+    sensing: Sensing,
+    period: Duration,
+    setpoints: &Signal<M, LoadMode>,
+    events: &Mutex<M, RefCell<EventLog<N>>>,
+) -> ! {
+    let dt_secs = period.as_micros() as f32 / 1_000_000.0;
+    let mut ticker = Ticker::every(period);
+
+    loop {
+        if let Some(mode) = setpoints.try_take() {
+            controller.set_mode(mode);
+            record(events, EventKind::ModeChange(mode));
+        }
+
+        control_cycle(&mut adc, &mut dac, &mut controller, &mut protection, &sensing, dt_secs, events);
+
+        ticker.next().await;
+    }
+}
+
+/// One cycle of [`control_loop`] once the setpoint is picked up: measures, checks the
+/// protection limits, updates the controller and writes the DAC.
+///
+/// A protection trip is pushed to `events` on the cycle it latches, and a measurement failing
+/// its checksum every time it does, both stamped with [`Instant::now`].
+pub fn control_cycle<AdcBus: RegisterTransport, AdcDelay: DelayNs, DacBus: SpiBus, M: RawMutex, const N: usize>(
+    adc: &mut ADC<AdcBus, AdcDelay>,
+    dac: &mut DAC<'_, DacBus>,
+    controller: &mut LoadController,
+    protection: &mut Protection,
+    sensing: &Sensing,
+    dt_secs: f32,
+    events: &Mutex<M, RefCell<EventLog<N>>>,
+) {
+    let code = match sensing.measure(adc) {
+        Ok(measurement) => {
+            let latched = protection.fault().is_some();
+            match protection.check(measurement.voltage, measurement.current, measurement.temperature) {
+                Some(fault) => {
+                    if !latched {
+                        record(events, EventKind::Fault(fault));
+                    }
+                    controller.disable();
+                    0
+                }
+                None => controller.update(measurement.voltage, measurement.current, dt_secs).0,
+            }
+        }
+        Err(error) => {
+            if matches!(error, AdcError::CrcMismatch) {
+                record(events, EventKind::CrcError);
+            }
+            warn!("Control loop couldn't read the ADC, holding the load off");
+            controller.disable();
+            0
+        }
+    };
+
+    if protection.write_latched(dac, code).is_err() {
+        warn!("Control loop couldn't write the DAC");
+    }
+}
+
+/// Pushes `kind` to `events`, stamped with the current time.
+fn record<M: RawMutex, const N: usize>(events: &Mutex<M, RefCell<EventLog<N>>>, kind: EventKind) {
+    events.lock(|events| events.borrow_mut().push(Instant::now().as_ticks(), kind));
+}
+
+/// [`control_loop`] as a task for the DMA backed drivers made by `new_with_peripherals`.
+#[allow(clippy::too_many_arguments, reason = "the drivers, settings and shared handles the loop runs on")]
+#[embassy_executor::task]
+pub async fn control_loop_task(
+    adc: ADC<SpiDmaBus<'static, Blocking>>,
+    dac: DAC<'static, SpiDmaBus<'static, Blocking>>,
+    controller: LoadController,
+    protection: Protection,
+    sensing: Sensing,
+    period: Duration,
+    setpoints: &'static Signal<CriticalSectionRawMutex, LoadMode>,
+    events: &'static Mutex<CriticalSectionRawMutex, RefCell<EventLog<EVENT_LOG_LEN>>>,
+) {
+    control_loop(adc, dac, controller, protection, sensing, period, setpoints, events).await
+}
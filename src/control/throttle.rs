@@ -0,0 +1,89 @@
+use embassy_time::{Duration, Instant};
+use embedded_hal::spi::SpiBus;
+use crate::dac::{DacError, DAC};
+
+/// Holds back DAC writes the control loop doesn't need, so the loop can compute a new code
+/// every cycle without the SPI traffic and output steps of writing each one.
+///
+/// A changed code is written once it differs from the last written one by the minimum delta, or
+/// once the minimum interval has passed since the last write. Smaller changes in between are
+/// dropped, the latest one goes out when the interval is up. Zero always goes straight through,
+/// so turning the load off is never delayed. By default every change is written.
+#[derive(Debug, Clone)]
+pub struct DacThrottle {
+    min_interval: Duration,
+    min_delta: u32,
+    /// Last written code and when, `None` before the first write.
+    last: Option<(u32, Instant)>,
+}
+
+impl DacThrottle {
+    pub fn new() -> Self {
+        Self {
+            min_interval: Duration::from_ticks(0),
+            min_delta: 1,
+            last: None,
+        }
+    }
+
+    /// Sets how long a change smaller than the minimum delta waits before it is written.
+    pub fn set_min_dac_interval(&mut self, min_interval: Duration) {
+        self.min_interval = min_interval;
+    }
+
+    /// Sets the change in codes that is written straight away, whatever the interval.
+    pub fn set_min_dac_delta(&mut self, min_delta: u32) {
+        assert!(min_delta > 0, "DAC delta must be at least one code");
+        self.min_delta = min_delta;
+    }
+
+    /// The code most recently written, if any.
+    pub fn last(&self) -> Option<u32> {
+        self.last.map(|(code, _)| code)
+    }
+
+    /// Forgets the last write, for when the DAC was written behind the throttle's back. The
+    /// next code goes straight through.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Decides whether `target` should be written at `now`, returning it if so.
+    pub fn next(&mut self, target: u32, now: Instant) -> Option<u32> {
+        let due = match self.last {
+            None => true,
+            Some((last, _)) if last == target => false,
+            Some((last, written_at)) => {
+                target == 0 || last.abs_diff(target) >= self.min_delta || now.saturating_duration_since(written_at) >= self.min_interval
+            }
+        };
+
+        if due {
+            self.last = Some((target, now));
+        }
+        due.then_some(target)
+    }
+
+    /// Writes `target` to the DAC if [`DacThrottle::next`] lets it through, returning the code
+    /// written.
+    ///
+    /// A failed write is forgotten, so the same code is tried again next time.
+    pub fn write<Bus: SpiBus>(&mut self, dac: &mut DAC<'_, Bus>, target: u32, now: Instant) -> Result<Option<u32>, DacError<Bus::Error>> {
+        let previous = self.last;
+        let Some(code) = self.next(target, now) else {
+            return Ok(None);
+        };
+
+        if let Err(error) = dac.write(code) {
+            self.last = previous;
+            return Err(error);
+        }
+        Ok(Some(code))
+    }
+}
+
+impl Default for DacThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,152 @@
+use defmt::Format;
+use embedded_hal::spi::SpiBus;
+use crate::dac::{DacError, DAC};
+
+/// A quarter period of sine in Q15, `sin(i * π / 128) * 32767` for `i` in `0..=64`.
+const QUARTER_SINE: [i16; 65] = [
+    0, 804, 1608, 2410, 3212, 4011, 4808, 5602,
+    6393, 7179, 7962, 8739, 9512, 10278, 11039, 11793,
+    12539, 13279, 14010, 14732, 15446, 16151, 16846, 17530,
+    18204, 18868, 19519, 20159, 20787, 21403, 22005, 22594,
+    23170, 23731, 24279, 24811, 25329, 25832, 26319, 26790,
+    27245, 27683, 28105, 28510, 28898, 29268, 29621, 29956,
+    30273, 30571, 30852, 31113, 31356, 31580, 31785, 31971,
+    32137, 32285, 32412, 32521, 32609, 32678, 32728, 32757,
+    32767,
+];
+
+/// Full scale of the Q15 samples the shapes are computed in.
+const Q15_ONE: i64 = 32767;
+
+/// The shape of a [`Waveform`].
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Starts at the offset, rising to the peak a quarter period in.
+    Sine,
+    /// Starts at the trough, rising linearly to the peak half a period in.
+    Triangle,
+    /// At the peak for the first half period and the trough for the second.
+    Square,
+}
+
+impl Shape {
+    /// The value of the shape at `phase`, a fraction of a period scaled to the full `u32` range,
+    /// in Q15.
+    fn sample(self, phase: u32) -> i64 {
+        match self {
+            Shape::Sine => sine(phase),
+            Shape::Triangle => {
+                // distance from the start of the period, folded back on the way down
+                let rise = if phase < 1 << 31 { phase } else { phase.wrapping_neg() };
+                ((rise as i64 * 2 * Q15_ONE) >> 31) - Q15_ONE
+            }
+            Shape::Square if phase < 1 << 31 => Q15_ONE,
+            Shape::Square => -Q15_ONE,
+        }
+    }
+}
+
+/// Looks up sine in Q15, interpolating linearly between table entries.
+fn sine(phase: u32) -> i64 {
+    let quadrant = phase >> 30;
+    // position within the quadrant, 6 bits of table index and 24 bits of fraction
+    let mut offset = phase & 0x3fff_ffff;
+    if quadrant & 1 == 1 {
+        offset = 0x4000_0000 - offset;
+    }
+
+    let index = (offset >> 24) as usize;
+    let fraction = (offset & 0xff_ffff) as i64;
+    let low = QUARTER_SINE[index] as i64;
+    let high = QUARTER_SINE[(index + 1).min(64)] as i64;
+    let value = low + (((high - low) * fraction) >> 24);
+
+    if quadrant >= 2 { -value } else { value }
+}
+
+/// Periodic setpoint, e.g. for looking at the transient response of the device under test.
+///
+/// Produces DAC codes swinging `amplitude` codes either side of `offset`, clamped to
+/// `0..=max_code`. Sine is looked up from a table, so no floating point maths library is needed.
+#[derive(Debug, Clone)]
+pub struct Waveform {
+    shape: Shape,
+    frequency_mhz: u64,
+    amplitude: u32,
+    offset: u32,
+    max_code: u32,
+}
+
+impl Waveform {
+    /// Creates a waveform of `frequency_hz`, with no amplitude and no offset yet.
+    ///
+    /// The frequency is kept to the nearest millihertz. Codes are clamped to a 16 bit DAC's range
+    /// until [`Waveform::with_max_code`] says otherwise.
+    pub fn new(shape: Shape, frequency_hz: f32) -> Self {
+        assert!(frequency_hz >= 0.0, "waveform frequency must not be negative");
+        Self {
+            shape,
+            frequency_mhz: (frequency_hz * 1000.0 + 0.5) as u64,
+            amplitude: 0,
+            offset: 0,
+            max_code: u16::MAX as u32,
+        }
+    }
+
+    /// Sets how many codes the output swings either side of the offset.
+    pub fn with_amplitude(mut self, amplitude: u32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Sets the code the output swings around.
+    pub fn with_offset(mut self, offset: u32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the largest code produced, normally [`DAC::max_code`].
+    pub fn with_max_code(mut self, max_code: u32) -> Self {
+        self.max_code = max_code;
+        self
+    }
+
+    /// The shape being generated.
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /// Changes the frequency, keeping shape, amplitude and offset.
+    ///
+    /// The phase is computed from the timestamp, so the output jumps unless the caller restarts
+    /// its time base.
+    pub fn set_frequency(&mut self, frequency_hz: f32) {
+        assert!(frequency_hz >= 0.0, "waveform frequency must not be negative");
+        self.frequency_mhz = (frequency_hz * 1000.0 + 0.5) as u64;
+    }
+
+    /// The fraction of a period elapsed at `time_us`, scaled to the full `u32` range.
+    fn phase(&self, time_us: u64) -> u32 {
+        // cycles elapsed in 32 bit fixed point, only the fractional part is kept
+        (((time_us as u128 * self.frequency_mhz as u128) << 32) / 1_000_000_000) as u32
+    }
+
+    /// The code to output `time_us` microseconds after the start of the waveform.
+    pub fn code_at(&self, time_us: u64) -> u32 {
+        let value = self.shape.sample(self.phase(time_us));
+        let code = self.offset as i64 + self.amplitude as i64 * value / Q15_ONE;
+        code.clamp(0, self.max_code as i64) as u32
+    }
+
+    /// The code to output on the `tick`th tick of a loop running every `tick_us` microseconds.
+    pub fn code_at_tick(&self, tick: u64, tick_us: u32) -> u32 {
+        self.code_at(tick * tick_us as u64)
+    }
+
+    /// Writes the code for `time_us` to the DAC and returns the code that was written.
+    pub fn write<Bus: SpiBus>(&self, dac: &mut DAC<'_, Bus>, time_us: u64) -> Result<u32, DacError<Bus::Error>> {
+        let code = self.code_at(time_us).min(dac.max_code());
+        dac.write(code)?;
+        Ok(code)
+    }
+}
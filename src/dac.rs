@@ -1,58 +1,719 @@
-use embedded_hal::spi::SpiBus;
+use defmt::Format;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::{SpiBus, SpiDevice};
 use esp_hal::Blocking;
-use esp_hal::dma::DmaChannelFor;
-use esp_hal::gpio::{NoPin, Output, OutputConfig, OutputPin};
-use esp_hal::spi::{AnySpi, BitOrder};
+use esp_hal::delay::Delay;
+use esp_hal::dma::{DmaBufError, DmaChannelFor};
+use esp_hal::gpio::{InputPin, NoPin, Output, OutputConfig, OutputPin};
+use esp_hal::spi::{AnySpi, BitOrder, Mode};
 use esp_hal::spi::master::{Config, Instance, Spi, SpiDmaBus};
 use esp_hal::time::Rate;
-use crate::initialize_dma_buffers;
+use crate::device_bus::DeviceBus;
+use crate::dma_bus_buffers;
+use crate::units::{Code, Millivolts};
+
+/// Resolution assumed until [`DAC::with_resolution`] says otherwise, the whole `u32` sent as 4
+/// bytes. Set the real resolution for the range check and the frame size to match the part.
+const DEFAULT_RESOLUTION_BITS: u8 = 32;
+
+/// DMA buffer sizes of the bus created by [`DAC::new_with_peripherals`]. Frames are at most 4
+/// bytes, which is also all [`DAC::read_back`] ever receives.
+const DMA_RX_BUFFER_SIZE: usize = 4;
+const DMA_TX_BUFFER_SIZE: usize = 32;
+
+/// Errors returned by the [`DAC`] driver.
+#[derive(Debug, Format)]
+pub enum DacError<E> {
+    /// The underlying SPI bus failed.
+    Spi(E),
+    /// The code doesn't fit in the DAC's resolution.
+    OutOfRange,
+    /// The DAC is powered down, see [`DAC::enable`].
+    PoweredDown,
+    /// Nothing can be read back, either because readback isn't enabled, see
+    /// [`DAC::with_readback`], or because nothing has been written yet.
+    ReadbackUnavailable,
+}
+
+impl<E> From<E> for DacError<E> {
+    fn from(error: E) -> Self {
+        DacError::Spi(error)
+    }
+}
+
+/// Converts an output voltage into the nearest code of a DAC with the given resolution.
+///
+/// Assumes the usual `Vout = code / 2^bits * Vref` transfer function. Voltages outside the
+/// output range are clamped to 0 and the full-scale code.
+pub fn voltage_to_code(volts: f32, reference_mv: f32, resolution_bits: u8) -> u32 {
+    let max_code = u32::MAX >> (32 - resolution_bits);
+    let code = volts * 1000.0 / reference_mv * (max_code as f32 + 1.0);
+    if code <= 0.0 {
+        return 0;
+    }
+
+    // float to int casts saturate, so this only needs clamping to the DAC's own range
+    ((code + 0.5) as u32).min(max_code)
+}
+
+/// Ideal output voltage of `code` on a DAC with the given resolution, the inverse of
+/// [`voltage_to_code`].
+pub fn code_to_voltage(code: u32, reference_mv: f32, resolution_bits: u8) -> f32 {
+    code as f32 / (1u64 << resolution_bits) as f32 * reference_mv / 1000.0
+}
+
+/// [`voltage_to_code`] taking and returning typed units.
+pub fn millivolts_to_code(voltage: Millivolts, reference: Millivolts, resolution_bits: u8) -> Code {
+    Code(voltage_to_code(voltage.to_volts(), reference.0, resolution_bits))
+}
+
+/// [`code_to_voltage`] taking and returning typed units.
+pub fn code_to_millivolts(code: Code, reference: Millivolts, resolution_bits: u8) -> Millivolts {
+    Millivolts::from_volts(code_to_voltage(code.0, reference.0, resolution_bits))
+}
+
+/// Measured gain and offset error of the DAC output, `measured = gain * ideal + offset` in volts.
+///
+/// [`DAC::write_voltage`] inverts it, so the commanded voltage is what actually comes out.
+#[derive(Format, Debug, Clone, Copy, PartialEq)]
+pub struct DacCalibration {
+    pub gain: f32,
+    pub offset: f32,
+}
+
+impl DacCalibration {
+    /// No correction.
+    pub const IDENTITY: Self = Self { gain: 1.0, offset: 0.0 };
+
+    /// Solves the line through two `(code, measured volts)` points.
+    ///
+    /// The codes are turned into their ideal voltages with `reference_mv` and `resolution_bits`.
+    /// Pick codes far apart, near either end of the range, to keep measurement noise from
+    /// skewing the gain.
+    pub fn from_two_points(a: (u32, f32), b: (u32, f32), reference_mv: f32, resolution_bits: u8) -> Self {
+        assert!(a.0 != b.0, "calibration points need different codes");
+
+        let ideal_a = code_to_voltage(a.0, reference_mv, resolution_bits);
+        let ideal_b = code_to_voltage(b.0, reference_mv, resolution_bits);
+        let gain = (b.1 - a.1) / (ideal_b - ideal_a);
+
+        Self {
+            gain,
+            offset: a.1 - gain * ideal_a,
+        }
+    }
+
+    /// The ideal voltage to command for `volts` to come out.
+    pub fn correct(&self, volts: f32) -> f32 {
+        (volts - self.offset) / self.gain
+    }
+}
+
+impl Default for DacCalibration {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Lays out `value` left justified in the fewest whole bytes that fit `resolution_bits`,
+/// returning the bytes and how many of the trailing ones make up the frame.
+fn frame(value: u32, resolution_bits: u8) -> ([u8; 4], usize) {
+    let len = (resolution_bits as usize).div_ceil(8);
+    ((value << (len * 8 - resolution_bits as usize)).to_be_bytes(), len)
+}
+
+/// Recovers the value from the `len` bytes of a frame laid out by [`frame`].
+fn unframe(bytes: &[u8], resolution_bits: u8) -> u32 {
+    let word = bytes.iter().fold(0u32, |word, &byte| word << 8 | byte as u32);
+    word >> (bytes.len() * 8 - resolution_bits as usize)
+}
+
+/// What [`DAC::power_down`] sends to put the output in a safe state.
+///
+/// Power-down commands differ per part, e.g. the TI DAC8551 takes a 24 bit word with the mode in
+/// bits 17:16, so `Command { word: 0x01_0000, len: 3 }` pulls the output to ground through 1 kΩ.
+/// Parts without a power-down mode, such as the AD5541, can only be parked at a code.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerDownMode {
+    /// Write this code, 0 by default.
+    Code(u32),
+    /// Send the last `len` bytes of `word`, MSB first.
+    Command { word: u32, len: u8 },
+}
+
+impl Default for PowerDownMode {
+    fn default() -> Self {
+        PowerDownMode::Code(0)
+    }
+}
+
+impl PowerDownMode {
+    /// The frame to send for a DAC with the given resolution, as in [`frame`].
+    fn frame(&self, resolution_bits: u8) -> ([u8; 4], usize) {
+        match *self {
+            PowerDownMode::Code(code) => frame(code, resolution_bits),
+            PowerDownMode::Command { word, len } => (word.to_be_bytes(), len as usize),
+        }
+    }
+}
+
+/// SPI clock rate and mode of the bus created by [`DAC::new_with_config`]. Transfers are always
+/// MSB first.
+///
+/// The mode depends on the part. Parts that latch data on the rising SCLK edge, such as the
+/// AD5541/AD5542 family, use mode 0, which is the default. Parts that latch on the falling
+/// edge, such as the TI DAC855x family, need mode 1. Check the maximum SCLK in the part's
+/// datasheet before raising the frequency, e.g. 50 MHz for the AD5542.
+#[derive(Debug, Clone, Copy)]
+pub struct DacSpiConfig {
+    pub frequency: Rate,
+    pub mode: Mode,
+}
+
+impl DacSpiConfig {
+    /// Sets the SCLK rate.
+    pub fn with_frequency(mut self, frequency: Rate) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the SPI mode.
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl Default for DacSpiConfig {
+    fn default() -> Self {
+        Self {
+            frequency: Rate::from_khz(500),
+            mode: Mode::_0,
+        }
+    }
+}
+
+/// Configuration and power state shared by [`DAC`] and [`AsyncDAC`], everything but the bus.
+#[derive(Debug)]
+struct DacCore {
+    resolution_bits: u8,
+    ldac_pulse_ns: u32,
+    calibration: DacCalibration,
+    power_down_mode: PowerDownMode,
+    enabled: bool,
+}
+
+impl DacCore {
+    fn new() -> Self {
+        Self {
+            resolution_bits: DEFAULT_RESOLUTION_BITS,
+            ldac_pulse_ns: 0,
+            calibration: DacCalibration::IDENTITY,
+            power_down_mode: PowerDownMode::default(),
+            enabled: true,
+        }
+    }
+
+    fn set_resolution(&mut self, bits: u8) {
+        assert!(bits > 0 && bits <= 32, "DAC resolution must be between 1 and 32 bits");
+        self.resolution_bits = bits;
+        self.assert_power_down_code_fits();
+    }
+
+    fn set_power_down_mode(&mut self, power_down_mode: PowerDownMode) {
+        assert!(matches!(power_down_mode, PowerDownMode::Code(_) | PowerDownMode::Command { len: 1..=4, .. }), "DAC power-down command must be 1 to 4 bytes");
+        self.power_down_mode = power_down_mode;
+        self.assert_power_down_code_fits();
+    }
+
+    /// [`frame`] would silently drop the bits of a code that don't fit.
+    fn assert_power_down_code_fits(&self) {
+        if let PowerDownMode::Code(code) = self.power_down_mode {
+            assert!(code <= self.max_code(), "DAC power-down code must fit the resolution");
+        }
+    }
+
+    fn max_code(&self) -> u32 {
+        u32::MAX >> (32 - self.resolution_bits)
+    }
+
+    fn check_writable<E>(&self, value: u32) -> Result<(), DacError<E>> {
+        if !self.enabled {
+            return Err(DacError::PoweredDown);
+        }
+        if value > self.max_code() {
+            return Err(DacError::OutOfRange);
+        }
+        Ok(())
+    }
+
+    /// The frame writing `value`, as in [`frame`], once [`DacCore::check_writable`] passes.
+    fn write_frame<E>(&self, value: u32) -> Result<([u8; 4], usize), DacError<E>> {
+        self.check_writable(value)?;
+        Ok(frame(value, self.resolution_bits))
+    }
+
+    fn power_down_frame(&self) -> ([u8; 4], usize) {
+        self.power_down_mode.frame(self.resolution_bits)
+    }
+
+    fn voltage_code(&self, volts: f32, reference_mv: f32) -> u32 {
+        voltage_to_code(self.calibration.correct(volts), reference_mv, self.resolution_bits)
+    }
+}
 
 #[derive(Debug)]
 pub struct DAC<'d, Bus: SpiBus> {
     spi: Bus,
-    ldac_pin: Output<'d>,
+    ldac_pin: Option<Output<'d>>,
+    core: DacCore,
+    readback: bool,
+    /// Bytes and length of the last frame sent, for [`DAC::read_back`] to send again.
+    last_frame: Option<([u8; 4], usize)>,
 }
 
 impl <'d> DAC<'d, SpiDmaBus<'d, Blocking>> {
     
     pub fn get_spi_config() -> Config {
+        Self::get_spi_config_with(DacSpiConfig::default())
+    }
+
+    /// SPI configuration with the rate and mode of `spi_config`, MSB first.
+    pub fn get_spi_config_with(spi_config: DacSpiConfig) -> Config {
         Config::default()
-            .with_frequency(Rate::from_khz(500))
-            .with_mode(esp_hal::spi::Mode::_0)
+            .with_frequency(spi_config.frequency)
+            .with_mode(spi_config.mode)
             .with_read_bit_order(BitOrder::MsbFirst)
             .with_write_bit_order(BitOrder::MsbFirst)
     }
-    
-    pub fn new_with_peripherals<SpiInstance: Instance + 'static, CS: OutputPin + 'static, SCK: OutputPin + 'static, MOSI: OutputPin + 'static, LDAC: OutputPin + 'static, DmaChannel: DmaChannelFor<AnySpi<'d>>>(spi: SpiInstance, cs: CS, sck: SCK, mosi: MOSI, ldac: LDAC, dma_channel: DmaChannel) -> Self {
-        let (dma_rx_buf, dma_tx_buf) = initialize_dma_buffers();
 
-        let dac_spi = Spi::new(spi, Self::get_spi_config()).unwrap()
+    /// Wires up a DMA backed SPI bus on the given peripherals and wraps it in a [`DAC`], with
+    /// LDAC on `ldac`.
+    ///
+    /// Fails if the DMA buffers of the bus can't be set up.
+    pub fn new_with_peripherals<SpiInstance: Instance + 'static, CS: OutputPin + 'static, SCK: OutputPin + 'static, MOSI: OutputPin + 'static, LDAC: OutputPin + 'static, DmaChannel: DmaChannelFor<AnySpi<'d>>>(spi: SpiInstance, cs: CS, sck: SCK, mosi: MOSI, ldac: LDAC, dma_channel: DmaChannel) -> Result<Self, DmaBufError> {
+        Self::new_with_config(spi, cs, sck, mosi, ldac, dma_channel, DacSpiConfig::default())
+    }
+
+    /// Like [`DAC::new_with_peripherals`], with the SCLK rate and SPI mode taken from
+    /// `spi_config` for parts that differ from the default 500 kHz in mode 0.
+    ///
+    /// ```no_run
+    /// # use dc_load_control_loop_rs::dac::{DacSpiConfig, DAC};
+    /// # use esp_hal::spi::Mode;
+    /// # use esp_hal::time::Rate;
+    /// let peripherals = esp_hal::init(esp_hal::Config::default());
+    /// let dac = DAC::new_with_config(
+    ///     peripherals.SPI3,
+    ///     peripherals.GPIO18, // CS
+    ///     peripherals.GPIO17, // SCK
+    ///     peripherals.GPIO10, // MOSI
+    ///     peripherals.GPIO9,  // LDAC
+    ///     peripherals.DMA_CH1,
+    ///     DacSpiConfig::default().with_frequency(Rate::from_mhz(40)).with_mode(Mode::_1),
+    /// ).expect("DAC DMA init failed");
+    /// ```
+    pub fn new_with_config<SpiInstance: Instance + 'static, CS: OutputPin + 'static, SCK: OutputPin + 'static, MOSI: OutputPin + 'static, LDAC: OutputPin + 'static, DmaChannel: DmaChannelFor<AnySpi<'d>>>(spi: SpiInstance, cs: CS, sck: SCK, mosi: MOSI, ldac: LDAC, dma_channel: DmaChannel, spi_config: DacSpiConfig) -> Result<Self, DmaBufError> {
+        let dac_spi = Self::spi_with_pins(spi, cs, sck, mosi, spi_config)
+            .with_miso(NoPin); // no need for MISO in DAC
+
+        Self::new_on_spi(dac_spi, ldac, dma_channel) // LDAC on D6
+    }
+
+    /// Like [`DAC::new_with_config`], with MISO wired to the DAC's SDO and readback enabled,
+    /// for parts that shift their input register out on SDO, see [`DAC::read_back`].
+    #[allow(clippy::too_many_arguments, reason = "one argument per pin, like the other constructors")]
+    pub fn new_with_readback<SpiInstance: Instance + 'static, CS: OutputPin + 'static, SCK: OutputPin + 'static, MOSI: OutputPin + 'static, MISO: InputPin + 'static, LDAC: OutputPin + 'static, DmaChannel: DmaChannelFor<AnySpi<'d>>>(spi: SpiInstance, cs: CS, sck: SCK, mosi: MOSI, miso: MISO, ldac: LDAC, dma_channel: DmaChannel, spi_config: DacSpiConfig) -> Result<Self, DmaBufError> {
+        let dac_spi = Self::spi_with_pins(spi, cs, sck, mosi, spi_config)
+            .with_miso(miso);
+
+        Ok(Self::new_on_spi(dac_spi, ldac, dma_channel)?.with_readback())
+    }
+
+    /// The SPI peripheral configured with `spi_config` and the output pins attached, MISO is up
+    /// to the constructor.
+    fn spi_with_pins<SpiInstance: Instance + 'static, CS: OutputPin + 'static, SCK: OutputPin + 'static, MOSI: OutputPin + 'static>(spi: SpiInstance, cs: CS, sck: SCK, mosi: MOSI, spi_config: DacSpiConfig) -> Spi<'d, Blocking> {
+        Spi::new(spi, Self::get_spi_config_with(spi_config)).unwrap()
             .with_cs(cs)
             .with_sck(sck)
             .with_mosi(mosi)
-            .with_miso(NoPin) // no need for MISO in DAC
+    }
+
+    /// Moves `spi` onto DMA and wraps it in a [`DAC`] with LDAC on `ldac`.
+    fn new_on_spi<LDAC: OutputPin + 'static, DmaChannel: DmaChannelFor<AnySpi<'d>>>(spi: Spi<'d, Blocking>, ldac: LDAC, dma_channel: DmaChannel) -> Result<Self, DmaBufError> {
+        let (dma_rx_buf, dma_tx_buf) = dma_bus_buffers!(DMA_RX_BUFFER_SIZE, DMA_TX_BUFFER_SIZE)?;
+
+        let dac_spi = spi
             .with_dma(dma_channel)
             .with_buffers(dma_rx_buf, dma_tx_buf);
 
-        let ldac_pin = Output::new(ldac, esp_hal::gpio::Level::High, OutputConfig::default()); // D6
+        let ldac_pin = Output::new(ldac, esp_hal::gpio::Level::High, OutputConfig::default());
+
+        Ok(Self::new(dac_spi, ldac_pin))
+    }
+}
 
-        Self::new(dac_spi, ldac_pin)
+impl<'d, Device: SpiDevice> DAC<'d, DeviceBus<Device>> {
+    /// Creates the driver on a shared bus, with chip select handled per transaction by `device`.
+    pub fn new_with_device(device: Device, ldac_pin: Output<'d>) -> Self {
+        Self::new(DeviceBus::new(device), ldac_pin)
     }
 }
 
 impl<'d, Bus: SpiBus> DAC<'d, Bus> {
     pub fn new(spi: Bus, ldac_pin: Output<'d>) -> Self {
+        DAC {
+            ldac_pin: Some(ldac_pin),
+            ..Self::new_without_ldac(spi)
+        }
+    }
+
+    /// Creates the driver for a DAC whose LDAC line isn't its own, e.g. one shared through a
+    /// [`DacGroup`] or tied low. [`DAC::strobe_ldac`] does nothing.
+    pub fn new_without_ldac(spi: Bus) -> Self {
         DAC {
             spi,
+            ldac_pin: None,
+            core: DacCore::new(),
+            readback: false,
+            last_frame: None,
+        }
+    }
+
+    /// Sets the resolution of the DAC, which decides the frame size and the valid code range.
+    ///
+    /// Codes are sent MSB first in the fewest whole bytes that fit, left justified, so a
+    /// 16 bit DAC gets 2 bytes and a 20 bit DAC gets 3 bytes with 4 trailing zero bits.
+    pub fn with_resolution(mut self, bits: u8) -> Self {
+        self.core.set_resolution(bits);
+        self
+    }
+
+    /// Sets the minimum time LDAC is held low when strobing it.
+    ///
+    /// Defaults to 0, which relies on the GPIO toggle alone being slower than the DAC's minimum
+    /// LDAC pulse width. Check the datasheet of the part in use when running the CPU fast.
+    pub fn with_ldac_pulse_width(mut self, ns: u32) -> Self {
+        self.core.ldac_pulse_ns = ns;
+        self
+    }
+
+    /// Corrects [`DAC::write_voltage`] for the measured gain and offset error of the output.
+    pub fn with_calibration(mut self, calibration: DacCalibration) -> Self {
+        self.core.calibration = calibration;
+        self
+    }
+
+    /// Sets what [`DAC::power_down`] sends, code 0 by default.
+    ///
+    /// Panics if a [`PowerDownMode::Code`] doesn't fit the resolution, so set that first with
+    /// [`DAC::with_resolution`], which panics in turn if it leaves the code out of range.
+    pub fn with_power_down_mode(mut self, power_down_mode: PowerDownMode) -> Self {
+        self.core.set_power_down_mode(power_down_mode);
+        self
+    }
+
+    /// Enables [`DAC::read_back`], for a part that shifts its input register out on SDO with
+    /// SDO wired to the bus.
+    pub fn with_readback(mut self) -> Self {
+        self.readback = true;
+        self
+    }
+
+    /// Largest code the DAC accepts.
+    pub fn max_code(&self) -> u32 {
+        self.core.max_code()
+    }
+
+    /// The underlying bus, e.g. for inspecting a mock in tests.
+    pub fn spi_mut(&mut self) -> &mut Bus {
+        &mut self.spi
+    }
+
+    /// Loads `value` into the DAC and updates the output straight away.
+    pub fn write(&mut self, value: u32) -> Result<(), DacError<Bus::Error>> {
+        self.write_without_strobe(value)?;
+        self.strobe_ldac();
+        Ok(())
+    }
+
+    /// Loads `value` into the DAC's input register without updating the output.
+    ///
+    /// The output changes on the next [`DAC::strobe_ldac`], which lets several DACs sharing an
+    /// LDAC line be loaded first and updated together.
+    pub fn write_without_strobe(&mut self, value: u32) -> Result<(), DacError<Bus::Error>> {
+        self.load(value)?;
+        self.spi.flush()?;
+
+        Ok(())
+    }
+
+    /// Hands the frame for `value` to the bus without waiting for it to be clocked out.
+    fn load(&mut self, value: u32) -> Result<(), DacError<Bus::Error>> {
+        let (frame, len) = self.core.write_frame(value)?;
+        self.spi.write(&frame[frame.len() - len..])?;
+        self.last_frame = Some((frame, len));
+
+        Ok(())
+    }
+
+    /// Reads back what the DAC holds in its input register, to confirm the last write landed.
+    ///
+    /// SDO only shifts the register out while a new frame is shifted in, so the last frame is
+    /// sent again, which leaves the input register as it was. LDAC isn't strobed, the output
+    /// doesn't change. After [`DAC::power_down`] with a [`PowerDownMode::Command`] the command
+    /// word comes back instead of a code.
+    ///
+    /// Fails with [`DacError::ReadbackUnavailable`] unless readback is enabled, see
+    /// [`DAC::with_readback`], and something has been written.
+    pub fn read_back(&mut self) -> Result<u32, DacError<Bus::Error>> {
+        let Some((frame, len)) = self.last_frame.filter(|_| self.readback) else {
+            return Err(DacError::ReadbackUnavailable);
+        };
+
+        let mut echo = [0; 4];
+        self.spi.transfer(&mut echo[..len], &frame[frame.len() - len..])?;
+
+        Ok(unframe(&echo[..len], self.core.resolution_bits))
+    }
+
+    /// Pulses LDAC low for at least the configured pulse width, transferring the input
+    /// register to the output.
+    pub fn strobe_ldac(&mut self) {
+        if let Some(ldac_pin) = &mut self.ldac_pin {
+            pulse_low(ldac_pin, self.core.ldac_pulse_ns);
+        }
+    }
+
+    /// Sends the configured [`PowerDownMode`] and updates the output, e.g. when the load is
+    /// switched off.
+    ///
+    /// Writes fail with [`DacError::PoweredDown`] until [`DAC::enable`] is called, so a stale
+    /// setpoint can't slip through.
+    pub fn power_down(&mut self) -> Result<(), DacError<Bus::Error>> {
+        let (frame, len) = self.core.power_down_frame();
+        self.spi.write(&frame[frame.len() - len..])?;
+        self.spi.flush()?;
+        self.last_frame = Some((frame, len));
+        self.strobe_ldac();
+        self.core.enabled = false;
+
+        Ok(())
+    }
+
+    /// Accepts writes again after [`DAC::power_down`]. The output stays in its safe state until
+    /// the next write, which also takes parts with a power-down command out of it.
+    pub fn enable(&mut self) {
+        self.core.enabled = true;
+    }
+
+    /// Whether writes are accepted, i.e. the DAC isn't powered down.
+    pub fn is_enabled(&self) -> bool {
+        self.core.enabled
+    }
+
+    /// The code [`DAC::write_voltage`] writes for `volts`.
+    pub fn voltage_code(&self, volts: f32, reference_mv: f32) -> u32 {
+        self.core.voltage_code(volts, reference_mv)
+    }
+
+    /// Sets the output to the code nearest to `volts` and returns the code that was written.
+    ///
+    /// The voltage is corrected with the [`DacCalibration`] first. The code is rounded to
+    /// nearest and clamped to the DAC's range, so the returned code is the actual setpoint for
+    /// closed-loop callers.
+    pub fn write_voltage(&mut self, volts: f32, reference_mv: f32) -> Result<u32, DacError<Bus::Error>> {
+        let code = self.voltage_code(volts, reference_mv);
+        self.write(code)?;
+        Ok(code)
+    }
+}
+
+/// DACs updated together by a single LDAC line they all share.
+///
+/// Each write loads every DAC's input register first and then pulses LDAC once, so all the
+/// outputs change at the same moment, e.g. for load stages running in parallel. Build the
+/// members with [`DAC::new_without_ldac`], the group drives the line.
+#[derive(Debug)]
+pub struct DacGroup<'d, Bus: SpiBus, const N: usize> {
+    dacs: [DAC<'d, Bus>; N],
+    ldac_pin: Output<'d>,
+    ldac_pulse_ns: u32,
+}
+
+impl<'d, Bus: SpiBus, const N: usize> DacGroup<'d, Bus, N> {
+    pub fn new(dacs: [DAC<'d, Bus>; N], ldac_pin: Output<'d>) -> Self {
+        Self {
+            dacs,
             ldac_pin,
+            ldac_pulse_ns: 0,
         }
     }
 
-    pub fn write(&mut self, value: u32) -> Result<(), Bus::Error> {
-        self.spi.write(&value.to_be_bytes())?;
-        self.ldac_pin.set_low();
-        self.ldac_pin.set_high();
+    /// See [`DAC::with_ldac_pulse_width`].
+    pub fn with_ldac_pulse_width(mut self, ns: u32) -> Self {
+        self.ldac_pulse_ns = ns;
+        self
+    }
+
+    /// The DACs in the group, in the order their values are given.
+    pub fn dacs_mut(&mut self) -> &mut [DAC<'d, Bus>; N] {
+        &mut self.dacs
+    }
+
+    /// Loads `values[i]` into DAC `i` and then updates all outputs together.
+    ///
+    /// Every value is checked before anything is sent, so an out of range value or a powered
+    /// down DAC leaves all DACs as they were. Every bus is flushed before the strobe, so no
+    /// frame is still being clocked out when LDAC falls. A bus error part way through skips
+    /// the strobe, leaving the outputs unchanged until the next successful write.
+    pub fn write(&mut self, values: [u32; N]) -> Result<(), DacError<Bus::Error>> {
+        for (dac, value) in self.dacs.iter().zip(values) {
+            dac.core.check_writable(value)?;
+        }
+
+        for (dac, value) in self.dacs.iter_mut().zip(values) {
+            dac.load(value)?;
+        }
+        for dac in &mut self.dacs {
+            dac.spi.flush()?;
+        }
+        self.strobe_ldac();
+
         Ok(())
     }
+
+    /// Like [`DacGroup::write`] with each voltage converted as in [`DAC::write_voltage`],
+    /// returning the codes that were written.
+    pub fn write_voltages(&mut self, volts: [f32; N], reference_mv: f32) -> Result<[u32; N], DacError<Bus::Error>> {
+        let mut codes = [0; N];
+        for ((code, dac), volts) in codes.iter_mut().zip(&self.dacs).zip(volts) {
+            *code = dac.voltage_code(volts, reference_mv);
+        }
+
+        self.write(codes)?;
+        Ok(codes)
+    }
+
+    /// Pulses the shared LDAC line, see [`DAC::strobe_ldac`].
+    pub fn strobe_ldac(&mut self) {
+        pulse_low(&mut self.ldac_pin, self.ldac_pulse_ns);
+    }
+}
+
+/// Holds `pin` low for at least `ns` nanoseconds.
+fn pulse_low(pin: &mut Output<'_>, ns: u32) {
+    pin.set_low();
+    if ns > 0 {
+        Delay::new().delay_ns(ns);
+    }
+    pin.set_high();
 }
 
+/// Async counterpart of [`DAC`], awaiting the SPI transfer instead of blocking.
+///
+/// Only the transfer is async, strobing LDAC is a short GPIO toggle and stays synchronous.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncDAC<'d, Bus: embedded_hal_async::spi::SpiBus> {
+    spi: Bus,
+    ldac_pin: Output<'d>,
+    core: DacCore,
+}
+
+#[cfg(feature = "async")]
+impl<'d, Device: embedded_hal_async::spi::SpiDevice> AsyncDAC<'d, DeviceBus<Device>> {
+    /// Creates the driver on a shared bus, with chip select handled per transaction by `device`.
+    pub fn new_with_device(device: Device, ldac_pin: Output<'d>) -> Self {
+        Self::new(DeviceBus::new(device), ldac_pin)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'d, Bus: embedded_hal_async::spi::SpiBus> AsyncDAC<'d, Bus> {
+    pub fn new(spi: Bus, ldac_pin: Output<'d>) -> Self {
+        AsyncDAC {
+            spi,
+            ldac_pin,
+            core: DacCore::new(),
+        }
+    }
+
+    /// See [`DAC::with_resolution`].
+    pub fn with_resolution(mut self, bits: u8) -> Self {
+        self.core.set_resolution(bits);
+        self
+    }
+
+    /// See [`DAC::with_ldac_pulse_width`].
+    pub fn with_ldac_pulse_width(mut self, ns: u32) -> Self {
+        self.core.ldac_pulse_ns = ns;
+        self
+    }
+
+    /// See [`DAC::with_calibration`].
+    pub fn with_calibration(mut self, calibration: DacCalibration) -> Self {
+        self.core.calibration = calibration;
+        self
+    }
+
+    /// See [`DAC::with_power_down_mode`].
+    pub fn with_power_down_mode(mut self, power_down_mode: PowerDownMode) -> Self {
+        self.core.set_power_down_mode(power_down_mode);
+        self
+    }
+
+    /// Largest code the DAC accepts.
+    pub fn max_code(&self) -> u32 {
+        self.core.max_code()
+    }
+
+    /// Loads `value` into the DAC and updates the output once the transfer has completed.
+    pub async fn write(&mut self, value: u32) -> Result<(), DacError<Bus::Error>> {
+        self.write_without_strobe(value).await?;
+        self.strobe_ldac();
+        Ok(())
+    }
+
+    /// See [`DAC::write_without_strobe`].
+    pub async fn write_without_strobe(&mut self, value: u32) -> Result<(), DacError<Bus::Error>> {
+        let (frame, len) = self.core.write_frame(value)?;
+        self.spi.write(&frame[frame.len() - len..]).await?;
+        self.spi.flush().await?;
+
+        Ok(())
+    }
+
+    /// See [`DAC::strobe_ldac`].
+    pub fn strobe_ldac(&mut self) {
+        pulse_low(&mut self.ldac_pin, self.core.ldac_pulse_ns);
+    }
+
+    /// See [`DAC::power_down`].
+    pub async fn power_down(&mut self) -> Result<(), DacError<Bus::Error>> {
+        let (frame, len) = self.core.power_down_frame();
+        self.spi.write(&frame[frame.len() - len..]).await?;
+        self.spi.flush().await?;
+        self.strobe_ldac();
+        self.core.enabled = false;
+
+        Ok(())
+    }
+
+    /// See [`DAC::enable`].
+    pub fn enable(&mut self) {
+        self.core.enabled = true;
+    }
+
+    /// See [`DAC::is_enabled`].
+    pub fn is_enabled(&self) -> bool {
+        self.core.enabled
+    }
+
+    /// See [`DAC::write_voltage`].
+    pub async fn write_voltage(&mut self, volts: f32, reference_mv: f32) -> Result<u32, DacError<Bus::Error>> {
+        let code = self.core.voltage_code(volts, reference_mv);
+        self.write(code).await?;
+        Ok(code)
+    }
+}
@@ -0,0 +1,72 @@
+use embedded_hal::spi::{ErrorType, SpiBus, SpiDevice};
+
+/// Presents an [`SpiDevice`] as an [`SpiBus`], so the drivers can run on a shared bus.
+///
+/// Every bus call becomes its own transaction, with chip select asserted for just that call by
+/// whatever manages the shared bus. The drivers never split a frame across calls, so this keeps
+/// the framing intact while other devices use the bus in between.
+#[derive(Debug)]
+pub struct DeviceBus<D> {
+    device: D,
+}
+
+impl<D> DeviceBus<D> {
+    pub fn new(device: D) -> Self {
+        Self { device }
+    }
+
+    /// Gives the device back.
+    pub fn release(self) -> D {
+        self.device
+    }
+}
+
+impl<D: ErrorType> ErrorType for DeviceBus<D> {
+    type Error = D::Error;
+}
+
+impl<D: SpiDevice> SpiBus for DeviceBus<D> {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.device.read(words)
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.device.write(words)
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        self.device.transfer(read, write)
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.device.transfer_in_place(words)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        // every transaction is complete by the time the device returns
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<D: embedded_hal_async::spi::SpiDevice> embedded_hal_async::spi::SpiBus for DeviceBus<D> {
+    async fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.device.read(words).await
+    }
+
+    async fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.device.write(words).await
+    }
+
+    async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        self.device.transfer(read, write).await
+    }
+
+    async fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.device.transfer_in_place(words).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+/// Average over the last `N` samples, kept in a fixed ring buffer.
+///
+/// Until `N` samples have been pushed the average is over the samples seen so far, so the
+/// output follows the input from the first sample instead of ramping up from zero.
+#[derive(Debug, Clone)]
+pub struct MovingAverage<const N: usize> {
+    samples: [i32; N],
+    next: usize,
+    len: usize,
+    sum: i64,
+}
+
+impl<const N: usize> MovingAverage<N> {
+    pub const fn new() -> Self {
+        assert!(N > 0, "moving average needs room for at least one sample");
+        Self {
+            samples: [0; N],
+            next: 0,
+            len: 0,
+            sum: 0,
+        }
+    }
+
+    /// Adds `sample`, dropping the oldest one once full, and returns the new average.
+    ///
+    /// The average is truncated towards zero.
+    pub fn push(&mut self, sample: i32) -> i32 {
+        if self.len == N {
+            self.sum -= self.samples[self.next] as i64;
+        } else {
+            self.len += 1;
+        }
+
+        self.samples[self.next] = sample;
+        self.sum += sample as i64;
+        self.next = (self.next + 1) % N;
+
+        self.average()
+    }
+
+    /// The current average, or 0 before the first sample.
+    pub fn average(&self) -> i32 {
+        if self.len == 0 {
+            return 0;
+        }
+        (self.sum / self.len as i64) as i32
+    }
+
+    /// Forgets every sample, starting the warm-up over.
+    pub fn reset(&mut self) {
+        self.next = 0;
+        self.len = 0;
+        self.sum = 0;
+    }
+}
+
+impl<const N: usize> Default for MovingAverage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Box average over blocks of `N` samples, producing one output per block.
+///
+/// Unlike [`MovingAverage`] the blocks don't overlap, so the output rate is the input rate
+/// divided by `N`, e.g. to run the control loop or telemetry slower than the ADC.
+#[derive(Debug, Clone)]
+pub struct Decimator<const N: usize> {
+    count: usize,
+    sum: i64,
+}
+
+impl<const N: usize> Decimator<N> {
+    pub const fn new() -> Self {
+        assert!(N > 0, "decimator needs at least one sample per block");
+        Self { count: 0, sum: 0 }
+    }
+
+    /// Adds `sample`, returning the average of the block on every `N`th sample and `None`
+    /// otherwise.
+    ///
+    /// The average is truncated towards zero.
+    pub fn push(&mut self, sample: i32) -> Option<i32> {
+        self.sum += sample as i64;
+        self.count += 1;
+        if self.count < N {
+            return None;
+        }
+
+        let average = (self.sum / N as i64) as i32;
+        self.reset();
+        Some(average)
+    }
+
+    /// Drops the partial block, so the next output averages the `N` samples after this call.
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.sum = 0;
+    }
+}
+
+impl<const N: usize> Default for Decimator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Running statistics over a measurement window, without keeping the samples.
+///
+/// Call [`Stats::reset`] at the start of each window. Sums are kept in `f32`, so windows of
+/// millions of samples lose some precision in the mean and RMS.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    count: u32,
+    sum: f32,
+    sum_of_squares: f32,
+    min: f32,
+    max: f32,
+}
+
+impl Stats {
+    pub const fn new() -> Self {
+        Self {
+            count: 0,
+            sum: 0.0,
+            sum_of_squares: 0.0,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+        }
+    }
+
+    /// Adds `sample` to the window.
+    pub fn push(&mut self, sample: f32) {
+        self.count += 1;
+        self.sum += sample;
+        self.sum_of_squares += sample * sample;
+        self.min = self.min.min(sample);
+        self.max = self.max.max(sample);
+    }
+
+    /// Number of samples in the window.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Mean of the window, or 0 before the first sample.
+    pub fn mean(&self) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum / self.count as f32
+    }
+
+    /// Root mean square of the window, or 0 before the first sample.
+    pub fn rms(&self) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        sqrt(self.sum_of_squares / self.count as f32)
+    }
+
+    /// Smallest sample in the window, or 0 before the first sample.
+    pub fn min(&self) -> f32 {
+        if self.count == 0 { 0.0 } else { self.min }
+    }
+
+    /// Largest sample in the window, or 0 before the first sample.
+    pub fn max(&self) -> f32 {
+        if self.count == 0 { 0.0 } else { self.max }
+    }
+
+    /// Forgets every sample, starting a new window.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Square root of a non-negative `value`, as `core` has none without a maths library.
+pub(crate) fn sqrt(value: f32) -> f32 {
+    if value <= 0.0 || !value.is_finite() {
+        return value.max(0.0);
+    }
+
+    // halving the exponent gives a guess within a few percent, Newton's method does the rest
+    let mut root = f32::from_bits((value.to_bits() >> 1) + 0x1fc0_0000);
+    for _ in 0..3 {
+        root = 0.5 * (root + value / root);
+    }
+    root
+}
@@ -1,14 +1,36 @@
 #![no_std]
 
-use esp_hal::dma::{DmaRxBuf, DmaTxBuf};
-use esp_hal::dma_buffers;
+use esp_hal::dma::{DmaBufError, DmaRxBuf, DmaTxBuf};
 
 pub mod adc;
+pub mod control;
 pub mod dac;
+pub mod device_bus;
+pub mod filter;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod units;
 
-pub fn initialize_dma_buffers() -> (DmaRxBuf, DmaTxBuf) {
-    let (rx_buffer, rx_descriptors, tx_buffer, tx_descriptors) = dma_buffers!(32000);
-    let dma_rx_buf = DmaRxBuf::new(rx_descriptors, rx_buffer).unwrap();
-    let dma_tx_buf = DmaTxBuf::new(tx_descriptors, tx_buffer).unwrap();
-    (dma_rx_buf, dma_tx_buf)
+/// Creates a DMA RX/TX buffer pair of the given sizes in bytes, for an SPI bus.
+///
+/// This is a macro rather than a function as the buffers are statics: every expansion gets its
+/// own pair, whereas a function would hand the same buffers to each of its callers.
+///
+/// Evaluates to a `Result<(DmaRxBuf, DmaTxBuf), DmaBufError>`, failing if the descriptors
+/// can't cover the buffers.
+#[macro_export]
+macro_rules! dma_bus_buffers {
+    ($rx_size:expr, $tx_size:expr) => {{
+        let (rx_buffer, rx_descriptors, tx_buffer, tx_descriptors) = esp_hal::dma_buffers!($rx_size, $tx_size);
+        match esp_hal::dma::DmaRxBuf::new(rx_descriptors, rx_buffer) {
+            Ok(dma_rx_buf) => esp_hal::dma::DmaTxBuf::new(tx_descriptors, tx_buffer).map(|dma_tx_buf| (dma_rx_buf, dma_tx_buf)),
+            Err(error) => Err(error),
+        }
+    }};
+}
+
+/// Creates 32000 byte DMA buffers. Every call returns the same buffers, so this can only back
+/// a single bus; use [`dma_bus_buffers!`] to size buffers per bus.
+pub fn initialize_dma_buffers() -> Result<(DmaRxBuf, DmaTxBuf), DmaBufError> {
+    dma_bus_buffers!(32000, 32000)
 }
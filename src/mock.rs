@@ -0,0 +1,138 @@
+use core::convert::Infallible;
+use embedded_hal::spi::{ErrorType, SpiBus};
+
+/// How many bytes the mock can record and queue.
+const CAPACITY: usize = 256;
+
+/// An in-memory SPI bus for testing the drivers without hardware.
+///
+/// Records every byte written to it and answers reads from a queue of canned responses, padding
+/// with zeros once the queue runs dry. Fixed capacity keeps it usable on target as well as on
+/// the host; exceeding it panics.
+#[derive(Debug)]
+pub struct MockSpiBus {
+    written: [u8; CAPACITY],
+    written_len: usize,
+    flushed_len: usize,
+    responses: [u8; CAPACITY],
+    responses_len: usize,
+    responses_pos: usize,
+}
+
+impl MockSpiBus {
+    pub fn new() -> Self {
+        Self {
+            written: [0; CAPACITY],
+            written_len: 0,
+            flushed_len: 0,
+            responses: [0; CAPACITY],
+            responses_len: 0,
+            responses_pos: 0,
+        }
+    }
+
+    /// Queues `bytes` to be clocked in by the following reads.
+    ///
+    /// Responses line up with the whole transfer, so a register read needs a placeholder for
+    /// the byte clocked in alongside the command.
+    pub fn queue_response(&mut self, bytes: &[u8]) {
+        assert!(self.responses_len + bytes.len() <= CAPACITY, "mock SPI response queue full");
+        self.responses[self.responses_len..self.responses_len + bytes.len()].copy_from_slice(bytes);
+        self.responses_len += bytes.len();
+    }
+
+    /// Everything written to the bus so far.
+    pub fn written(&self) -> &[u8] {
+        &self.written[..self.written_len]
+    }
+
+    /// The bytes written up to the last flush, the ones a driver made sure were clocked out.
+    pub fn flushed(&self) -> &[u8] {
+        &self.written[..self.flushed_len]
+    }
+
+    /// Forgets the bytes written so far, e.g. after setting up the device under test.
+    pub fn clear_written(&mut self) {
+        self.written_len = 0;
+        self.flushed_len = 0;
+    }
+
+    fn record(&mut self, words: &[u8]) {
+        assert!(self.written_len + words.len() <= CAPACITY, "mock SPI write log full");
+        self.written[self.written_len..self.written_len + words.len()].copy_from_slice(words);
+        self.written_len += words.len();
+    }
+
+    fn respond(&mut self, words: &mut [u8]) {
+        for word in words {
+            *word = if self.responses_pos < self.responses_len {
+                self.responses_pos += 1;
+                self.responses[self.responses_pos - 1]
+            } else {
+                0
+            };
+        }
+    }
+}
+
+impl Default for MockSpiBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ErrorType for MockSpiBus {
+    type Error = Infallible;
+}
+
+impl SpiBus for MockSpiBus {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.respond(words);
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.record(words);
+        Ok(())
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        self.record(write);
+        self.respond(read);
+        Ok(())
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.record(words);
+        self.respond(words);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.flushed_len = self.written_len;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::spi::SpiBus for MockSpiBus {
+    async fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        SpiBus::read(self, words)
+    }
+
+    async fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        SpiBus::write(self, words)
+    }
+
+    async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        SpiBus::transfer(self, read, write)
+    }
+
+    async fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        SpiBus::transfer_in_place(self, words)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        SpiBus::flush(self)
+    }
+}
@@ -0,0 +1,44 @@
+use defmt::Format;
+
+/// A raw converter code, as read from the ADC or written to the DAC.
+///
+/// Together with [`Millivolts`] and [`Milliamps`] this keeps the typed conversion helpers from
+/// being handed a code where a voltage is expected, at no cost over the bare number.
+#[derive(Format, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Code(pub u32);
+
+/// A voltage in millivolts.
+#[derive(Format, Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+#[repr(transparent)]
+pub struct Millivolts(pub f32);
+
+/// A current in milliamps.
+#[derive(Format, Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+#[repr(transparent)]
+pub struct Milliamps(pub f32);
+
+impl Millivolts {
+    pub fn from_volts(volts: f32) -> Self {
+        Self(volts * 1000.0)
+    }
+
+    pub fn to_volts(self) -> f32 {
+        self.0 / 1000.0
+    }
+
+    /// Current this voltage drives through `ohms`.
+    pub fn across(self, ohms: f32) -> Milliamps {
+        Milliamps(self.0 / ohms)
+    }
+}
+
+impl Milliamps {
+    pub fn from_amps(amps: f32) -> Self {
+        Self(amps * 1000.0)
+    }
+
+    pub fn to_amps(self) -> f32 {
+        self.0 / 1000.0
+    }
+}
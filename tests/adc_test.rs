@@ -0,0 +1,393 @@
+//! Host independent tests for the ADC register layer.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use dc_load_control_loop_rs::adc::{sequence_cycle_time_us, sequence_cycle_time_us_at, Channel, ClockSource, Crc, DataRegisterLength, Delay, EnhancedFilterRate, FilterOrder, Input, InvalidInputPair, Mode, OutputCoding, OutputDataRate, ReferenceSource, Setup, SyncErrorPinMode};
+    use dc_load_control_loop_rs::adc::crc::crc8;
+    use dc_load_control_loop_rs::adc::scaling::{Correction, Scaling};
+    use dc_load_control_loop_rs::adc::register::{register_name, AdcModeRegister, Channel0Register, Channel2Register, Channel3Register, DataAndStatusRegister, DataRegister, DefaultFilterConfig0Register, DefaultFilterConfig3Register, DirectSinc3MapFilterConfig0Register, DirectSinc3MapFilterConfig3Register, Gain0Register, Gain3Register, GPIOConfigRegister, InterfaceModeRegister, Offset0Register, Offset2Register, Offset3Register, Register, Setup0Registers, Setup2Registers, Setup3Registers, SetupConfig0Register, SetupConfig2Register, SetupRegisters, SetupConfig3Register, StatusRegister, WritableRegister, INTERNAL_MODULATOR_FREQUENCY_HZ};
+    use dc_load_control_loop_rs::adc::timestamp::SampleClock;
+    use dc_load_control_loop_rs::units::{Code, Milliamps, Millivolts};
+    use embassy_time::{Duration, Instant};
+    use defmt::{assert, assert_eq};
+
+    #[init]
+    fn init() {
+        let _ = esp_hal::init(esp_hal::Config::default());
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        let diff = a - b;
+        diff < 0.001 && diff > -0.001
+    }
+
+    #[test]
+    fn bipolar_midscale_is_zero() {
+        let data = DataRegister::new().with_data(0x800000);
+        assert!(data.to_voltage(2500.0, OutputCoding::Bipolar) == 0.0);
+    }
+
+    #[test]
+    fn bipolar_full_scale() {
+        let positive = DataRegister::new().with_data(0xffffff);
+        let negative = DataRegister::new().with_data(0x000000);
+        assert!(close(positive.to_voltage(2500.0, OutputCoding::Bipolar), 2500.0 - 2500.0 / 8388608.0));
+        assert!(close(negative.to_voltage(2500.0, OutputCoding::Bipolar), -2500.0));
+    }
+
+    #[test]
+    fn bipolar_as_signed() {
+        assert_eq!(DataRegister::new().with_data(0x800000).as_signed(OutputCoding::Bipolar), 0);
+        assert_eq!(DataRegister::new().with_data(0xffffff).as_signed(OutputCoding::Bipolar), 0x7fffff);
+        assert_eq!(DataRegister::new().with_data(0x000000).as_signed(OutputCoding::Bipolar), -0x800000);
+    }
+
+    #[test]
+    fn unipolar_as_signed_is_unchanged() {
+        assert_eq!(DataRegister::new().with_data(0xffffff).as_signed(OutputCoding::Unipolar), 0xffffff);
+    }
+
+    #[test]
+    fn unipolar_scaling() {
+        let midscale = DataRegister::new().with_data(0x800000);
+        let full_scale = DataRegister::new().with_data(0xffffff);
+        assert!(close(midscale.to_voltage(2500.0, OutputCoding::Unipolar), 1250.0));
+        assert!(close(full_scale.to_voltage(2500.0, OutputCoding::Unipolar), 2500.0 - 2500.0 / 16777216.0));
+    }
+
+    #[test]
+    fn crc8_datasheet_vector() {
+        // "Example of a Polynomial CRC Calculation" from the datasheet
+        assert_eq!(crc8(&[0x65, 0x43, 0x21]), 0x86);
+    }
+
+    #[test]
+    fn crc8_of_message_and_checksum_is_zero() {
+        let checksum = crc8(&[0x42, 0x0c, 0xd0]);
+        assert_eq!(crc8(&[0x42, 0x0c, 0xd0, checksum]), 0x00);
+    }
+
+    #[test]
+    fn temperature_sensor_at_25_celsius() {
+        // 298.15 K * 477 µV/K = 142.22 mV against the 2.5 V internal reference
+        let data = DataRegister::new().with_data(0x874813);
+        let celsius = data.to_celsius(OutputCoding::Bipolar);
+        assert!(celsius > 24.99 && celsius < 25.01);
+    }
+
+    #[test]
+    fn data_and_status_framing() {
+        let frame = DataAndStatusRegister::from_buffer(&[0x12, 0x34, 0x56, 0x01]);
+        assert_eq!(frame.data(), 0x123456);
+
+        let status = StatusRegister::from_buffer(&[frame.status()]);
+        assert!(status.ready());
+        assert!(status.channel() == Channel::Ch1);
+    }
+
+    #[test]
+    fn settling_time_matches_datasheet_tables() {
+        assert_eq!(OutputDataRate::Sps250000.settling_time_us(FilterOrder::Sinc5Sinc1), 20);
+        assert_eq!(OutputDataRate::Sps10000.settling_time_us(FilterOrder::Sinc5Sinc1), 100);
+        assert_eq!(OutputDataRate::Sps5.settling_time_us(FilterOrder::Sinc5Sinc1), 200_000);
+        assert_eq!(OutputDataRate::Sps250000.settling_time_us(FilterOrder::Sinc3), 12);
+        assert_eq!(OutputDataRate::Sps1000.settling_time_us(FilterOrder::Sinc3), 3_000);
+    }
+
+    #[test]
+    fn sequence_cycle_time() {
+        assert_eq!(sequence_cycle_time_us(&[], false), 0);
+        // a single channel converts continuously at the output data rate
+        assert_eq!(sequence_cycle_time_us(&[(FilterOrder::Sinc5Sinc1, OutputDataRate::Sps10000)], false), 100);
+        assert_eq!(sequence_cycle_time_us(&[(FilterOrder::Sinc5Sinc1, OutputDataRate::Sps250000)], false), 4);
+        // switching channels at 250 kSPS settles in 20 µs per channel rather than 4 µs
+        assert_eq!(sequence_cycle_time_us(&[(FilterOrder::Sinc5Sinc1, OutputDataRate::Sps250000); 2], false), 40);
+        assert_eq!(sequence_cycle_time_us(&[(FilterOrder::Sinc5Sinc1, OutputDataRate::Sps10000); 4], false), 400);
+        assert_eq!(
+            sequence_cycle_time_us(&[(FilterOrder::Sinc5Sinc1, OutputDataRate::Sps1000), (FilterOrder::Sinc3, OutputDataRate::Sps1000)], false),
+            4_000
+        );
+    }
+
+    #[test]
+    fn single_cycle_settling_slows_single_channel() {
+        // sinc3 at 1 kSPS needs three conversions to settle
+        assert_eq!(OutputDataRate::Sps1000.sample_period_us(FilterOrder::Sinc3, false), 1_000);
+        assert_eq!(OutputDataRate::Sps1000.sample_period_us(FilterOrder::Sinc3, true), 3_000);
+        assert_eq!(OutputDataRate::Sps250000.sample_period_us(FilterOrder::Sinc5Sinc1, true), 20);
+        // sinc5 + sinc1 settles in one cycle at 10 kSPS and below
+        assert!(!OutputDataRate::Sps10000.single_cycle_has_effect(FilterOrder::Sinc5Sinc1));
+        assert!(OutputDataRate::Sps15625.single_cycle_has_effect(FilterOrder::Sinc5Sinc1));
+        assert_eq!(OutputDataRate::Sps59p92.sample_period_us(FilterOrder::Sinc5Sinc1, true), 16_689);
+
+        assert_eq!(sequence_cycle_time_us(&[(FilterOrder::Sinc3, OutputDataRate::Sps1000)], true), 3_000);
+        // a sequence settles fully on every channel either way
+        assert_eq!(sequence_cycle_time_us(&[(FilterOrder::Sinc3, OutputDataRate::Sps1000); 2], true), 6_000);
+        assert_eq!(sequence_cycle_time_us(&[(FilterOrder::Sinc3, OutputDataRate::Sps1000); 2], false), 6_000);
+    }
+
+    #[test]
+    fn nominal_sample_rates() {
+        assert!(OutputDataRate::Sps250000.sps() == 250_000.0);
+        assert!(OutputDataRate::Sps397p5.sps() == 397.5);
+        assert!(OutputDataRate::Sps5.sps() == 5.0);
+    }
+
+    #[test]
+    fn direct_sinc3_map_decimation_rate() {
+        let filter = DirectSinc3MapFilterConfig0Register::from_target_odr(1000.0, INTERNAL_MODULATOR_FREQUENCY_HZ);
+        assert_eq!(filter.decimation_rate(), 250);
+        assert!(close(filter.odr_hz(INTERNAL_MODULATOR_FREQUENCY_HZ), 1000.0));
+        // rounds to the nearest rate, 8 MHz / (32 × 300 Hz) = 833.3
+        assert_eq!(DirectSinc3MapFilterConfig0Register::from_target_odr(300.0, INTERNAL_MODULATOR_FREQUENCY_HZ).decimation_rate(), 833);
+    }
+
+    #[test]
+    fn direct_sinc3_map_decimation_rate_clamps() {
+        assert_eq!(DirectSinc3MapFilterConfig0Register::from_target_odr(0.1, INTERNAL_MODULATOR_FREQUENCY_HZ).decimation_rate(), 0x7fff);
+        assert_eq!(DirectSinc3MapFilterConfig0Register::from_target_odr(1e9, INTERNAL_MODULATOR_FREQUENCY_HZ).decimation_rate(), 1);
+        assert_eq!(DirectSinc3MapFilterConfig0Register::from_target_odr(0.0, INTERNAL_MODULATOR_FREQUENCY_HZ).decimation_rate(), 0x7fff);
+    }
+
+    #[test]
+    fn register_id_constants() {
+        assert_eq!(Channel2Register::ID, 0x12);
+        assert_eq!(Channel2Register::get_id(), Channel2Register::ID);
+        assert_eq!(DataAndStatusRegister::ID, DataRegister::ID);
+
+        let kind = match 0x3b {
+            Gain3Register::ID => "gain",
+            _ => "other",
+        };
+        assert_eq!(kind, "gain");
+    }
+
+    #[test]
+    fn register_names() {
+        assert_eq!(register_name(0x02), Some("IFMODE"));
+        assert_eq!(register_name(0x29), Some("FILTCON1"));
+        assert_eq!(register_name(0x3b), Some("GAIN3"));
+        assert_eq!(register_name(0x05), None);
+        assert_eq!(register_name(0x3f), None);
+    }
+
+    #[test]
+    fn setup_config_buffer_presets() {
+        let high = SetupConfig2Register::for_high_impedance();
+        assert!(high.ainbuf_pos_enabled() && high.ainbuf_neg_enabled());
+        assert!(high.refbuf_pos_enabled() && high.refbuf_neg_enabled());
+        assert_eq!(high.bi_unipolar(), OutputCoding::Bipolar);
+
+        let low = SetupConfig2Register::for_low_impedance();
+        assert!(!low.ainbuf_pos_enabled() && !low.ainbuf_neg_enabled());
+        assert!(!low.refbuf_pos_enabled() && !low.refbuf_neg_enabled());
+        assert_eq!(low.ref_sel(), SetupConfig2Register::new().ref_sel());
+    }
+
+    /// 2.5 V reference, ×10 amplifier and a 10 mΩ shunt, so 25 A full scale.
+    fn shunt_scaling() -> Scaling {
+        Scaling::new(2500.0, 0.01, 10.0)
+    }
+
+    #[test]
+    fn scaling_unipolar_code_to_amps() {
+        let scaling = shunt_scaling();
+
+        assert!(close(scaling.code_to_amps(0, OutputCoding::Unipolar), 0.0));
+        assert!(close(scaling.code_to_amps(0x800000, OutputCoding::Unipolar), 12.5));
+        assert!(close(scaling.code_to_amps(0xffffff, OutputCoding::Unipolar), 25.0));
+    }
+
+    #[test]
+    fn scaling_bipolar_code_to_amps() {
+        let scaling = shunt_scaling();
+
+        assert!(close(scaling.code_to_amps(0, OutputCoding::Bipolar), -25.0));
+        assert!(close(scaling.code_to_amps(0x800000, OutputCoding::Bipolar), 0.0));
+        assert!(close(scaling.code_to_amps(0xffffff, OutputCoding::Bipolar), 25.0));
+    }
+
+    #[test]
+    fn scaling_applies_correction() {
+        let scaling = shunt_scaling().with_correction(Correction { offset: 0.5, gain: 1.02 });
+
+        assert!(close(scaling.code_to_amps(0x800000, OutputCoding::Unipolar), 12.24));
+        assert!(close(scaling.code_to_amps(0x800000, OutputCoding::Bipolar), -0.51));
+    }
+
+    #[test]
+    fn typed_scaling_keeps_units_apart() {
+        let data = DataRegister::new().with_data(0xc00000);
+        assert_eq!(data.code(), Code(0xc00000));
+        assert_eq!(data.to_millivolts(Millivolts(2500.0), OutputCoding::Bipolar), Millivolts(1250.0));
+
+        // 1.25 V over a 10x amplifier and a 10 mOhm shunt
+        let current = shunt_scaling().code_to_milliamps(Code(0xc00000), OutputCoding::Bipolar);
+        assert!(close(current.to_amps(), 12.5));
+        assert_eq!(Millivolts(125.0).across(10.0), Milliamps(12.5));
+    }
+
+    #[test]
+    fn gain_register_factor_round_trips() {
+        assert_eq!(Gain0Register::from_factor(1.0).gain(), 0x400000);
+        assert!(close(Gain0Register::new().with_gain(0x555555).as_factor(), 4.0 / 3.0));
+        assert_eq!(Gain0Register::from_factor(Gain0Register::new().with_gain(0x5432a1).as_factor()).gain(), 0x5432a1);
+        assert_eq!(Gain0Register::from_factor(-1.0).gain(), 0);
+        assert_eq!(Gain0Register::from_factor(8.0).gain(), 0xffffff);
+    }
+
+    #[test]
+    fn offset_register_code_round_trips() {
+        assert_eq!(Offset2Register::new().as_code(), 0);
+        assert_eq!(Offset2Register::from_code(-16).offset(), 0x7ffff0);
+        assert_eq!(Offset2Register::from_code(-16).as_code(), -16);
+        assert_eq!(Offset2Register::from_code(i32::MAX).offset(), 0xffffff);
+        assert_eq!(Offset2Register::from_code(i32::MIN).offset(), 0);
+    }
+
+    /// Encodes `register` and decodes it again, which has to give back the same register.
+    fn round_trip<const N: usize, T: WritableRegister<N> + PartialEq + defmt::Format>(register: T) {
+        assert_eq!(T::from_buffer(&register.to_buffer()), register);
+    }
+
+    #[test]
+    fn registers_round_trip_through_their_buffers() {
+        round_trip(AdcModeRegister::new()
+            .with_ref_enable(false)
+            .with_hide_delay(true)
+            .with_sing_cyc(true)
+            .with_delay(Delay::FortyMicroseconds)
+            .with_mode(Mode::PowerDown)
+            .with_clksel(ClockSource::ExternalCrystal));
+        round_trip(InterfaceModeRegister::new()
+            .with_alt_sync(true)
+            .with_iostrength(true)
+            .with_dout_reset(true)
+            .with_cont_read(true)
+            .with_data_stat(true)
+            .with_reg_check(true)
+            .with_crc_en(Crc::EnableWithXorOnRead)
+            .with_wl16(DataRegisterLength::SixteenBits));
+        round_trip(GPIOConfigRegister::new()
+            .with_mux_io(true)
+            .with_sync_en(false)
+            .with_err_en(SyncErrorPinMode::OpenDrainErrorOutput)
+            .with_err_dat(true)
+            .with_gpio1_input_enable(true)
+            .with_gpio0_output_enable(true)
+            .with_gpio1_data(true));
+        round_trip(Channel0Register::new().with_ch_en(false).with_setup_sel(Setup::Setup2).with_ainpos(Input::Analog3).with_ainneg(Input::TemperatureSensorPos));
+        round_trip(Channel3Register::new().with_setup_sel(Setup::Setup3).with_ainpos(Input::Analog4).with_ainneg(Input::Analog1));
+        round_trip(SetupConfig0Register::new()
+            .with_bi_unipolar(OutputCoding::Unipolar)
+            .with_refbuf_pos_enabled(true)
+            .with_refbuf_neg_enabled(true)
+            .with_ainbuf_pos_enabled(false)
+            .with_burnout_en(true)
+            .with_ref_sel(ReferenceSource::Avdd1AvssDiff));
+        round_trip(SetupConfig3Register::new().with_ainbuf_neg_enabled(false).with_ref_sel(ReferenceSource::External));
+        round_trip(DefaultFilterConfig0Register::new().with_enhfilten(true).with_enhfilt(EnhancedFilterRate::Sps27).with_order(FilterOrder::Sinc3).with_odr(OutputDataRate::Sps31250));
+        round_trip(DefaultFilterConfig3Register::new().with_enhfilt(EnhancedFilterRate::Sps16p67).with_odr(OutputDataRate::Sps125000));
+        round_trip(DirectSinc3MapFilterConfig0Register::new().with_decimation_rate(0x1234));
+        round_trip(DirectSinc3MapFilterConfig3Register::new().with_decimation_rate(0x7fff));
+        round_trip(Offset3Register::new().with_offset(0x123456));
+        round_trip(Gain3Register::new().with_gain(0xabcdef));
+    }
+
+    #[test]
+    fn register_reset_values_match_datasheet() {
+        assert_eq!(AdcModeRegister::new().to_buffer(), [0x80, 0x00]);
+        assert_eq!(InterfaceModeRegister::new().to_buffer(), [0x00, 0x00]);
+        assert_eq!(GPIOConfigRegister::new().to_buffer(), [0x08, 0x00]);
+        // bipolar, input buffers on, internal reference
+        assert_eq!(SetupConfig0Register::new().to_buffer(), [0x13, 0x20]);
+        assert_eq!(DefaultFilterConfig0Register::new().to_buffer(), [0x05, 0x00]);
+        assert_eq!(Offset0Register::new().to_buffer(), [0x80, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn register_fields_land_on_datasheet_bits() {
+        assert_eq!(SetupConfig0Register::new().with_ref_sel(ReferenceSource::External).to_buffer(), [0x13, 0x00]);
+        assert_eq!(SetupConfig0Register::new().with_ref_sel(ReferenceSource::Avdd1AvssDiff).to_buffer(), [0x13, 0x30]);
+        // sinc3 at 10 SPS
+        assert_eq!(DefaultFilterConfig0Register::new().with_order(FilterOrder::Sinc3).with_odr(OutputDataRate::Sps10).to_buffer(), [0x05, 0x73]);
+        // AIN2 to AIN3 on setup 1
+        assert_eq!(Channel0Register::new().with_setup_sel(Setup::Setup1).with_ainpos(Input::Analog2).with_ainneg(Input::Analog3).to_buffer(), [0x90, 0x43]);
+        assert_eq!(InterfaceModeRegister::new().with_crc_en(Crc::Enable).with_data_stat(true).to_buffer(), [0x00, 0x48]);
+    }
+
+    #[test]
+    fn timing_scales_with_modulator_clock() {
+        // a 2% fast oscillator shortens every period by the same ratio
+        let fast = INTERNAL_MODULATOR_FREQUENCY_HZ * 1.02;
+        assert!(close(OutputDataRate::Sps1000.sps_at(fast), 1020.0));
+        assert_eq!(OutputDataRate::Sps1000.sample_period_us_at(FilterOrder::Sinc3, false, fast), 980);
+        assert_eq!(OutputDataRate::Sps1000.settling_time_us_at(FilterOrder::Sinc3, fast), 2_941);
+        assert_eq!(sequence_cycle_time_us_at(&[(FilterOrder::Sinc3, OutputDataRate::Sps1000); 2], false, fast), 5_882);
+
+        // at the nominal clock nothing changes
+        assert_eq!(OutputDataRate::Sps59p92.sample_period_us_at(FilterOrder::Sinc5Sinc1, false, INTERNAL_MODULATOR_FREQUENCY_HZ), OutputDataRate::Sps59p92.sample_period_us(FilterOrder::Sinc5Sinc1, false));
+        assert_eq!(sequence_cycle_time_us_at(&[(FilterOrder::Sinc5Sinc1, OutputDataRate::Sps10000); 4], false, INTERNAL_MODULATOR_FREQUENCY_HZ), 400);
+    }
+
+    #[test]
+    fn input_pairs_follow_datasheet() {
+        use Input::*;
+        let cases = [
+            (Analog0, Analog1, true),
+            (Analog4, Analog0, true),
+            (Analog2, Analog2, true),
+            (TemperatureSensorPos, TemperatureSensorNeg, true),
+            (Avdd1AvssDiffOver5Pos, Avdd1AvssDiffOver5Neg, true),
+            (PositiveReferenceVoltage, NegativeReferenceVoltage, true),
+            (NegativeReferenceVoltage, PositiveReferenceVoltage, true),
+            (TemperatureSensorPos, Analog1, false),
+            (Analog0, NegativeReferenceVoltage, false),
+            (TemperatureSensorPos, NegativeReferenceVoltage, false),
+            (TemperatureSensorPos, TemperatureSensorPos, false),
+        ];
+
+        for (pos, neg, valid) in cases {
+            let expected = if valid { Ok(()) } else { Err(InvalidInputPair { pos, neg }) };
+            assert_eq!(Input::check_pair(pos, neg), expected);
+        }
+    }
+
+    #[test]
+    fn setup_registers_belong_to_their_setup() {
+        fn ids<S: SetupRegisters>() -> [u8; 5] {
+            [S::SetupConfig::get_id(), S::DefaultFilterConfig::get_id(), S::DirectSinc3MapFilterConfig::get_id(), S::Offset::get_id(), S::Gain::get_id()]
+        }
+
+        assert!(Setup0Registers::SETUP == Setup::Setup0);
+        assert_eq!(ids::<Setup0Registers>(), [0x20, 0x28, 0x28, 0x30, 0x38]);
+        assert!(Setup3Registers::SETUP == Setup::Setup3);
+        assert_eq!(ids::<Setup3Registers>(), [0x23, 0x2b, 0x2b, 0x33, 0x3b]);
+        assert_eq!(Setup2Registers::setup_config(ReferenceSource::External, OutputCoding::Unipolar), SetupConfig2Register::new().with_ref_sel(ReferenceSource::External).with_bi_unipolar(OutputCoding::Unipolar));
+    }
+
+    #[test]
+    fn sample_clock_keeps_conversions_on_the_grid() {
+        let us = Instant::from_micros;
+        let mut clock = SampleClock::new(Duration::from_micros(100));
+
+        assert_eq!(clock.stamp(us(1_000)), us(1_000));
+        // seen late by varying amounts, still a period apart
+        assert_eq!(clock.stamp(us(1_130)), us(1_100));
+        assert_eq!(clock.stamp(us(1_210)), us(1_200));
+        // seen before its grid time, the grid follows
+        assert_eq!(clock.stamp(us(1_290)), us(1_290));
+        // two conversions missed, the grid restarts
+        assert_eq!(clock.stamp(us(1_600)), us(1_600));
+        assert_eq!(clock.last(), Some(us(1_600)));
+
+        clock.reset();
+        assert_eq!(clock.stamp(us(1_650)), us(1_650));
+    }
+}
@@ -0,0 +1,597 @@
+//! Host independent tests for the control loop.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use dc_load_control_loop_rs::adc::Channel;
+    use dc_load_control_loop_rs::control::autotune::{RelayStep, RelayTuner};
+    use dc_load_control_loop_rs::control::events::{Event, EventKind, EventLog};
+    use dc_load_control_loop_rs::control::hysteresis::Hysteresis;
+    use dc_load_control_loop_rs::control::pid::{ControllerStatus, Pid, Saturation};
+    use dc_load_control_loop_rs::control::power::{PowerMeter, SamplePairer};
+    use dc_load_control_loop_rs::control::profile::{Profile, Segment};
+    use dc_load_control_loop_rs::control::protection::{FaultKind, Protection};
+    use dc_load_control_loop_rs::control::slew::SlewLimiter;
+    use dc_load_control_loop_rs::control::throttle::DacThrottle;
+    use dc_load_control_loop_rs::control::waveform::{Shape, Waveform};
+    use dc_load_control_loop_rs::control::{FeedForward, LoadController, LoadMode};
+    use defmt::{assert, assert_eq};
+    use embassy_time::{Duration, Instant};
+
+    const DT_SECS: f32 = 0.001;
+
+    /// A first-order plant with a 10 ms time constant.
+    fn plant(output: f32, measured: f32) -> f32 {
+        measured + (output - measured) * DT_SECS / 0.01
+    }
+
+    const MAX_CODE: u32 = 0xffff;
+
+    /// A 12 V source with 1 Ω of internal resistance, loaded by a 10 A full scale current sink
+    /// that follows the DAC with a 10 ms lag.
+    struct Source {
+        current: f32,
+    }
+
+    impl Source {
+        fn step(&mut self, code: u32) -> (f32, f32) {
+            let commanded = code as f32 / MAX_CODE as f32 * 10.0;
+            self.current = plant(commanded, self.current);
+            (12.0 - self.current, self.current)
+        }
+    }
+
+    /// Runs the controller against a fresh [`Source`] and returns the final voltage and current.
+    fn settle(mode: LoadMode) -> (f32, f32) {
+        let mut controller = LoadController::new(Pid::new(0.0, 100_000.0, 0.0), MAX_CODE, mode);
+        let mut source = Source { current: 0.0 };
+        let (mut voltage, mut current) = source.step(0);
+        for _ in 0..3000 {
+            (voltage, current) = source.step(controller.update(voltage, current, DT_SECS).0);
+        }
+
+        (voltage, current)
+    }
+
+    #[init]
+    fn init() {
+        let _ = esp_hal::init(esp_hal::Config::default());
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn pid_settles_first_order_plant() {
+        let mut pid = Pid::new(2.0, 50.0, 0.0).with_output_limits(0.0, 10.0);
+        let mut measured = 0.0;
+        for _ in 0..2000 {
+            let (output, _) = pid.update(1.0, measured, DT_SECS);
+            measured = plant(output, measured);
+        }
+
+        assert!((measured - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn pid_clamps_output() {
+        let mut pid = Pid::new(100.0, 0.0, 0.0).with_output_limits(-1.0, 1.0);
+
+        assert!(pid.update(10.0, 0.0, DT_SECS).0 == 1.0);
+        assert!(pid.update(-10.0, 0.0, DT_SECS).0 == -1.0);
+    }
+
+    #[test]
+    fn pid_integral_does_not_wind_up() {
+        let mut pid = Pid::new(1.0, 100.0, 0.0).with_output_limits(0.0, 1.0);
+        // an unreachable setpoint keeps the output pinned at the limit for a long time
+        for _ in 0..10_000 {
+            pid.update(5.0, 0.0, DT_SECS);
+        }
+
+        // without anti-windup the integral would hold the output at the limit long after this
+        assert!(pid.update(0.0, 0.5, DT_SECS).0 < 1.0);
+    }
+
+    #[test]
+    fn pid_reset_clears_integral() {
+        let mut pid = Pid::new(0.0, 10.0, 0.0);
+        for _ in 0..100 {
+            pid.update(1.0, 0.0, DT_SECS);
+        }
+        pid.reset();
+
+        assert!(pid.update(0.0, 0.0, DT_SECS).0 == 0.0);
+    }
+
+    #[test]
+    fn pid_reports_saturation() {
+        let mut pid = Pid::new(1.0, 100.0, 0.0).with_output_limits(0.0, 1.0);
+
+        let (_, status) = pid.update(0.5, 0.0, DT_SECS);
+        assert_eq!(status, ControllerStatus { saturation: Saturation::InRange, integral_held: false });
+        assert!(!status.at_limit());
+
+        let (output, status) = pid.update(5.0, 0.0, DT_SECS);
+        assert!(output == 1.0);
+        assert_eq!(status, ControllerStatus { saturation: Saturation::High, integral_held: true });
+
+        let (output, status) = pid.update(-5.0, 0.0, DT_SECS);
+        assert!(output == 0.0);
+        assert_eq!(status.saturation, Saturation::Low);
+        assert!(status.at_limit());
+    }
+
+    #[test]
+    fn pid_holds_still_within_deadband() {
+        let mut pid = Pid::new(1.0, 10.0, 0.0);
+        pid.set_deadband(0.1);
+        for _ in 0..100 {
+            assert!(pid.update(1.0, 0.95, DT_SECS).0 == 0.0);
+            assert!(pid.update(1.0, 1.05, DT_SECS).0 == 0.0);
+        }
+
+        // nothing was integrated inside the band
+        let (output, _) = pid.update(1.0, 0.5, DT_SECS);
+        assert!((output - (0.5 + 10.0 * 0.5 * DT_SECS)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn load_reports_unreachable_setpoint() {
+        let mut controller = LoadController::new(Pid::new(0.0, 100_000.0, 0.0), MAX_CODE, LoadMode::ConstantCurrent(20.0));
+        let mut status = None;
+        for _ in 0..100 {
+            status = Some(controller.update(12.0, 1.0, DT_SECS).1);
+        }
+
+        assert_eq!(controller.output(), MAX_CODE);
+        assert_eq!(status, Some(ControllerStatus { saturation: Saturation::High, integral_held: true }));
+    }
+
+    #[test]
+    fn load_settles_constant_current() {
+        let (_, current) = settle(LoadMode::ConstantCurrent(2.0));
+        assert!((current - 2.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn load_settles_constant_voltage() {
+        let (voltage, _) = settle(LoadMode::ConstantVoltage(9.0));
+        assert!((voltage - 9.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn load_settles_constant_power() {
+        let (voltage, current) = settle(LoadMode::ConstantPower(20.0));
+        assert!((voltage * current - 20.0).abs() < 1e-1);
+    }
+
+    #[test]
+    fn load_settles_constant_resistance() {
+        let (voltage, current) = settle(LoadMode::ConstantResistance(5.0));
+        assert!((voltage / current - 5.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn load_mode_switch_keeps_output() {
+        let mut controller = LoadController::new(Pid::new(0.0, 100_000.0, 0.0), MAX_CODE, LoadMode::ConstantCurrent(2.0));
+        for _ in 0..100 {
+            controller.update(12.0, 1.0, DT_SECS);
+        }
+        let before = controller.output();
+
+        controller.set_mode(LoadMode::ConstantVoltage(11.0));
+        // already at the new setpoint, so the output should stay put
+        assert!(controller.update(11.0, 1.0, DT_SECS).0 == before);
+    }
+
+    #[test]
+    fn load_feedforward_speeds_up_step() {
+        // current after 50 ms, five time constants of the sink
+        let step = |feedforward: Option<FeedForward>| {
+            let mut controller = LoadController::new(Pid::new(0.0, 10_000.0, 0.0), MAX_CODE, LoadMode::ConstantCurrent(5.0));
+            controller.set_feedforward(feedforward);
+            let mut source = Source { current: 0.0 };
+            let (mut voltage, mut current) = source.step(0);
+            for _ in 0..50 {
+                (voltage, current) = source.step(controller.update(voltage, current, DT_SECS).0);
+            }
+            current
+        };
+
+        let with_feedforward = step(Some(FeedForward::from_two_points((0.0, 0), (10.0, MAX_CODE))));
+        assert!((with_feedforward - 5.0).abs() < 0.1);
+        assert!((with_feedforward - 5.0).abs() < (step(None) - 5.0).abs());
+    }
+
+    #[test]
+    fn load_soft_start_ramps_after_enable() {
+        let mut controller = LoadController::new(Pid::new(0.0, 1e7, 0.0), MAX_CODE, LoadMode::ConstantCurrent(20.0));
+        controller.set_soft_start(Duration::from_millis(10));
+        assert!(!controller.is_enabled());
+
+        let mut previous = 0;
+        for tick in 1..=5 {
+            let code = controller.update(12.0, 1.0, DT_SECS).0;
+            assert!(code > previous && code <= MAX_CODE * tick / 10 + 1);
+            previous = code;
+        }
+        for _ in 0..6 {
+            controller.update(12.0, 1.0, DT_SECS);
+        }
+        assert_eq!(controller.output(), MAX_CODE);
+
+        // only enabling ramps, so a disable starts it over
+        controller.disable();
+        assert_eq!(controller.output(), 0);
+        assert!(controller.update(12.0, 1.0, DT_SECS).0 <= MAX_CODE / 10 + 1);
+    }
+
+    #[test]
+    fn load_without_soft_start_steps_straight_up() {
+        let mut controller = LoadController::new(Pid::new(0.0, 1e7, 0.0), MAX_CODE, LoadMode::ConstantCurrent(20.0));
+
+        assert_eq!(controller.update(12.0, 1.0, DT_SECS).0, MAX_CODE);
+        assert!(controller.is_enabled());
+    }
+
+    /// Two 10 ms lags in series between the DAC and a 10 A full scale current, slow enough for
+    /// a relay to get a well resolved oscillation out of.
+    struct LaggedSink {
+        stage: f32,
+        current: f32,
+    }
+
+    impl LaggedSink {
+        fn step(&mut self, code: u32) -> f32 {
+            self.stage = plant(code as f32 / MAX_CODE as f32 * 10.0, self.stage);
+            self.current = plant(self.stage, self.current);
+            self.current
+        }
+    }
+
+    #[test]
+    fn relay_tuner_gains_settle_the_load() {
+        let mut tuner = RelayTuner::new(0, MAX_CODE * 4 / 10, Duration::from_millis(1)).with_hysteresis(0.02);
+        let mut sink = LaggedSink { stage: 0.0, current: 0.0 };
+        let mut current = 0.0;
+        let gains = loop {
+            match tuner.update(2.0 - current) {
+                RelayStep::Output(code) => current = sink.step(code),
+                RelayStep::Done(gains) => break gains,
+                RelayStep::TimedOut => panic!("relay didn't oscillate"),
+            }
+        };
+        assert!(gains.kp > 0.0 && gains.ki > 0.0 && gains.kd > 0.0);
+
+        let mut controller = LoadController::new(Pid::new(gains.kp, gains.ki, gains.kd), MAX_CODE, LoadMode::ConstantCurrent(2.0));
+        let mut sink = LaggedSink { stage: 0.0, current: 0.0 };
+        let mut current = 0.0;
+        for _ in 0..3000 {
+            current = sink.step(controller.update(12.0, current, DT_SECS).0);
+        }
+        assert!((current - 2.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn relay_tuner_times_out_below_setpoint() {
+        // the high code only reaches 1 A, so the error never changes sign
+        let mut tuner = RelayTuner::new(0, MAX_CODE / 10, Duration::from_millis(1)).with_max_ticks(500);
+        let mut sink = LaggedSink { stage: 0.0, current: 0.0 };
+        let mut current = 0.0;
+        for _ in 0..499 {
+            match tuner.update(2.0 - current) {
+                RelayStep::Output(code) => current = sink.step(code),
+                step => panic!("unexpected {:?}", step),
+            }
+        }
+
+        assert_eq!(tuner.update(2.0 - current), RelayStep::TimedOut);
+    }
+
+    #[test]
+    fn protection_latches_first_fault() {
+        let mut protection = Protection::new(5.0, 30.0, 80.0);
+        assert_eq!(protection.check(12.0, 2.0, 40.0), None);
+
+        assert_eq!(protection.check(12.0, 6.0, 40.0), Some(FaultKind::OverCurrent));
+        // a later fault doesn't replace the latched one, nor does the condition going away
+        assert_eq!(protection.check(35.0, 2.0, 40.0), Some(FaultKind::OverCurrent));
+        assert_eq!(protection.check(12.0, 2.0, 40.0), Some(FaultKind::OverCurrent));
+        assert_eq!(protection.fault(), Some(FaultKind::OverCurrent));
+    }
+
+    #[test]
+    fn protection_trips_on_nan_readings() {
+        let mut protection = Protection::new(5.0, 30.0, 80.0);
+        assert_eq!(protection.check(12.0, f32::NAN, 40.0), Some(FaultKind::OverCurrent));
+
+        let mut protection = Protection::new(5.0, 30.0, 80.0);
+        assert_eq!(protection.check(f32::NAN, 2.0, 40.0), Some(FaultKind::OverVoltage));
+        assert_eq!(protection.clear_fault(12.0, 2.0, f32::NAN), Err(FaultKind::OverTemperature));
+        // no temperature sensor reads as negative infinity, which never trips
+        assert_eq!(protection.clear_fault(12.0, 2.0, f32::NEG_INFINITY), Ok(()));
+    }
+
+    #[test]
+    fn protection_clears_only_once_within_limits() {
+        let mut protection = Protection::new(5.0, 30.0, 80.0);
+        protection.check(12.0, 2.0, 90.0);
+
+        assert_eq!(protection.clear_fault(12.0, 2.0, 85.0), Err(FaultKind::OverTemperature));
+        assert_eq!(protection.fault(), Some(FaultKind::OverTemperature));
+
+        assert_eq!(protection.clear_fault(12.0, 2.0, 60.0), Ok(()));
+        assert_eq!(protection.fault(), None);
+    }
+
+    #[test]
+    fn throttle_holds_back_small_changes() {
+        let mut throttle = DacThrottle::new();
+        throttle.set_min_dac_interval(Duration::from_millis(10));
+        throttle.set_min_dac_delta(100);
+        let at = Instant::from_millis;
+
+        assert_eq!(throttle.next(1000, at(0)), Some(1000));
+        assert_eq!(throttle.next(1050, at(1)), None);
+        assert_eq!(throttle.next(1000, at(2)), None);
+        assert_eq!(throttle.next(1100, at(3)), Some(1100));
+        // small changes wait for the interval, and the latest goes out
+        assert_eq!(throttle.next(1120, at(5)), None);
+        assert_eq!(throttle.next(1130, at(13)), Some(1130));
+        // off is never held back
+        assert_eq!(throttle.next(0, at(14)), Some(0));
+        assert_eq!(throttle.last(), Some(0));
+    }
+
+    #[test]
+    fn slew_limits_step_size() {
+        let mut slew = SlewLimiter::new(100);
+
+        for step in 1..=10 {
+            assert_eq!(slew.next(1000), step * 100);
+        }
+        assert_eq!(slew.next(1000), 1000);
+
+        assert_eq!(slew.next(950), 950);
+        assert_eq!(slew.next(0), 850);
+    }
+
+    #[test]
+    fn slew_disabled_steps() {
+        let mut slew = SlewLimiter::new(100);
+        slew.set_slew_rate(None);
+
+        assert_eq!(slew.next(0xffff), 0xffff);
+        assert_eq!(slew.next(0), 0);
+    }
+
+    #[test]
+    fn waveform_sine_sweep() {
+        for frequency_hz in [1, 10, 250, 1000, 2500] {
+            let period_us = 1_000_000 / frequency_hz as u64;
+            let sine = Waveform::new(Shape::Sine, frequency_hz as f32)
+                .with_amplitude(10_000)
+                .with_offset(0x8000);
+
+            assert_eq!(sine.code_at(0), 0x8000);
+            assert_eq!(sine.code_at(period_us / 4), 0x8000 + 10_000);
+            assert_eq!(sine.code_at(period_us / 2), 0x8000);
+            assert_eq!(sine.code_at(period_us * 3 / 4), 0x8000 - 10_000);
+            assert_eq!(sine.code_at(period_us), 0x8000);
+
+            // sin(45°) * 10 000, within the table's interpolation error
+            let code = sine.code_at(period_us / 8);
+            assert!(code.abs_diff(0x8000 + 7_071) <= 2);
+        }
+    }
+
+    #[test]
+    fn waveform_sine_is_monotonic_per_quarter() {
+        let sine = Waveform::new(Shape::Sine, 1000.0).with_amplitude(30_000).with_offset(0x8000);
+
+        let codes = (0..=250).map(|tick| sine.code_at_tick(tick, 1));
+        let mut last = 0;
+        for code in codes {
+            assert!(code >= last);
+            last = code;
+        }
+    }
+
+    #[test]
+    fn waveform_triangle_and_square() {
+        let triangle = Waveform::new(Shape::Triangle, 100.0).with_amplitude(1000).with_offset(2000);
+
+        assert_eq!(triangle.code_at(0), 1000);
+        assert_eq!(triangle.code_at(2500), 2000);
+        assert_eq!(triangle.code_at(5000), 3000);
+        assert_eq!(triangle.code_at(7500), 2000);
+        assert_eq!(triangle.code_at(1250), 1500);
+
+        let square = Waveform::new(Shape::Square, 100.0).with_amplitude(1000).with_offset(2000);
+
+        assert_eq!(square.code_at(0), 3000);
+        assert_eq!(square.code_at(4999), 3000);
+        assert_eq!(square.code_at(5000), 1000);
+        assert_eq!(square.code_at(9999), 1000);
+        assert_eq!(square.code_at(10_000), 3000);
+    }
+
+    #[test]
+    fn waveform_clamps_to_dac_range() {
+        let square = Waveform::new(Shape::Square, 100.0)
+            .with_amplitude(1000)
+            .with_offset(500)
+            .with_max_code(0xfff);
+
+        assert_eq!(square.code_at(5000), 0);
+
+        let square = square.with_offset(0xf00);
+        assert_eq!(square.code_at(0), 0xfff);
+    }
+
+    const PROFILE: [Segment; 4] = [
+        Segment::hold(1.0, Duration::from_millis(100)),
+        Segment::ramp(5.0, Duration::from_millis(200)),
+        Segment::hold(5.0, Duration::from_millis(0)),
+        Segment::ramp(3.0, Duration::from_millis(100)),
+    ];
+
+    #[test]
+    fn profile_holds_and_ramps() {
+        let profile = Profile::new(&PROFILE);
+
+        assert_eq!(profile.duration(), Duration::from_millis(400));
+        assert_eq!(profile.sample(Duration::from_millis(0)), 1.0);
+        assert_eq!(profile.sample(Duration::from_millis(99)), 1.0);
+        assert_eq!(profile.sample(Duration::from_millis(100)), 1.0);
+        assert_eq!(profile.sample(Duration::from_millis(200)), 3.0);
+        assert_eq!(profile.sample(Duration::from_millis(300)), 5.0);
+        assert_eq!(profile.sample(Duration::from_millis(350)), 4.0);
+    }
+
+    #[test]
+    fn profile_holds_last_target_when_finished() {
+        let profile = Profile::new(&PROFILE);
+
+        assert!(!profile.is_finished(Duration::from_millis(399)));
+        assert!(profile.is_finished(Duration::from_millis(400)));
+        assert_eq!(profile.sample(Duration::from_millis(400)), 3.0);
+        assert_eq!(profile.sample(Duration::from_secs(60)), 3.0);
+    }
+
+    #[test]
+    fn profile_loops() {
+        let profile = Profile::new(&PROFILE).with_looping(true).with_start(2.0);
+
+        assert!(!profile.is_finished(Duration::from_secs(60)));
+        assert_eq!(profile.sample(Duration::from_millis(450)), 1.0);
+        assert_eq!(profile.sample(Duration::from_millis(4_350)), 4.0);
+    }
+
+    #[test]
+    fn profile_ramps_from_start() {
+        let segments = [Segment::ramp(10.0, Duration::from_millis(100))];
+        let profile = Profile::new(&segments).with_start(2.0);
+
+        assert_eq!(profile.sample(Duration::from_millis(0)), 2.0);
+        assert_eq!(profile.sample(Duration::from_millis(50)), 6.0);
+        assert_eq!(profile.sample(Duration::from_millis(100)), 10.0);
+    }
+
+    #[test]
+    fn profile_without_length() {
+        assert_eq!(Profile::new(&[]).with_start(1.5).sample(Duration::from_secs(1)), 1.5);
+
+        let segments = [Segment::ramp(4.0, Duration::from_millis(0))];
+        let profile = Profile::new(&segments).with_looping(true);
+        assert_eq!(profile.sample(Duration::from_millis(10)), 4.0);
+    }
+
+    #[test]
+    fn hysteresis_switches_at_the_outer_thresholds() {
+        let mut cutoff = Hysteresis::new(3.0, 3.4);
+
+        // off until the high threshold has been reached once
+        assert!(!cutoff.update(3.2));
+        assert!(cutoff.update(3.4));
+        assert!(cutoff.update(3.1));
+        assert!(cutoff.update(3.0));
+        assert!(!cutoff.update(2.99));
+        assert!(!cutoff.update(3.3));
+        assert!(cutoff.update(3.5));
+    }
+
+    #[test]
+    fn hysteresis_with_equal_thresholds_is_a_comparator() {
+        let mut comparator = Hysteresis::new(1.0, 1.0);
+
+        assert!(comparator.update(1.0));
+        assert!(!comparator.update(0.99));
+        assert!(comparator.update(1.01));
+    }
+
+    #[test]
+    fn hysteresis_initial_state_holds_between_thresholds() {
+        let mut cutoff = Hysteresis::new(3.0, 3.4).with_state(true);
+
+        assert!(cutoff.state());
+        assert!(cutoff.update(3.2));
+    }
+
+    #[test]
+    fn event_log_drains_oldest_first() {
+        let mut log = EventLog::<4>::new();
+        assert_eq!(log.pop(), None);
+
+        log.push(10, EventKind::Fault(FaultKind::OverCurrent));
+        log.push(20, EventKind::ModeChange(LoadMode::ConstantVoltage(5.0)));
+        assert_eq!(log.len(), 2);
+
+        assert_eq!(log.pop(), Some(Event { timestamp_ticks: 10, kind: EventKind::Fault(FaultKind::OverCurrent) }));
+        assert_eq!(log.pop(), Some(Event { timestamp_ticks: 20, kind: EventKind::ModeChange(LoadMode::ConstantVoltage(5.0)) }));
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn event_log_overwrites_oldest_when_full() {
+        let mut log = EventLog::<3>::new();
+        for tick in 0..5 {
+            log.push(tick, EventKind::CrcError);
+        }
+
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.overwritten(), 2);
+        for tick in 2..5 {
+            assert_eq!(log.pop().map(|event| event.timestamp_ticks), Some(tick));
+        }
+        assert_eq!(log.pop(), None);
+
+        // the ring keeps working after wrapping and draining
+        log.push(7, EventKind::Fault(FaultKind::OverTemperature));
+        assert_eq!(log.pop().map(|event| event.kind), Some(EventKind::Fault(FaultKind::OverTemperature)));
+    }
+
+    #[test]
+    fn sample_pairer_keeps_pairs_within_one_turn() {
+        // current on channel 0 converts before voltage on channel 1
+        let mut pairer = SamplePairer::new(Channel::Ch1, Channel::Ch0);
+
+        // joined halfway through a turn, the voltage has no current to go with
+        assert_eq!(pairer.push(Channel::Ch1, 11.0), None);
+        assert_eq!(pairer.push(Channel::Ch0, 2.0), None);
+        assert_eq!(pairer.push(Channel::Ch2, 25.0), None);
+        assert_eq!(pairer.push(Channel::Ch1, 12.0), Some((12.0, 2.0)));
+
+        // a missed voltage drops the current of that turn
+        assert_eq!(pairer.push(Channel::Ch0, 3.0), None);
+        assert_eq!(pairer.push(Channel::Ch0, 4.0), None);
+        assert_eq!(pairer.push(Channel::Ch1, 10.0), Some((10.0, 4.0)));
+    }
+
+    #[test]
+    fn power_meter_integrates_energy_and_averages_per_window() {
+        let mut meter = PowerMeter::new(Duration::from_secs(1));
+        assert_eq!(meter.power_w(), 0.0);
+
+        meter.add(10.0, 2.0, 0.5);
+        assert_eq!(meter.instantaneous_w(), 20.0);
+        assert_eq!(meter.power_w(), 20.0);
+
+        meter.add(10.0, 4.0, 0.5);
+        assert_eq!(meter.power_w(), 30.0);
+        assert_eq!(meter.energy_j(), 30.0);
+
+        // the average holds until the next window completes
+        meter.add(10.0, 1.0, 0.5);
+        assert_eq!(meter.instantaneous_w(), 10.0);
+        assert_eq!(meter.power_w(), 30.0);
+        assert_eq!(meter.energy_j(), 35.0);
+
+        meter.reset();
+        assert_eq!(meter.energy_j(), 0.0);
+        assert_eq!(meter.power_w(), 0.0);
+    }
+}
@@ -0,0 +1,73 @@
+//! Host independent tests for the DAC code conversions.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use dc_load_control_loop_rs::dac::{code_to_millivolts, code_to_voltage, millivolts_to_code, voltage_to_code, DacCalibration};
+    use dc_load_control_loop_rs::units::{Code, Millivolts};
+    use defmt::{assert, assert_eq};
+
+    #[init]
+    fn init() {
+        let _ = esp_hal::init(esp_hal::Config::default());
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn rounds_to_nearest_code() {
+        assert_eq!(voltage_to_code(1.25, 2500.0, 16), 0x8000);
+        // 1 LSB at 16 bits and 2.5 V is ~38.1 µV
+        assert_eq!(voltage_to_code(0.000_02, 2500.0, 16), 1);
+        assert_eq!(voltage_to_code(0.000_018, 2500.0, 16), 0);
+    }
+
+    #[test]
+    fn clamps_to_zero() {
+        assert_eq!(voltage_to_code(0.0, 2500.0, 16), 0);
+        assert_eq!(voltage_to_code(-1.0, 2500.0, 16), 0);
+    }
+
+    #[test]
+    fn clamps_to_full_scale() {
+        assert_eq!(voltage_to_code(2.5, 2500.0, 16), 0xffff);
+        assert_eq!(voltage_to_code(10.0, 2500.0, 16), 0xffff);
+        assert_eq!(voltage_to_code(10.0, 2500.0, 32), u32::MAX);
+    }
+
+    #[test]
+    fn code_to_voltage_inverts_voltage_to_code() {
+        assert_eq!(code_to_voltage(0x8000, 2500.0, 16), 1.25);
+        assert_eq!(voltage_to_code(code_to_voltage(0x1234, 2500.0, 16), 2500.0, 16), 0x1234);
+    }
+
+    #[test]
+    fn calibration_from_two_points() {
+        // a DAC reading 2 % low with 10 mV of offset
+        let measured = |code| 0.98 * code_to_voltage(code, 2500.0, 16) + 0.01;
+        let calibration = DacCalibration::from_two_points((0x1000, measured(0x1000)), (0xf000, measured(0xf000)), 2500.0, 16);
+
+        assert!((calibration.gain - 0.98).abs() < 1e-5);
+        assert!((calibration.offset - 0.01).abs() < 1e-5);
+
+        // commanding the corrected voltage gets 1 V out
+        let code = voltage_to_code(calibration.correct(1.0), 2500.0, 16);
+        assert!((measured(code) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn calibration_defaults_to_identity() {
+        assert_eq!(DacCalibration::default(), DacCalibration::IDENTITY);
+        assert_eq!(DacCalibration::IDENTITY.correct(1.5), 1.5);
+    }
+
+    #[test]
+    fn typed_conversions_match_bare_ones() {
+        assert_eq!(millivolts_to_code(Millivolts(1250.0), Millivolts(2500.0), 16), Code(0x8000));
+        assert_eq!(code_to_millivolts(Code(0x8000), Millivolts(2500.0), 16), Millivolts(1250.0));
+        assert_eq!(Millivolts::from_volts(1.25).to_volts(), 1.25);
+    }
+}
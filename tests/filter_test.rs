@@ -0,0 +1,140 @@
+//! Host independent tests for the software filters.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use dc_load_control_loop_rs::filter::{Decimator, MovingAverage, Stats};
+    use defmt::{assert, assert_eq};
+
+    #[init]
+    fn init() {
+        let _ = esp_hal::init(esp_hal::Config::default());
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn moving_average_warms_up() {
+        let mut average = MovingAverage::<4>::new();
+
+        assert_eq!(average.average(), 0);
+        assert_eq!(average.push(10), 10);
+        assert_eq!(average.push(20), 15);
+        assert_eq!(average.push(30), 20);
+    }
+
+    #[test]
+    fn moving_average_drops_oldest_sample() {
+        let mut average = MovingAverage::<4>::new();
+        for sample in [10, 20, 30, 40] {
+            average.push(sample);
+        }
+
+        assert_eq!(average.push(50), 35);
+        assert_eq!(average.push(-120), 0);
+    }
+
+    #[test]
+    fn moving_average_does_not_overflow() {
+        let mut average = MovingAverage::<8>::new();
+        for _ in 0..20 {
+            average.push(i32::MAX);
+        }
+
+        assert_eq!(average.average(), i32::MAX);
+    }
+
+    #[test]
+    fn moving_average_reset_restarts_warm_up() {
+        let mut average = MovingAverage::<4>::new();
+        average.push(100);
+        average.push(100);
+        average.reset();
+
+        assert_eq!(average.push(-8), -8);
+    }
+
+    #[test]
+    fn decimator_outputs_every_nth_block_average() {
+        let mut decimator = Decimator::<4>::new();
+
+        assert_eq!(decimator.push(10), None);
+        assert_eq!(decimator.push(20), None);
+        assert_eq!(decimator.push(30), None);
+        assert_eq!(decimator.push(41), Some(25));
+        // the next block starts from scratch
+        assert_eq!(decimator.push(-7), None);
+        assert_eq!(decimator.push(0), None);
+        assert_eq!(decimator.push(0), None);
+        assert_eq!(decimator.push(0), Some(-1));
+    }
+
+    #[test]
+    fn decimator_does_not_overflow() {
+        let mut decimator = Decimator::<16>::new();
+        for _ in 0..15 {
+            assert_eq!(decimator.push(i32::MAX), None);
+        }
+
+        assert_eq!(decimator.push(i32::MAX), Some(i32::MAX));
+    }
+
+    #[test]
+    fn decimator_reset_drops_partial_block() {
+        let mut decimator = Decimator::<2>::new();
+        decimator.push(1000);
+        decimator.reset();
+
+        assert_eq!(decimator.push(2), None);
+        assert_eq!(decimator.push(4), Some(3));
+    }
+
+    #[test]
+    fn stats_over_known_samples() {
+        let mut stats = Stats::new();
+        for sample in [1.0, 2.0, 3.0, 4.0, 5.0] {
+            stats.push(sample);
+        }
+
+        assert_eq!(stats.count(), 5);
+        assert_eq!(stats.mean(), 3.0);
+        assert_eq!(stats.min(), 1.0);
+        assert_eq!(stats.max(), 5.0);
+        // sqrt(55 / 5)
+        assert!((stats.rms() - 3.316_625).abs() < 1e-5);
+    }
+
+    #[test]
+    fn stats_rms_of_square_wave() {
+        let mut stats = Stats::new();
+        for tick in 0..100 {
+            stats.push(if tick % 2 == 0 { 2.5 } else { -2.5 });
+        }
+
+        assert_eq!(stats.mean(), 0.0);
+        assert_eq!(stats.min(), -2.5);
+        assert_eq!(stats.max(), 2.5);
+        assert!((stats.rms() - 2.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn stats_reset_starts_new_window() {
+        let mut stats = Stats::new();
+        stats.push(10.0);
+        stats.push(-10.0);
+        stats.reset();
+
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.rms(), 0.0);
+        assert_eq!(stats.min(), 0.0);
+        assert_eq!(stats.max(), 0.0);
+
+        stats.push(0.25);
+        assert_eq!(stats.min(), 0.25);
+        assert_eq!(stats.max(), 0.25);
+        assert!((stats.rms() - 0.25).abs() < 1e-6);
+    }
+}
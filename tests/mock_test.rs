@@ -0,0 +1,1222 @@
+//! Driver framing tests against the in-memory SPI bus.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests]
+mod tests {
+    use core::cell::RefCell;
+    use core::num::NonZeroU16;
+    use dc_load_control_loop_rs::adc::crc::{crc8, xor8};
+    use dc_load_control_loop_rs::adc::device::Ad7175_8;
+    use dc_load_control_loop_rs::adc::register::{AdcModeRegister, Channel0Register, Channel2Register, Channel7Register, DefaultFilterConfig1Register, GPIOConfigRegister, IdRegister, InterfaceModeRegister, Offset0Register, Register, ReservedBitsSet, Setup1Registers, SetupConfig1Register, WritableRegister};
+    use dc_load_control_loop_rs::adc::setup_builder::SetupBuilder;
+    use dc_load_control_loop_rs::adc::transport::RegisterTransport;
+    use dc_load_control_loop_rs::adc::{AdcConfig, AdcError, Calibration, Channel, ClockSource, Crc, DataRegisterLength, EnhancedFilterRate, FilterConfig, FilterOrder, Input, InvalidInputPair, InvalidValue, Mode, NotOnPart, OutputCoding, OutputDataRate, Sequencer, SettleTimes, Settling, Setup, ADC};
+    use dc_load_control_loop_rs::adc::Delay as ConversionDelay;
+    use dc_load_control_loop_rs::adc::scaling::Scaling;
+    use dc_load_control_loop_rs::control::autotune::{AutotuneError, RelayTuner};
+    use dc_load_control_loop_rs::control::pid::{Pid, PidGains};
+    use dc_load_control_loop_rs::control::protection::{FaultKind, Protection};
+    use dc_load_control_loop_rs::control::range::{Range, RangeSwitch};
+    use dc_load_control_loop_rs::control::selftest::{selftest, DacMonitor, SelfTestError};
+    use dc_load_control_loop_rs::control::events::{EventKind, EventLog};
+    use dc_load_control_loop_rs::control::task::{control_cycle, Measurement, Sensing};
+    use dc_load_control_loop_rs::control::{LoadController, LoadMode};
+    use dc_load_control_loop_rs::dac::{DacError, DacGroup, PowerDownMode, DAC};
+    use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+    use embassy_sync::blocking_mutex::Mutex;
+    use embassy_time::Duration;
+    use dc_load_control_loop_rs::mock::MockSpiBus;
+    use defmt::{assert, assert_eq};
+    use esp_hal::delay::Delay;
+    use esp_hal::gpio::{Level, Output, OutputConfig};
+    use esp_hal::peripherals::Peripherals;
+
+    #[init]
+    fn init() -> Peripherals {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        rtt_target::rtt_init_defmt!();
+
+        peripherals
+    }
+
+    /// An ADC on the mock bus with CRC enabled and the interface mode write forgotten.
+    fn adc_with_crc() -> ADC<MockSpiBus> {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_crc_en(Crc::Enable)).unwrap();
+        adc.spi_mut().clear_written();
+        adc
+    }
+
+    #[test]
+    fn adc_read_sends_command_byte() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x0c, 0xd0]);
+        let mut adc = ADC::new(spi);
+
+        assert_eq!(adc.read::<2, IdRegister>().unwrap().id(), 0x0cd0);
+        // 0x40 (read) | 0x07 (ID), followed by the clocks for the two data bytes
+        assert_eq!(adc.spi_mut().written(), &[0x47, 0x00, 0x00]);
+    }
+
+    /// A transport that isn't a bus at all, answering every read with the AD7175-2 ID and
+    /// counting writes.
+    struct FakeTransport {
+        writes: usize,
+    }
+
+    impl RegisterTransport for FakeTransport {
+        type Error = ();
+
+        fn read_register(&mut self, frame: &mut [u8]) -> Result<(), ()> {
+            frame[1..3].copy_from_slice(&[0x0c, 0xd0]);
+            Ok(())
+        }
+
+        fn write_register(&mut self, _frame: &[u8]) -> Result<(), ()> {
+            self.writes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn adc_runs_over_any_transport() {
+        let mut adc = ADC::new(FakeTransport { writes: 0 });
+
+        assert!(adc.check_id().is_ok());
+        adc.write(&AdcModeRegister::new()).unwrap();
+        assert_eq!(adc.spi_mut().writes, 1);
+    }
+
+    #[test]
+    fn adc_check_id_expects_selected_part() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x3c, 0xd3]);
+        spi.queue_response(&[0x00, 0x0c, 0xd0]);
+        let mut adc = ADC::new(spi).with_part(Ad7175_8);
+
+        adc.check_id().unwrap();
+        assert!(matches!(adc.check_id(), Err(AdcError::IdMismatch { expected: 0x3cd0, got: 0x0cd0 })));
+    }
+
+    #[test]
+    fn adc_rejects_what_the_part_lacks() {
+        let mut adc = ADC::new(MockSpiBus::new());
+
+        let result = adc.configure_channel(Channel::Ch7, Setup::Setup0, Input::Analog0, Input::Analog1);
+        assert!(matches!(result, Err(AdcError::NotOnPart(NotOnPart::Channel(Channel::Ch7)))));
+        let result = adc.configure_channel(Channel::Ch0, Setup::Setup5, Input::Analog0, Input::Analog1);
+        assert!(matches!(result, Err(AdcError::NotOnPart(NotOnPart::Setup(Setup::Setup5)))));
+        let result = adc.configure_channel(Channel::Ch0, Setup::Setup0, Input::Analog9, Input::Analog1);
+        assert!(matches!(result, Err(AdcError::NotOnPart(NotOnPart::Input(Input::Analog9)))));
+        assert!(matches!(adc.read_calibration(Setup::Setup4), Err(AdcError::NotOnPart(NotOnPart::Setup(Setup::Setup4)))));
+        assert!(matches!(adc.read::<2, Channel7Register>(), Err(AdcError::NotOnPart(NotOnPart::Channel(Channel::Ch7)))));
+        assert!(adc.spi_mut().written().is_empty());
+    }
+
+    #[test]
+    fn adc_reaches_ad7175_8_channels_and_setups() {
+        let mut adc = ADC::new(MockSpiBus::new()).with_part(Ad7175_8);
+
+        adc.configure_channel(Channel::Ch7, Setup::Setup5, Input::Analog9, Input::Analog16).unwrap();
+        // CH7 at 0x17: CH_EN, SETUP_SEL = 5, AINPOS = 9, AINNEG = 16
+        assert_eq!(adc.spi_mut().written(), &[0x17, 0xd1, 0x30]);
+
+        adc.spi_mut().clear_written();
+        adc.write_calibration(Setup::Setup7, &Calibration { offset: 0x800001, gain: 0x555555 }).unwrap();
+        assert_eq!(adc.spi_mut().written(), &[0x37, 0x80, 0x00, 0x01, 0x3f, 0x55, 0x55, 0x55]);
+    }
+
+    #[test]
+    fn adc_write_all_writes_in_order() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        let offsets = [Offset0Register::new().with_offset(0x123456)];
+        adc.write_all((
+            InterfaceModeRegister::new().with_data_stat(true),
+            &offsets[..],
+            AdcModeRegister::new().with_mode(Mode::SingleConversion),
+        )).unwrap();
+
+        assert_eq!(adc.spi_mut().written(), &[0x02, 0x00, 0x40, 0x30, 0x12, 0x34, 0x56, 0x01, 0x80, 0x10]);
+    }
+
+    #[test]
+    fn adc_write_all_stops_at_first_error() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        let result = adc.write_all((
+            InterfaceModeRegister::new(),
+            AdcModeRegister::from_buffer(&[0x90, 0x00]),
+            GPIOConfigRegister::new(),
+        ));
+
+        assert!(matches!(result, Err(AdcError::ReservedBitsSet(_))));
+        assert_eq!(adc.spi_mut().written(), &[0x02, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn adc_write_rejects_reserved_bits() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        // bit 12 of the ADC mode register is reserved
+        let mode = AdcModeRegister::from_buffer(&[0x90, 0x00]);
+
+        assert_eq!(mode.check_reserved_bits(), Err(ReservedBitsSet { bits: 0x1000 }));
+        assert!(matches!(adc.write(&mode), Err(AdcError::ReservedBitsSet(_))));
+        assert!(adc.spi_mut().written().is_empty());
+        assert_eq!(AdcModeRegister::new().check_reserved_bits(), Ok(()));
+    }
+
+    #[test]
+    fn adc_read_raw_returns_register_bytes() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x0c, 0xd0]);
+        let mut adc = ADC::new(spi);
+
+        assert_eq!(adc.read_raw(0x07, 2).unwrap(), &[0x0c, 0xd0]);
+        assert!(matches!(adc.read_raw(0x07, 6), Err(AdcError::InvalidLength)));
+        assert_eq!(adc.spi_mut().written(), &[0x47, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn adc_read_with_raw_returns_decoded_and_wire_bytes() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x0c, 0xd1]);
+        let mut adc = ADC::new(spi);
+
+        let (id, raw) = adc.read_with_raw::<2, IdRegister>().unwrap();
+        assert_eq!(id.id(), 0x0cd1);
+        assert_eq!(raw, [0x0c, 0xd1]);
+        assert_eq!(adc.spi_mut().written(), &[0x47, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn adc_read_rejects_undecodable_field() {
+        let mut spi = MockSpiBus::new();
+        // channel 0 enabled with AINPOS = 0x1f, which isn't a valid input
+        spi.queue_response(&[0x00, 0x83, 0xe1]);
+        let mut adc = ADC::new(spi);
+
+        let result = adc.read::<2, Channel0Register>();
+        assert!(matches!(result, Err(AdcError::InvalidValue(InvalidValue { type_name: "Input", value: 0x1f }))));
+    }
+
+    #[test]
+    fn adc_configure_channel_rejects_invalid_input_pair() {
+        let mut adc = ADC::new(MockSpiBus::new());
+
+        let result = adc.configure_channel(Channel::Ch1, Setup::Setup0, Input::TemperatureSensorPos, Input::Analog1);
+        assert!(matches!(result, Err(AdcError::InvalidInputPair(InvalidInputPair { pos: Input::TemperatureSensorPos, neg: Input::Analog1 }))));
+        assert!(adc.spi_mut().written().is_empty());
+    }
+
+    #[test]
+    fn try_from_bits_rejects_unknown_values() {
+        assert_eq!(Crc::try_from_bits(0x02), Ok(Crc::Enable));
+        assert!(Crc::try_from_bits(0x03).is_err());
+    }
+
+    #[test]
+    fn adc_write_sends_register_bytes() {
+        let mut adc = ADC::new(MockSpiBus::new());
+
+        adc.write(&AdcModeRegister::new()).unwrap();
+        adc.write(&Offset0Register::new()).unwrap();
+
+        assert_eq!(adc.spi_mut().written(), &[0x01, 0x80, 0x00, 0x30, 0x80, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn adc_write_verified_reads_register_back() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x80, 0x00]);
+        spi.queue_response(&[0x00, 0x80, 0x10]);
+        let mut adc = ADC::new(spi);
+
+        adc.write_verified(&AdcModeRegister::new()).unwrap();
+        assert_eq!(adc.spi_mut().written(), &[0x01, 0x80, 0x00, 0x41, 0x00, 0x00]);
+        assert!(matches!(
+            adc.write_verified(&AdcModeRegister::new()),
+            Err(AdcError::Verify { id: 0x01, expected: 0x8000, got: 0x8010 })
+        ));
+    }
+
+    #[test]
+    fn adc_write_verified_masked_ignores_masked_bits() {
+        let mut spi = MockSpiBus::new();
+        // the mode went back to standby after the write
+        spi.queue_response(&[0x00, 0x80, 0x20]);
+        let mut adc = ADC::new(spi);
+
+        adc.write_verified_masked(&AdcModeRegister::new().with_mode(Mode::SingleConversion), [0xff, 0x8f]).unwrap();
+    }
+
+    #[test]
+    fn adc_write_appends_crc() {
+        let mut adc = adc_with_crc();
+
+        adc.write(&AdcModeRegister::new()).unwrap();
+
+        assert_eq!(adc.spi_mut().written(), &[0x01, 0x80, 0x00, crc8(&[0x01, 0x80, 0x00])]);
+    }
+
+    #[test]
+    fn adc_read_checks_crc() {
+        let mut adc = adc_with_crc();
+        let crc = crc8(&[0x47, 0x0c, 0xd0]);
+
+        adc.spi_mut().queue_response(&[0x00, 0x0c, 0xd0, crc]);
+        assert_eq!(adc.read::<2, IdRegister>().unwrap().id(), 0x0cd0);
+
+        adc.spi_mut().queue_response(&[0x00, 0x0c, 0xd0, crc ^ 0x01]);
+        assert!(matches!(adc.read::<2, IdRegister>(), Err(AdcError::CrcMismatch)));
+    }
+
+    #[test]
+    fn adc_xor_mode_checks_reads_with_xor() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_crc_en(Crc::EnableWithXorOnRead)).unwrap();
+        adc.spi_mut().clear_written();
+        let xor = xor8(&[0x47, 0x0c, 0xd0]);
+
+        adc.spi_mut().queue_response(&[0x00, 0x0c, 0xd0, xor]);
+        assert_eq!(adc.read::<2, IdRegister>().unwrap().id(), 0x0cd0);
+        assert_eq!(adc.spi_mut().written(), &[0x47, 0x00, 0x00, 0x00]);
+
+        adc.spi_mut().queue_response(&[0x00, 0x0c, 0xd0, xor ^ 0x01]);
+        assert!(matches!(adc.read::<2, IdRegister>(), Err(AdcError::CrcMismatch)));
+    }
+
+    #[test]
+    fn adc_xor_mode_still_protects_writes_with_crc() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_crc_en(Crc::EnableWithXorOnRead)).unwrap();
+        adc.spi_mut().clear_written();
+
+        adc.write(&AdcModeRegister::new()).unwrap();
+
+        assert_eq!(adc.spi_mut().written(), &[0x01, 0x80, 0x00, crc8(&[0x01, 0x80, 0x00])]);
+    }
+
+    #[test]
+    fn adc_read_data_follows_word_length() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.spi_mut().queue_response(&[0x00, 0x12, 0x34, 0x56]);
+        assert_eq!(adc.read_data().unwrap().data(), 0x123456);
+        assert_eq!(adc.spi_mut().written(), &[0x44, 0x00, 0x00, 0x00]);
+
+        adc.write(&InterfaceModeRegister::new().with_wl16(DataRegisterLength::SixteenBits)).unwrap();
+        adc.spi_mut().clear_written();
+
+        adc.spi_mut().queue_response(&[0x00, 0x12, 0x34]);
+        assert_eq!(adc.read_data().unwrap().data(), 0x123400);
+        assert_eq!(adc.spi_mut().written(), &[0x44, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn adc_read_data_and_status_reads_one_frame() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        assert!(matches!(adc.read_data_and_status(), Err(AdcError::DataStatDisabled)));
+        assert!(adc.spi_mut().written().is_empty());
+
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+        adc.spi_mut().clear_written();
+        adc.spi_mut().queue_response(&[0x00, 0x12, 0x34, 0x56, 0x01]);
+        let frame = adc.read_data_and_status().unwrap();
+        assert_eq!(frame.data(), 0x123456);
+        assert_eq!(frame.status(), 0x01);
+        assert_eq!(adc.spi_mut().written(), &[0x44, 0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn adc_read_16_bit_data_and_status_checks_crc() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        let interface_mode = InterfaceModeRegister::new()
+            .with_wl16(DataRegisterLength::SixteenBits)
+            .with_data_stat(true)
+            .with_crc_en(Crc::Enable);
+        adc.write(&interface_mode).unwrap();
+        adc.spi_mut().clear_written();
+
+        let crc = crc8(&[0x44, 0x80, 0x01, 0x21]);
+        adc.spi_mut().queue_response(&[0x00, 0x80, 0x01, 0x21, crc]);
+        let frame = adc.read_data_and_status().unwrap();
+        assert_eq!(frame.data(), 0x800100);
+        assert_eq!(frame.status(), 0x21);
+        assert_eq!(adc.spi_mut().written().len(), 5);
+
+        adc.spi_mut().queue_response(&[0x00, 0x80, 0x01, 0x21, crc ^ 0x01]);
+        assert!(matches!(adc.read_data_and_status(), Err(AdcError::CrcMismatch)));
+    }
+
+    /// A delay that only adds up how long it was asked to wait.
+    #[derive(Default)]
+    struct RecordingDelay {
+        waited_ns: u64,
+    }
+
+    impl embedded_hal::delay::DelayNs for RecordingDelay {
+        fn delay_ns(&mut self, ns: u32) {
+            self.waited_ns += ns as u64;
+        }
+    }
+
+    /// A DOUT/RDY line that signals `ready` more conversions and then goes quiet.
+    struct FakeReady {
+        ready: usize,
+    }
+
+    impl embedded_hal::digital::ErrorType for FakeReady {
+        type Error = core::convert::Infallible;
+    }
+
+    impl embedded_hal::digital::InputPin for FakeReady {
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            self.is_low().map(|low| !low)
+        }
+
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            let low = self.ready > 0;
+            self.ready = self.ready.saturating_sub(1);
+            Ok(low)
+        }
+    }
+
+    #[test]
+    fn adc_read_burst_reads_each_ready_conversion() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_cont_read(true)).unwrap();
+        adc.spi_mut().clear_written();
+        adc.spi_mut().queue_response(&[0x12, 0x34, 0x56, 0x80, 0x00, 0x01]);
+
+        let mut out = [0; 2];
+        assert_eq!(adc.read_burst(&mut FakeReady { ready: 2 }, &mut out).unwrap(), 2);
+        assert_eq!(out, [0x123456, 0x800001]);
+        assert_eq!(adc.spi_mut().written(), &[0x00; 6]);
+    }
+
+    #[test]
+    fn adc_samples_iterates_until_conversions_stop() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_cont_read(true)).unwrap();
+        adc.spi_mut().queue_response(&[0x12, 0x34, 0x56, 0x80, 0x00, 0x01]);
+
+        let mut rdy = FakeReady { ready: 2 };
+        let mut samples = adc.samples(&mut rdy);
+        assert_eq!(samples.next().map(Result::unwrap), Some(0x123456));
+        assert_eq!(samples.next().map(Result::unwrap), Some(0x800001));
+        assert!(samples.next().is_none());
+        assert!(samples.next().is_none());
+    }
+
+    #[test]
+    fn adc_read_burst_stops_when_conversions_stop() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_cont_read(true)).unwrap();
+        adc.spi_mut().queue_response(&[0x12, 0x34, 0x56]);
+
+        let mut out = [0; 4];
+        assert_eq!(adc.read_burst(&mut FakeReady { ready: 1 }, &mut out).unwrap(), 1);
+        assert_eq!(out, [0x123456, 0, 0, 0]);
+    }
+
+    #[test]
+    fn adc_manual_cs_is_held_only_when_asked(peripherals: Peripherals) {
+        let cs = Output::new(peripherals.GPIO8, Level::High, OutputConfig::default());
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x0c, 0xd0]);
+        let mut adc = ADC::new(spi).with_manual_cs(cs);
+
+        assert_eq!(adc.read::<2, IdRegister>().unwrap().id(), 0x0cd0);
+        assert!(!adc.is_cs_held());
+
+        adc.write(&InterfaceModeRegister::new().with_cont_read(true)).unwrap();
+        adc.spi_mut().queue_response(&[0x12, 0x34, 0x56]);
+        let mut out = [0; 1];
+        assert_eq!(adc.read_burst(&mut FakeReady { ready: 1 }, &mut out).unwrap(), 1);
+        assert_eq!(out, [0x123456]);
+        // the burst gives CS back once it's done
+        assert!(!adc.is_cs_held());
+
+        adc.hold_cs();
+        assert!(adc.is_cs_held());
+        adc.release_cs().unwrap();
+        assert!(!adc.is_cs_held());
+    }
+
+    #[test]
+    fn adc_read_burst_needs_continuous_read() {
+        let mut adc = ADC::new(MockSpiBus::new());
+
+        let result = adc.read_burst(&mut FakeReady { ready: 1 }, &mut [0; 1]);
+        assert!(matches!(result, Err(AdcError::ContinuousReadInactive)));
+    }
+
+    #[test]
+    fn adc_reset_clocks_64_ones() {
+        let mut adc = ADC::new(MockSpiBus::new());
+
+        adc.reset().unwrap();
+
+        assert_eq!(adc.spi_mut().written(), &[0xff; 8]);
+    }
+
+    #[test]
+    fn adc_reset_waits_on_the_driver_delay() {
+        let mut delay = RecordingDelay::default();
+        let mut adc = ADC::new(MockSpiBus::new()).with_delay(&mut delay);
+
+        adc.reset().unwrap();
+        drop(adc);
+        assert_eq!(delay.waited_ns, 500_000);
+    }
+
+    #[test]
+    fn adc_caches_written_modes_until_reset() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        assert_eq!(adc.interface_mode(), InterfaceModeRegister::new());
+        assert_eq!(adc.adc_mode(), AdcModeRegister::new());
+
+        let interface_mode = InterfaceModeRegister::new().with_data_stat(true);
+        let adc_mode = AdcModeRegister::new().with_mode(Mode::ContinuousConversion);
+        adc.write(&interface_mode).unwrap();
+        adc.write(&adc_mode).unwrap();
+        assert_eq!(adc.interface_mode(), interface_mode);
+        assert_eq!(adc.adc_mode(), adc_mode);
+
+        adc.reset().unwrap();
+        assert_eq!(adc.interface_mode(), InterfaceModeRegister::new());
+        assert_eq!(adc.adc_mode(), AdcModeRegister::new());
+    }
+
+    #[test]
+    fn adc_timing_uses_configured_modulator_clock() {
+        let adc = ADC::new(MockSpiBus::new());
+        assert_eq!(adc.sequence_cycle_time_us(&[(FilterOrder::Sinc3, OutputDataRate::Sps1000)], true), 3_000);
+
+        // a modulator clock 1.5 times the nominal one cuts every time by a third
+        let adc = ADC::new(MockSpiBus::new()).with_modulator_frequency(8_000_000.0 * 1.5);
+        assert_eq!(adc.sequence_cycle_time_us(&[(FilterOrder::Sinc3, OutputDataRate::Sps1000)], true), 2_000);
+        assert_eq!(adc.settling_time_us(OutputDataRate::Sps1000, FilterOrder::Sinc3), 2_000);
+    }
+
+    #[test]
+    fn adc_wake_restores_mode_after_standby() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x80, 0x10]);
+        let mut adc = ADC::new(spi);
+
+        adc.standby().unwrap();
+        adc.wake().unwrap();
+
+        // single conversion → standby → single conversion
+        assert_eq!(adc.spi_mut().written(), &[0x41, 0x00, 0x00, 0x01, 0x80, 0x20, 0x01, 0x80, 0x10]);
+    }
+
+    #[test]
+    fn adc_wake_resets_after_power_down() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x80, 0x00]);
+        spi.queue_response(&[0x00, 0x80, 0x20]);
+        let mut adc = ADC::new(spi);
+
+        adc.power_down().unwrap();
+        adc.spi_mut().clear_written();
+        adc.wake().unwrap();
+
+        assert_eq!(adc.spi_mut().written(), &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0x80, 0x00]);
+    }
+
+    #[test]
+    fn adc_mode_writes_wait_out_settle_times() {
+        let mut delay = RecordingDelay::default();
+        let mut adc = ADC::new(MockSpiBus::new())
+            .with_delay(&mut delay)
+            .with_settle_times(SettleTimes { reference_us: 200, mode_us: 20, clock_us: 0 });
+
+        // only MODE changes
+        adc.write(&AdcModeRegister::new().with_ref_enable(false).with_mode(Mode::Standby)).unwrap();
+        // only the reference comes on
+        adc.write(&AdcModeRegister::new().with_mode(Mode::Standby)).unwrap();
+        // nothing to wait for
+        adc.write(&AdcModeRegister::new().with_mode(Mode::Standby)).unwrap();
+        adc.write(&InterfaceModeRegister::new()).unwrap();
+        drop(adc);
+
+        assert_eq!(delay.waited_ns, 220_000);
+    }
+
+    #[test]
+    fn adc_set_clock_source_waits_for_crystal() {
+        let mut delay = RecordingDelay::default();
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x80, 0x00]);
+        let mut adc = ADC::new(spi).with_delay(&mut delay).with_external_clock_frequency(16_384_000.0);
+
+        adc.set_clock_source(ClockSource::ExternalCrystal).unwrap();
+        assert_eq!(adc.spi_mut().written(), &[0x41, 0x00, 0x00, 0x01, 0x80, 0x0c]);
+        assert_eq!(adc.modulator_frequency(), 8_192_000.0);
+        assert_eq!(adc.settling_time_us(OutputDataRate::Sps10000, FilterOrder::Sinc5Sinc1), 98);
+        drop(adc);
+
+        assert_eq!(delay.waited_ns, 10_000);
+    }
+
+    #[test]
+    fn adc_read_single_converts_channel_0() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x80, 0x00]);
+        // not ready yet, then ready on channel 0
+        spi.queue_response(&[0x00, 0x80, 0x00, 0x00, 0x00, 0x12, 0x34, 0x56]);
+        let mut adc = ADC::new(spi);
+
+        assert_eq!(adc.read_single(Input::Analog2, Input::Analog3, Setup::Setup1).unwrap(), 0x123456);
+
+        let written = adc.spi_mut().written();
+        let channel = Channel0Register::from_buffer(&[written[1], written[2]]);
+        assert_eq!(written[0], 0x10);
+        assert_eq!((channel.ainpos(), channel.ainneg(), channel.setup_sel()), (Input::Analog2, Input::Analog3, Setup::Setup1));
+        assert_eq!(&written[6..9], &[0x01, 0x80, 0x10]);
+    }
+
+    #[test]
+    fn adc_stop_continuous_read_clocks_one_conversion() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_cont_read(true)).unwrap();
+        adc.spi_mut().clear_written();
+        adc.stop_continuous_read().unwrap();
+        assert_eq!(adc.spi_mut().written(), &[0x44, 0x00, 0x00]);
+        assert!(!adc.interface_mode().cont_read());
+        assert!(matches!(adc.stop_continuous_read(), Err(AdcError::ContinuousReadInactive)));
+
+        // data, status and CRC
+        adc.write(&InterfaceModeRegister::new().with_cont_read(true).with_data_stat(true).with_crc_en(Crc::Enable)).unwrap();
+        adc.spi_mut().clear_written();
+        adc.stop_continuous_read().unwrap();
+        assert_eq!(adc.spi_mut().written(), &[0x44, 0x00, 0x00, 0x00, 0x00]);
+
+        // register access works again
+        adc.spi_mut().queue_response(&[0x00, 0x0c, 0xd1, crc8(&[0x47, 0x0c, 0xd1])]);
+        adc.check_id().unwrap();
+    }
+
+    #[test]
+    fn adc_read_data_checked_reads_status_separately() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x12, 0x34, 0x56, 0x00, 0x00]);
+        spi.queue_response(&[0x00, 0x12, 0x34, 0x56, 0x00, 0x40]);
+        let mut adc = ADC::new(spi);
+
+        assert_eq!(adc.read_data_checked().unwrap().data(), 0x123456);
+        assert_eq!(adc.spi_mut().written(), &[0x44, 0x00, 0x00, 0x00, 0x40, 0x00]);
+        assert!(matches!(adc.read_data_checked(), Err(AdcError::AdcFault)));
+    }
+
+    #[test]
+    fn adc_read_data_checked_uses_appended_status() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+        adc.spi_mut().clear_written();
+        adc.spi_mut().queue_response(&[0x00, 0x12, 0x34, 0x56, 0x01]);
+        adc.spi_mut().queue_response(&[0x00, 0x12, 0x34, 0x56, 0x10]);
+        adc.spi_mut().queue_response(&[0x00, 0x12, 0x34, 0x56, 0x20]);
+
+        assert_eq!(adc.read_data_checked().unwrap().data(), 0x123456);
+        assert_eq!(adc.spi_mut().written(), &[0x44, 0x00, 0x00, 0x00, 0x00]);
+        assert!(matches!(adc.read_data_checked(), Err(AdcError::RegisterError)));
+        assert!(matches!(adc.read_data_checked(), Err(AdcError::CrcMismatch)));
+    }
+
+    #[test]
+    fn adc_filter_config_round_trips_either_layout() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.spi_mut().queue_response(&[0x00, 0x80, 0x20]);
+        adc.spi_mut().queue_response(&[0x00, 0x05, 0x0a]);
+
+        let sinc3 = adc.read_filter_config(Setup::Setup2).unwrap();
+        assert!(matches!(sinc3, FilterConfig::DirectSinc3Map(register) if register.to_buffer() == [0x80, 0x20]));
+        let default = adc.read_filter_config(Setup::Setup1).unwrap();
+        assert!(matches!(default, FilterConfig::Default(register) if register.to_buffer() == [0x05, 0x0a]));
+
+        adc.spi_mut().clear_written();
+        adc.write_filter_config(Setup::Setup3, &sinc3).unwrap();
+        adc.write_filter_config(Setup::Setup0, &default).unwrap();
+        assert_eq!(adc.spi_mut().written(), &[0x2b, 0x80, 0x20, 0x28, 0x05, 0x0a]);
+    }
+
+    #[test]
+    fn adc_read_signed_uses_coding_of_sample_setup() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&SetupConfig1Register::new().with_bi_unipolar(OutputCoding::Unipolar)).unwrap();
+        adc.write(&Channel2Register::new().with_ch_en(true).with_setup_sel(Setup::Setup1)).unwrap();
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+        assert_eq!(adc.output_coding(Setup::Setup1), OutputCoding::Unipolar);
+        assert_eq!(adc.output_coding(Setup::Setup0), OutputCoding::Bipolar);
+
+        // channel 2 on unipolar setup 1
+        adc.spi_mut().queue_response(&[0x00, 0x00, 0x00, 0x10, 0x02]);
+        assert_eq!(adc.read_signed().unwrap(), 0x10);
+
+        // channel 0 still on bipolar setup 0
+        adc.spi_mut().queue_response(&[0x00, 0x7f, 0xff, 0xff, 0x00]);
+        assert_eq!(adc.read_signed().unwrap(), -1);
+    }
+
+    #[test]
+    fn adc_verify_registers_compares_register_check() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        assert!(matches!(adc.verify_registers(), Err(AdcError::RegisterCheckDisabled)));
+
+        adc.spi_mut().queue_response(&[0x00, 0x00, 0x00]);
+        adc.spi_mut().queue_response(&[0x00, 0x12, 0x34, 0x56]);
+        adc.enable_register_check().unwrap();
+        // REG_CHECK cleared and set again
+        assert_eq!(&adc.spi_mut().written()[3..9], &[0x02, 0x00, 0x00, 0x02, 0x00, 0x20]);
+
+        adc.spi_mut().queue_response(&[0x00, 0x80, 0x00, 0x12, 0x34, 0x56]);
+        assert!(adc.verify_registers().unwrap());
+        adc.spi_mut().queue_response(&[0x00, 0x80, 0x00, 0x12, 0x34, 0x57]);
+        assert!(!adc.verify_registers().unwrap());
+        // REG_ERROR set
+        adc.spi_mut().queue_response(&[0x00, 0x90]);
+        assert!(!adc.verify_registers().unwrap());
+
+        // a write through the driver drops the baseline
+        adc.write(&AdcModeRegister::new()).unwrap();
+        assert!(matches!(adc.verify_registers(), Err(AdcError::RegisterCheckDisabled)));
+    }
+
+    #[test]
+    fn adc_read_data_checked_reports_reference_monitor() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.spi_mut().queue_response(&[0x00, 0x08, 0x00]);
+        adc.enable_reference_monitor().unwrap();
+        // SYNC off, SYNC/ERROR as error input
+        assert_eq!(&adc.spi_mut().written()[3..], &[0x06, 0x02, 0x00]);
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+
+        // ADC error on channel 1, which uses setup 2 on the external reference, ERROR held low
+        adc.spi_mut().queue_response(&[0x00, 0xff, 0xff, 0xff, 0x41]);
+        adc.spi_mut().queue_response(&[0x00, 0xa0, 0x01]);
+        adc.spi_mut().queue_response(&[0x00, 0x13, 0x00]);
+        adc.spi_mut().queue_response(&[0x00, 0x02, 0x00]);
+        assert!(matches!(adc.read_data_checked(), Err(AdcError::ReferenceOutOfRange)));
+
+        // the same on the internal reference is an ordinary ADC error
+        adc.spi_mut().queue_response(&[0x00, 0xff, 0xff, 0xff, 0x41]);
+        adc.spi_mut().queue_response(&[0x00, 0xa0, 0x01]);
+        adc.spi_mut().queue_response(&[0x00, 0x13, 0x20]);
+        assert!(matches!(adc.read_data_checked(), Err(AdcError::AdcFault)));
+    }
+
+    #[test]
+    fn adc_set_single_cycle_keeps_mode() {
+        let mut spi = MockSpiBus::new();
+        // internal reference on, single conversion mode
+        spi.queue_response(&[0x00, 0x80, 0x10]);
+        let mut adc = ADC::new(spi);
+
+        adc.set_single_cycle(true).unwrap();
+        assert_eq!(adc.spi_mut().written(), &[0x41, 0x00, 0x00, 0x01, 0xa0, 0x10]);
+    }
+
+    #[test]
+    fn adc_wait_ready_returns_ready_status() {
+        let mut spi = MockSpiBus::new();
+        // not ready twice, then ready on channel 2 with the ADC error flag set
+        spi.queue_response(&[0x00, 0x80, 0x00, 0x80, 0x00, 0x42]);
+        let mut adc = ADC::new(spi);
+
+        let status = adc.wait_ready(3).unwrap();
+        assert!(status.ready() && status.adc_error());
+        assert_eq!(status.channel(), Channel::Ch2);
+        assert_eq!(adc.spi_mut().written(), &[0x40, 0x00, 0x40, 0x00, 0x40, 0x00]);
+    }
+
+    #[test]
+    fn adc_current_channel_reads_status() {
+        let mut spi = MockSpiBus::new();
+        // not ready yet, last conversion on channel 3
+        spi.queue_response(&[0x00, 0x83]);
+        let mut adc = ADC::new(spi);
+
+        assert_eq!(adc.current_channel().unwrap(), Channel::Ch3);
+        assert_eq!(adc.spi_mut().written(), &[0x40, 0x00]);
+    }
+
+    #[test]
+    fn adc_wait_ready_gives_up_after_max_polls() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x80, 0x00, 0x80, 0x00, 0x00]);
+        let mut adc = ADC::new(spi);
+
+        assert!(matches!(adc.wait_ready(2), Err(AdcError::Timeout)));
+        assert_eq!(adc.spi_mut().written().len(), 4);
+    }
+
+    #[test]
+    fn adc_read_averaged_keeps_bipolar_sign() {
+        let mut spi = MockSpiBus::new();
+        // bipolar setup, then the mode register
+        spi.queue_response(&[0x00, 0x10, 0x00]);
+        spi.queue_response(&[0x00, 0x80, 0x00]);
+        // each conversion is a ready status then the data: -3, -6 and 0
+        spi.queue_response(&[0x00, 0x00, 0x00, 0x7f, 0xff, 0xfd]);
+        spi.queue_response(&[0x00, 0x00, 0x00, 0x7f, 0xff, 0xfa]);
+        spi.queue_response(&[0x00, 0x00, 0x00, 0x80, 0x00, 0x00]);
+        let mut adc = ADC::new(spi);
+
+        assert_eq!(adc.read_averaged(Input::Analog0, Input::Analog1, Setup::Setup2, NonZeroU16::new(3).unwrap()).unwrap(), -3);
+        assert_eq!(adc.spi_mut().written()[0], 0x62);
+    }
+
+    #[test]
+    fn adc_read_averaged_unipolar() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x00, 0x00]);
+        spi.queue_response(&[0x00, 0x80, 0x00]);
+        for _ in 0..4 {
+            spi.queue_response(&[0x00, 0x00, 0x00, 0x12, 0x34, 0x56]);
+        }
+        let mut adc = ADC::new(spi);
+
+        assert_eq!(adc.read_averaged(Input::Analog0, Input::Analog1, Setup::Setup0, NonZeroU16::new(4).unwrap()).unwrap(), 0x123456);
+    }
+
+    #[test]
+    fn adc_calibration_round_trip() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x80, 0x01, 0x23]);
+        spi.queue_response(&[0x00, 0x55, 0x67, 0x89]);
+        let mut adc = ADC::new(spi);
+
+        let calibration = adc.read_calibration(Setup::Setup2).unwrap();
+        assert_eq!(calibration, Calibration { offset: 0x800123, gain: 0x556789 });
+
+        adc.spi_mut().clear_written();
+        adc.write_calibration(Setup::Setup2, &calibration).unwrap();
+        assert_eq!(adc.spi_mut().written(), &[0x32, 0x80, 0x01, 0x23, 0x3a, 0x55, 0x67, 0x89]);
+    }
+
+    #[test]
+    fn adc_dump_config_reads_every_register() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x80, 0x00]);
+        spi.queue_response(&[0x00, 0x00, 0x40]);
+        spi.queue_response(&[0x00, 0x80, 0x01]);
+        for _ in 0..3 {
+            spi.queue_response(&[0x00, 0x00, 0x01]);
+        }
+        for _ in 0..4 {
+            spi.queue_response(&[0x00, 0x10, 0x20]);
+        }
+        spi.queue_response(&[0x00, 0x80, 0x10]);
+        for _ in 0..3 {
+            spi.queue_response(&[0x00, 0x05, 0x00]);
+        }
+        let mut adc = ADC::new(spi);
+
+        let snapshot = adc.dump_config().unwrap();
+
+        assert!(snapshot.interface_mode.data_stat());
+        assert!(snapshot.channel0.ch_en());
+        assert!(!snapshot.channel3.ch_en());
+        assert!(matches!(snapshot.filter_config0, FilterConfig::DirectSinc3Map(filter) if filter.decimation_rate() == 0x10));
+        assert!(matches!(snapshot.filter_config3, FilterConfig::Default(filter) if filter.enhfilt() == EnhancedFilterRate::Sps20));
+
+        let written = adc.spi_mut().written();
+        let commands = [0x41, 0x42, 0x50, 0x51, 0x52, 0x53, 0x60, 0x61, 0x62, 0x63, 0x68, 0x69, 0x6a, 0x6b];
+        assert_eq!(written.len(), commands.len() * 3);
+        for (frame, command) in written.chunks(3).zip(commands) {
+            assert_eq!(frame, &[command, 0x00, 0x00]);
+        }
+    }
+
+    #[test]
+    fn adc_apply_config_writes_mode_last() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.apply_config(&AdcConfig::default()).unwrap();
+
+        let written = adc.spi_mut().written();
+        let commands = [0x02, 0x06, 0x20, 0x21, 0x22, 0x23, 0x28, 0x29, 0x2a, 0x2b, 0x10, 0x11, 0x12, 0x13, 0x01];
+        assert_eq!(written.len(), commands.len() * 3);
+        for (frame, command) in written.chunks(3).zip(commands) {
+            assert_eq!(frame[0], command);
+        }
+        assert_eq!(&written[42..], &[0x01, 0x80, 0x00]);
+    }
+
+    #[test]
+    fn adc_apply_config_diff_writes_changed_registers() {
+        let current = AdcConfig::default();
+        let mut config = current;
+        config.channel1 = config.channel1.with_setup_sel(Setup::Setup1);
+        config.adc_mode = config.adc_mode.with_mode(Mode::SingleConversion);
+
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.apply_config_diff(&config, &current).unwrap();
+        let written = adc.spi_mut().written();
+        assert_eq!(written.len(), 6);
+        assert_eq!(&written[1..3], &config.channel1.to_buffer());
+        assert_eq!([written[0], written[3]], [0x11, 0x01]);
+        assert_eq!(&written[4..], &config.adc_mode.to_buffer());
+
+        adc.spi_mut().clear_written();
+        adc.apply_config_diff(&config, &config).unwrap();
+        assert!(adc.spi_mut().written().is_empty());
+    }
+
+    /// Current on channel 0 through a 25 A full scale shunt, voltage on channel 1 through a
+    /// 10:1 divider, both unipolar against a 2.5 V reference.
+    fn sensing(temperature_channel: Option<Channel>) -> Sensing {
+        Sensing {
+            current_channel: Channel::Ch0,
+            current_scaling: Scaling::new(2500.0, 0.01, 10.0),
+            voltage_channel: Channel::Ch1,
+            voltage_ratio: 10.0,
+            temperature_channel,
+            reference_mv: 2500.0,
+            coding: OutputCoding::Unipolar,
+        }
+    }
+
+    #[test]
+    fn sensing_measures_each_channel() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+        // status poll then data and status, for channel 1 then channel 0
+        adc.spi_mut().queue_response(&[0x00, 0x01, 0x00, 0x40, 0x00, 0x00, 0x01]);
+        adc.spi_mut().queue_response(&[0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00]);
+
+        let measurement = sensing(None).measure(&mut adc).unwrap();
+        assert_eq!(measurement, Measurement { voltage: 6.25, current: 12.5, temperature: f32::NEG_INFINITY });
+    }
+
+    #[test]
+    fn sensing_reads_temperature_as_bipolar() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+        // channel 1, channel 0, then 25 °C on channel 2
+        adc.spi_mut().queue_response(&[0x00, 0x01, 0x00, 0x40, 0x00, 0x00, 0x01]);
+        adc.spi_mut().queue_response(&[0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00]);
+        adc.spi_mut().queue_response(&[0x00, 0x02, 0x00, 0x87, 0x48, 0x13, 0x02]);
+
+        let measurement = sensing(Some(Channel::Ch2)).measure(&mut adc).unwrap();
+        assert_eq!((measurement.voltage, measurement.current), (6.25, 12.5));
+        assert!(measurement.temperature > 24.99 && measurement.temperature < 25.01);
+    }
+
+    #[test]
+    fn range_switch_moves_channel_and_scaling_together() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        let mut sensing = sensing(None);
+        let mut switch = RangeSwitch::new();
+        let low = Range {
+            setup: Setup::Setup1,
+            input_pos: Input::Analog2,
+            input_neg: Input::Analog3,
+            select_high: true,
+            scaling: Scaling::new(2500.0, 0.1, 10.0),
+        };
+
+        switch.set_range(&mut adc, &mut sensing, low).unwrap();
+        assert_eq!(adc.spi_mut().written(), &[0x10, 0x90, 0x43]);
+        assert_eq!(sensing.current_scaling, low.scaling);
+        assert_eq!(switch.range(), Some(low));
+
+        // a rejected channel configuration leaves the scaling alone
+        let invalid = Range { input_pos: Input::TemperatureSensorPos, scaling: Scaling::new(2500.0, 0.01, 10.0), ..low };
+        let result = switch.set_range(&mut adc, &mut sensing, invalid);
+        assert!(matches!(result, Err(AdcError::InvalidInputPair(_))));
+        assert_eq!(sensing.current_scaling, low.scaling);
+        assert_eq!(switch.range(), None);
+    }
+
+    #[test]
+    fn sensing_times_out_on_missing_channel() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+        for _ in 0..8 {
+            adc.spi_mut().queue_response(&[0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00]);
+        }
+
+        assert!(matches!(sensing(Some(Channel::Ch2)).measure(&mut adc), Err(AdcError::Timeout)));
+    }
+
+    #[test]
+    fn sequencer_discards_unsettled_conversions() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+        // channel 0 on setup 0, channel 1 on setup 1, channels 2 and 3 disabled
+        adc.spi_mut().queue_response(&[0x00, 0x80, 0x01]);
+        adc.spi_mut().queue_response(&[0x00, 0x90, 0x43]);
+        adc.spi_mut().queue_response(&[0x00, 0x00, 0x01]);
+        adc.spi_mut().queue_response(&[0x00, 0x00, 0x01]);
+        // status poll then data and status, for channel 0, channel 1 and channel 0 again
+        adc.spi_mut().queue_response(&[0x00, 0x00, 0x00, 0x11, 0x11, 0x11, 0x00]);
+        adc.spi_mut().queue_response(&[0x00, 0x01, 0x00, 0x22, 0x22, 0x22, 0x01]);
+        adc.spi_mut().queue_response(&[0x00, 0x00, 0x00, 0x33, 0x33, 0x33, 0x00]);
+
+        let mut sequencer = Sequencer::attach(&mut adc).with_settling(Setup::Setup0, Settling::Discard(1)).unwrap();
+        assert_eq!(sequencer.next_sample().unwrap(), (Channel::Ch1, 0x222222));
+        assert_eq!(sequencer.next_sample().unwrap(), (Channel::Ch0, 0x333333));
+    }
+
+    #[test]
+    fn sequencer_programs_longest_settling_delay() {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.spi_mut().queue_response(&[0x00, 0x80, 0x00]);
+        adc.spi_mut().queue_response(&[0x00, 0x83, 0x00]);
+
+        let _ = Sequencer::attach(&mut adc)
+            .with_settling(Setup::Setup0, Settling::Delay(ConversionDelay::FortyMicroseconds))
+            .unwrap()
+            .with_settling(Setup::Setup1, Settling::Delay(ConversionDelay::FourMicroseconds))
+            .unwrap();
+
+        // only the longer delay is written, the shorter one leaves the mode alone
+        assert_eq!(adc.spi_mut().written(), &[0x41, 0x00, 0x00, 0x01, 0x83, 0x00, 0x41, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn adc_set_gpio_preserves_other_pin() {
+        let mut spi = MockSpiBus::new();
+        // GPIO1 configured as a high output, SYNC enabled
+        spi.queue_response(&[0x00, 0x08, 0x0a]);
+        spi.queue_response(&[0x00, 0x08, 0x0a]);
+        let mut adc = ADC::new(spi);
+
+        adc.configure_gpio(0, true).unwrap();
+        adc.set_gpio(0, true).unwrap();
+
+        let written = adc.spi_mut().written();
+        assert_eq!(&written[3..6], &[0x06, 0x08, 0x0e]);
+        assert_eq!(&written[9..12], &[0x06, 0x08, 0x0b]);
+    }
+
+    #[test]
+    fn adc_gpio_rejects_invalid_pin() {
+        let mut adc = ADC::new(MockSpiBus::new());
+
+        assert!(matches!(adc.set_gpio(2, true), Err(AdcError::InvalidPin)));
+        assert!(matches!(adc.configure_gpio(2, true), Err(AdcError::InvalidPin)));
+    }
+
+    #[test]
+    fn setup_builder_writes_matching_setup() {
+        let mut adc = ADC::new(MockSpiBus::new());
+
+        SetupBuilder::<Setup1Registers>::new(Channel::Ch2, Input::Analog0, Input::Analog1)
+            .with_output_coding(OutputCoding::Unipolar)
+            .with_filter_order(FilterOrder::Sinc3)
+            .with_output_data_rate(OutputDataRate::Sps1000)
+            .apply(&mut adc)
+            .unwrap();
+
+        let written = adc.spi_mut().written();
+        assert_eq!(written.len(), 9);
+        assert_eq!([written[0], written[3], written[6]], [0x21, 0x29, 0x12]);
+
+        let setup_config = SetupConfig1Register::from_buffer(&[written[1], written[2]]);
+        assert_eq!(setup_config.bi_unipolar(), OutputCoding::Unipolar);
+        let filter_config = DefaultFilterConfig1Register::from_buffer(&[written[4], written[5]]);
+        assert_eq!(filter_config.order(), FilterOrder::Sinc3);
+        assert_eq!(filter_config.odr(), OutputDataRate::Sps1000);
+        let channel = Channel2Register::from_buffer(&[written[7], written[8]]);
+        assert!(channel.ch_en());
+        assert_eq!(channel.setup_sel(), Setup::Setup1);
+        assert_eq!(channel.ainpos(), Input::Analog0);
+        assert_eq!(channel.ainneg(), Input::Analog1);
+    }
+
+    #[test]
+    fn adc_check_open_input_restores_setup() {
+        let mut spi = MockSpiBus::new();
+        spi.queue_response(&[0x00, 0x10, 0x20]);
+        // two ready conversions of channel 1, the first one is discarded
+        spi.queue_response(&[0x00, 0x01, 0x00, 0x12, 0x34, 0x56]);
+        spi.queue_response(&[0x00, 0x01, 0x00, 0xff, 0xff, 0xf0]);
+        let mut adc = ADC::new(spi);
+
+        assert!(adc.check_open_input(Setup::Setup0, Channel::Ch1).unwrap());
+
+        let written = adc.spi_mut().written();
+        // burnout enabled, then the original configuration written back
+        assert_eq!(&written[3..6], &[0x20, 0x10, 0xa0]);
+        assert_eq!(&written[written.len() - 3..], &[0x20, 0x10, 0x20]);
+    }
+
+    /// ADC channel reading the DAC output directly, both on a 2.5 V reference.
+    fn dac_monitor() -> DacMonitor {
+        DacMonitor {
+            input_pos: Input::Analog0,
+            input_neg: Input::Analog1,
+            setup: Setup::Setup0,
+            adc_reference_mv: 2500.0,
+            coding: OutputCoding::Unipolar,
+            dac_reference_mv: 2500.0,
+            ratio: 1.0,
+            tolerance: 0.01,
+            settle_us: 0,
+        }
+    }
+
+    /// Queues the responses of one [`ADC::read_single`] converting to `code`.
+    fn queue_single_conversion(adc: &mut ADC<MockSpiBus>, code: u32) {
+        adc.spi_mut().queue_response(&[0x00, 0x80, 0x00]);
+        adc.spi_mut().queue_response(&[0x00, 0x00]);
+        adc.spi_mut().queue_response(&[0x00, (code >> 16) as u8, (code >> 8) as u8, code as u8]);
+    }
+
+    #[test]
+    fn selftest_passes_when_adc_follows_dac(peripherals: Peripherals) {
+        let ldac = Output::new(peripherals.GPIO9, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac).with_resolution(16);
+        let mut adc = ADC::new(MockSpiBus::new());
+        // 1 mV under each level
+        for level in [0.1, 0.3, 0.5, 0.7, 0.9] {
+            queue_single_conversion(&mut adc, ((level - 0.0004) * (1 << 24) as f32) as u32);
+        }
+
+        selftest(&mut adc, &mut dac, &dac_monitor(), &mut Delay::new()).unwrap();
+        let written = dac.spi_mut().written();
+        assert_eq!(&written[written.len() - 2..], &[0x00, 0x00]);
+    }
+
+    #[test]
+    fn selftest_catches_stuck_monitor(peripherals: Peripherals) {
+        let ldac = Output::new(peripherals.GPIO9, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac).with_resolution(16);
+        let mut adc = ADC::new(MockSpiBus::new());
+        // the first level reads right, the second reads the same
+        queue_single_conversion(&mut adc, 0x19_9999);
+        queue_single_conversion(&mut adc, 0x19_9999);
+
+        let result = selftest(&mut adc, &mut dac, &dac_monitor(), &mut Delay::new());
+        assert!(matches!(result, Err(SelfTestError::NotMonotonic { .. })));
+        let written = dac.spi_mut().written();
+        assert_eq!(&written[written.len() - 2..], &[0x00, 0x00]);
+    }
+
+    #[test]
+    fn dac_write_sends_left_justified_frame(peripherals: Peripherals) {
+        let ldac = Output::new(peripherals.GPIO9, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac);
+        dac.write(0x1234).unwrap();
+        assert_eq!(dac.spi_mut().written(), &[0x00, 0x00, 0x12, 0x34]);
+        assert_eq!(dac.spi_mut().flushed(), &[0x00, 0x00, 0x12, 0x34]);
+
+        let ldac = Output::new(peripherals.GPIO11, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac).with_resolution(16);
+        assert_eq!(dac.max_code(), 0xffff);
+        dac.write(0x8000).unwrap();
+        assert_eq!(dac.spi_mut().written(), &[0x80, 0x00]);
+        assert!(matches!(dac.write(0x10000), Err(DacError::OutOfRange)));
+        assert_eq!(dac.spi_mut().written(), &[0x80, 0x00]);
+
+        let ldac = Output::new(peripherals.GPIO10, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac).with_resolution(20);
+        dac.write(0xabcde).unwrap();
+        assert_eq!(dac.spi_mut().written(), &[0xab, 0xcd, 0xe0]);
+    }
+
+    #[test]
+    fn dac_read_back_resends_last_frame(peripherals: Peripherals) {
+        let ldac = Output::new(peripherals.GPIO9, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac).with_resolution(20);
+        dac.write(0xabcde).unwrap();
+        assert!(matches!(dac.read_back(), Err(DacError::ReadbackUnavailable)));
+
+        let mut dac = dac.with_readback();
+        dac.spi_mut().clear_written();
+        dac.spi_mut().queue_response(&[0xab, 0xcd, 0xe0]);
+        assert_eq!(dac.read_back().unwrap(), 0xabcde);
+        assert_eq!(dac.spi_mut().written(), &[0xab, 0xcd, 0xe0]);
+    }
+
+    #[test]
+    fn dac_group_loads_every_dac_before_updating(peripherals: Peripherals) {
+        let ldac = Output::new(peripherals.GPIO9, Level::High, OutputConfig::default());
+        let dacs = [DAC::new_without_ldac(MockSpiBus::new()).with_resolution(16), DAC::new_without_ldac(MockSpiBus::new()).with_resolution(20)];
+        let mut group = DacGroup::new(dacs, ldac);
+
+        group.write([0x1234, 0xabcde]).unwrap();
+        assert_eq!(group.dacs_mut()[0].spi_mut().written(), &[0x12, 0x34]);
+        assert_eq!(group.dacs_mut()[1].spi_mut().written(), &[0xab, 0xcd, 0xe0]);
+        assert_eq!(group.dacs_mut()[0].spi_mut().flushed(), &[0x12, 0x34]);
+        assert_eq!(group.dacs_mut()[1].spi_mut().flushed(), &[0xab, 0xcd, 0xe0]);
+
+        // 0x10000 doesn't fit the 16 bit DAC, so nothing is sent to either
+        assert!(matches!(group.write([0x10000, 0]), Err(DacError::OutOfRange)));
+        assert_eq!(group.dacs_mut()[1].spi_mut().written(), &[0xab, 0xcd, 0xe0]);
+
+        assert_eq!(group.write_voltages([1.25, 0.0], 2500.0).unwrap(), [0x8000, 0]);
+    }
+
+    #[test]
+    fn dac_rejects_writes_while_powered_down(peripherals: Peripherals) {
+        let ldac = Output::new(peripherals.GPIO9, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac).with_resolution(16).with_power_down_mode(PowerDownMode::Command { word: 0x01_0000, len: 3 });
+
+        dac.power_down().unwrap();
+        assert!(!dac.is_enabled());
+        assert!(matches!(dac.write(0x1234), Err(DacError::PoweredDown)));
+        assert_eq!(dac.spi_mut().written(), &[0x01, 0x00, 0x00]);
+
+        dac.enable();
+        dac.write(0x1234).unwrap();
+        assert_eq!(dac.spi_mut().written(), &[0x01, 0x00, 0x00, 0x12, 0x34]);
+
+        // parts without a power-down mode are parked at a code instead
+        let ldac = Output::new(peripherals.GPIO10, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac).with_resolution(16).with_power_down_mode(PowerDownMode::Code(0x8000));
+        dac.power_down().unwrap();
+        assert_eq!(dac.spi_mut().written(), &[0x80, 0x00]);
+        assert_eq!(dac.spi_mut().flushed(), &[0x80, 0x00]);
+    }
+
+    #[test]
+    fn autotune_stops_on_protection_trip(peripherals: Peripherals) {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+        // 6.25 V and 12.5 A, over the 5 A limit straight away
+        adc.spi_mut().queue_response(&[0x00, 0x01, 0x00, 0x40, 0x00, 0x00, 0x01]);
+        adc.spi_mut().queue_response(&[0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00]);
+        let ldac = Output::new(peripherals.GPIO9, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac).with_resolution(16);
+        let mut protection = Protection::new(5.0, 30.0, 80.0);
+        let mut controller = LoadController::new(Pid::new(1.0, 0.0, 0.0), 0xffff, LoadMode::ConstantCurrent(2.0));
+        let tuner = RelayTuner::new(0, 0x4000, Duration::from_millis(1));
+
+        let result = controller.autotune(tuner, &mut adc, &mut dac, &sensing(None), &mut protection, &mut Delay::new());
+        assert!(matches!(result, Err(AutotuneError::Fault(FaultKind::OverCurrent))));
+        // the relay never switched on, the load was only ever written off
+        assert_eq!(dac.spi_mut().written(), &[0x00, 0x00]);
+        assert_eq!(controller.gains(), PidGains { kp: 1.0, ki: 0.0, kd: 0.0 });
+    }
+
+    #[test]
+    fn control_cycle_logs_protection_trip(peripherals: Peripherals) {
+        let mut adc = ADC::new(MockSpiBus::new());
+        adc.write(&InterfaceModeRegister::new().with_data_stat(true)).unwrap();
+        let ldac = Output::new(peripherals.GPIO9, Level::High, OutputConfig::default());
+        let mut dac = DAC::new(MockSpiBus::new(), ldac).with_resolution(16);
+        let mut protection = Protection::new(5.0, 30.0, 80.0);
+        let mut controller = LoadController::new(Pid::new(1.0, 0.0, 0.0), 0xffff, LoadMode::ConstantCurrent(2.0));
+        let events = Mutex::<CriticalSectionRawMutex, _>::new(RefCell::new(EventLog::<4>::new()));
+
+        // 6.25 V and 12.5 A, over the 5 A limit, on two cycles in a row
+        for _ in 0..2 {
+            adc.spi_mut().queue_response(&[0x00, 0x01, 0x00, 0x40, 0x00, 0x00, 0x01]);
+            adc.spi_mut().queue_response(&[0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00]);
+            control_cycle(&mut adc, &mut dac, &mut controller, &mut protection, &sensing(None), 0.001, &events);
+        }
+
+        assert_eq!(dac.spi_mut().written(), &[0x00, 0x00, 0x00, 0x00]);
+        // only the cycle that latched the fault records it
+        let event = events.lock(|events| events.borrow_mut().pop());
+        assert_eq!(event.map(|event| event.kind), Some(EventKind::Fault(FaultKind::OverCurrent)));
+        assert!(events.lock(|events| events.borrow().is_empty()));
+    }
+}